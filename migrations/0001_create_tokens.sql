@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS tokens (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    icon TEXT,
+    decimals INTEGER NOT NULL
+);
@@ -0,0 +1,3 @@
+ALTER TABLE tokens ADD COLUMN token_program TEXT;
+ALTER TABLE tokens ADD COLUMN transfer_fee_basis_points INTEGER;
+ALTER TABLE tokens ADD COLUMN transfer_fee_maximum INTEGER;
@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS portfolio_snapshots (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    wallet TEXT NOT NULL,
+    taken_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS portfolio_snapshots_wallet_taken_at
+    ON portfolio_snapshots (wallet, taken_at);
+CREATE TABLE IF NOT EXISTS portfolio_snapshot_holdings (
+    snapshot_id INTEGER NOT NULL REFERENCES portfolio_snapshots (id),
+    mint TEXT NOT NULL,
+    name TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    raw_balance TEXT NOT NULL,
+    decimals INTEGER NOT NULL,
+    usd_price REAL,
+    usd_value REAL,
+    PRIMARY KEY (snapshot_id, mint)
+);
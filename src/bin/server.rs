@@ -1,11 +1,21 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
+use clap::Parser;
 use sol_trace::server::{
+    auth::ApiKeyInterceptor,
+    config::{ServerArgs, ServerConfig, StoreBackend},
     domain::WebSocketClient,
     run_server,
-    services::{HashmapTokenStore, JupiterRpcClient, SolanaRpcClient, SolanaWebSocketClient},
-    states::AppState,
-    utils::{constants::SOLANA_WS_URL, init_tracing},
+    services::{
+        EndpointPool, HashmapSnapshotStore, HashmapTokenStore, JupiterRpcClient, SolanaRpcClient,
+        SolanaWebSocketClient, SqliteSnapshotStore, SqliteTokenStore, WebSocketSettings,
+        watch_ws_health,
+    },
+    states::{
+        AppState,
+        app_state::{SnapshotStoreType, TokenStoreType},
+    },
+    utils::init_tracing,
 };
 use tokio::sync::RwLock;
 
@@ -14,26 +24,75 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     color_eyre::install().expect("Failed to install color_eyre");
     init_tracing().expect("Failed to initialize tracing");
 
-    let addr = "127.0.0.1:50051";
+    let args = ServerArgs::parse();
+    let (config, tls) = match ServerConfig::build(&args)
+        .and_then(|config| config.tls.load().map(|tls| (config, tls)))
+    {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
 
-    let token_store = Arc::new(RwLock::new(HashmapTokenStore::default()));
+    let sqlite = config.store.backend() == StoreBackend::Sqlite;
+    let token_store: TokenStoreType = match config.store.token_path().filter(|_| sqlite) {
+        Some(path) => {
+            tracing::info!("Using sqlite token store: {}", path);
+            Arc::new(RwLock::new(SqliteTokenStore::open(path)?))
+        }
+        None => Arc::new(RwLock::new(HashmapTokenStore::default())),
+    };
+    let snapshot_store: SnapshotStoreType = match config.store.snapshot_path().filter(|_| sqlite) {
+        Some(path) => {
+            tracing::info!("Using sqlite snapshot store: {}", path);
+            Arc::new(RwLock::new(SqliteSnapshotStore::open(path)?))
+        }
+        None => Arc::new(RwLock::new(HashmapSnapshotStore::default())),
+    };
 
-    let client = reqwest::Client::new();
-    let off_chain_rpc_client = Arc::new(JupiterRpcClient::build(client));
+    // without the timeouts a hung provider would never fail over
+    let http_client = || {
+        reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(config.endpoints.connect_timeout_secs))
+            .timeout(Duration::from_secs(config.endpoints.request_timeout_secs))
+            .build()
+    };
 
-    let client2 = reqwest::Client::new();
-    let on_chain_rpc_client = Arc::new(SolanaRpcClient::build(client2));
+    let client = http_client()?;
+    let off_chain_rpc_client = Arc::new(JupiterRpcClient::build(client, &config.endpoints));
 
-    //let ws_url = "wss://api.mainnet-beta.solana.com/".to_string();
+    let client2 = http_client()?;
+    let rpc_pool = Arc::new(EndpointPool::new(config.endpoints.rpc.clone()));
+    let on_chain_rpc_client =
+        Arc::new(SolanaRpcClient::build(client2, rpc_pool, config.commitment));
+    tokio::spawn(on_chain_rpc_client.clone().watch_health());
+
+    // the pool is shared by the connections of every client
+    let ws_pool = Arc::new(EndpointPool::new(config.endpoints.ws.clone()));
+    let ws_settings = WebSocketSettings::from(&config);
+    tokio::spawn(watch_ws_health(
+        ws_pool.clone(),
+        ws_settings.connect_timeout,
+    ));
     let ws_client_factory: Arc<dyn Fn() -> Box<dyn WebSocketClient + Send + Sync> + Send + Sync> =
-        Arc::new(move || Box::new(SolanaWebSocketClient::new(&SOLANA_WS_URL)));
+        Arc::new(move || Box::new(SolanaWebSocketClient::new(ws_pool.clone(), ws_settings)));
 
     let state = AppState::new(
         token_store,
+        snapshot_store,
         off_chain_rpc_client,
         on_chain_rpc_client,
         ws_client_factory,
     );
 
-    run_server(addr, state).await
+    run_server(
+        config.listen_addr(),
+        state,
+        config.channels,
+        config.sessions,
+        ApiKeyInterceptor::new(&config.auth),
+        tls,
+    )
+    .await
 }
@@ -1,7 +1,10 @@
 use crate::{
-    client::{AppState, Config, Panel, SharedState, scroll_down, scroll_up, ui},
+    client::{ApiKeyAuth, AppState, Config, Panel, SharedState, scroll_down, scroll_up, ui},
     proto::{
-        GetTradeRequest, HoldingsRequest, InitRequest, SubscribeRequest, UnsubscribeRequest,
+        AddTokensRequest, AddWalletsRequest, DeleteAlertRequest, GetPortfolioHistoryRequest,
+        GetPositionsRequest, GetTradeHistoryRequest, GetTradeRequest, HeartbeatRequest,
+        HoldingsRequest, InitRequest, ListAlertsRequest, RemoveTokensRequest, RemoveWalletsRequest,
+        SetAlertRequest, StreamFilter, SubscribeRequest, UnsubscribeRequest,
         cli_service_client::CliServiceClient,
     },
 };
@@ -13,7 +16,11 @@ use tokio::{
     time::{Duration, sleep},
 };
 use tokio_util::sync::CancellationToken;
-use tonic::{Request, metadata::MetadataValue};
+use tonic::{
+    Request,
+    metadata::MetadataValue,
+    transport::{Certificate, Channel, ClientTlsConfig, Identity},
+};
 use uuid::Uuid;
 
 use crossterm::{
@@ -24,6 +31,9 @@ use crossterm::{
 };
 use ratatui::{Terminal, backend::CrosstermBackend};
 
+// until the first heartbeat tells the server's session ttl
+const HEARTBEAT_DEFAULT_SECS: u64 = 60;
+
 #[derive(Parser)]
 #[command(name = "Sol-trace client")]
 #[command(about = "Solana wallet tracker client with REPL", long_about = None)]
@@ -33,6 +43,54 @@ pub struct CliArgs {
 
     #[arg(long, short)]
     pub config: String,
+
+    // SOL_TRACE_API_KEY if not given
+    #[arg(long)]
+    pub api_key: Option<String>,
+
+    // the PEM CA of the server certificate, enables TLS for an https:// address
+    #[arg(long)]
+    pub tls_ca: Option<String>,
+
+    // the PEM client certificate of a server requiring mutual TLS
+    #[arg(long, requires = "tls_key")]
+    pub tls_cert: Option<String>,
+
+    // the PEM private key of the client certificate
+    #[arg(long, requires = "tls_cert")]
+    pub tls_key: Option<String>,
+
+    // the name verified against the server certificate, the host of the address by default
+    #[arg(long)]
+    pub tls_domain: Option<String>,
+}
+
+impl CliArgs {
+    fn tls_config(&self) -> Result<Option<ClientTlsConfig>, Box<dyn std::error::Error>> {
+        let Some(tls_ca) = &self.tls_ca else {
+            if self.tls_cert.is_some() {
+                return Err("--tls-cert needs the server CA of --tls-ca".into());
+            }
+            return Ok(None);
+        };
+        if !self.addr.starts_with("https://") {
+            return Err(format!("TLS needs an https:// address, got {}", self.addr).into());
+        }
+
+        let read = |path: &String| {
+            std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))
+        };
+
+        let mut tls = ClientTlsConfig::new().ca_certificate(Certificate::from_pem(read(tls_ca)?));
+        if let (Some(cert), Some(key)) = (&self.tls_cert, &self.tls_key) {
+            tls = tls.identity(Identity::from_pem(read(cert)?, read(key)?));
+        }
+        if let Some(domain) = &self.tls_domain {
+            tls = tls.domain_name(domain);
+        }
+
+        Ok(Some(tls))
+    }
 }
 
 enum ClientEvent {
@@ -48,15 +106,30 @@ enum ClientEvent {
 }
 
 pub async fn run_cli_client(cli: CliArgs) -> Result<(), Box<dyn std::error::Error>> {
-    let mut client = CliServiceClient::connect(cli.addr.clone()).await?;
-    let init_request = InitRequest::build(Config::load(cli.config.as_str())?)?;
+    let api_key = cli
+        .api_key
+        .clone()
+        .or_else(|| std::env::var("SOL_TRACE_API_KEY").ok());
+    // checked before connecting, a key that is not a valid header value fails at startup
+    let auth = ApiKeyAuth::new(api_key.as_deref())
+        .map_err(|_| "Invalid API key, only visible ASCII characters are allowed")?;
+    let mut endpoint = Channel::from_shared(cli.addr.clone())?;
+    if let Some(tls) = cli.tls_config()? {
+        endpoint = endpoint.tls_config(tls)?;
+    }
+    let channel = endpoint.connect().await?;
+    let mut client = CliServiceClient::with_interceptor(channel, auth);
+    let config = Config::load(cli.config.as_str())?;
+    let stream_filter = config
+        .stream_filter
+        .as_ref()
+        .map(StreamFilter::build)
+        .transpose()?;
+    let init_request = InitRequest::build(config)?;
 
     let client_id = match client.init(Request::new(init_request)).await {
-        Ok(response) => {
-            let client_id = Uuid::parse_str(response.into_inner().client_id.as_str())
-                .map_err(|e| format!("Failed to parse client_id: {}", e))?;
-            client_id
-        }
+        Ok(response) => Uuid::parse_str(response.into_inner().client_id.as_str())
+            .map_err(|e| format!("Failed to parse client_id: {}", e))?,
         Err(e) => return Err(format!("Error: {:?}", e).into()),
     };
 
@@ -70,6 +143,44 @@ pub async fn run_cli_client(cli: CliArgs) -> Result<(), Box<dyn std::error::Erro
         )))
         .await;
 
+    // keeps the session alive while the REPL is idle, at a third of the server's ttl
+    let mut heartbeat_client = client.clone();
+    let tx_heartbeat = tx.clone();
+    let heartbeat_client_id = MetadataValue::try_from(client_id.to_string())?;
+    tokio::spawn(async move {
+        let mut period = Duration::from_secs(HEARTBEAT_DEFAULT_SECS);
+        loop {
+            let mut heartbeat_request = Request::new(HeartbeatRequest {});
+            heartbeat_request
+                .metadata_mut()
+                .insert("client-id", heartbeat_client_id.clone());
+
+            match heartbeat_client.heartbeat(heartbeat_request).await {
+                Ok(response) => {
+                    period = Duration::from_secs((response.into_inner().ttl_secs / 3).max(1));
+                }
+                Err(e) => {
+                    let _ = tx_heartbeat
+                        .send(ClientEvent::Log(format!(
+                            "Heartbeat failed: {}",
+                            e.message()
+                        )))
+                        .await;
+                    if e.code() == tonic::Code::NotFound {
+                        let _ = tx_heartbeat
+                            .send(ClientEvent::Log(
+                                "Session expired, restart the client".to_string(),
+                            ))
+                            .await;
+                        break;
+                    }
+                }
+            }
+
+            sleep(period).await;
+        }
+    });
+
     let shared_state = Arc::new(Mutex::new(SharedState {
         current_cancel: None,
     }));
@@ -90,10 +201,10 @@ pub async fn run_cli_client(cli: CliArgs) -> Result<(), Box<dyn std::error::Erro
             tokio::select! {
                             _ = cancel_repl_task.cancelled() => break,
                             _ = async {
-                                if event::poll(Duration::from_millis(50)).unwrap() {
-                                    if let Ok(event) = event::read() {
-                                        if let Event::Key(key) = event {
-                                            if key.kind == KeyEventKind::Press {
+                                if event::poll(Duration::from_millis(50)).unwrap()
+                                    && let Ok(event) = event::read()
+                                        && let Event::Key(key) = event
+                                            && key.kind == KeyEventKind::Press {
                                             //tx_repl.send(ClientEvent::Log(format!("Error: {:?}", key))).await.unwrap();
                                                 match key.code {
                                                     KeyCode::Char(c)  => { tx_repl.send(ClientEvent::InputChar(c)).await.unwrap()}
@@ -105,9 +216,6 @@ pub async fn run_cli_client(cli: CliArgs) -> Result<(), Box<dyn std::error::Erro
                                                     _ => {}
                                                 }
                                             }
-                                        }
-                                    }
-                                }
                             } => {}
             }
         }
@@ -160,12 +268,22 @@ pub async fn run_cli_client(cli: CliArgs) -> Result<(), Box<dyn std::error::Erro
                         //state.history_list.push(line.clone());
                         let tx_log = tx.clone();
                         const TX_PREFIX: &str = "tx ";
+                        const HIST_CMD: &str = "hist";
+                        const HIST_PREFIX: &str = "hist ";
+                        const ADD_WALLETS_PREFIX: &str = "addw ";
+                        const REMOVE_WALLETS_PREFIX: &str = "rmw ";
+                        const ADD_TOKENS_PREFIX: &str = "addt ";
+                        const REMOVE_TOKENS_PREFIX: &str = "rmt ";
+                        const PORTFOLIO_HIST_CMD: &str = "phist";
+                        const PORTFOLIO_HIST_PREFIX: &str = "phist ";
+                        const ALERT_PREFIX: &str = "alert ";
+                        const REMOVE_ALERT_PREFIX: &str = "rmalert ";
                         if line.as_str().starts_with(TX_PREFIX) {
                             let _ = tx_log.send(ClientEvent::Log("Tx request has been sent".to_string())).await;
                             let signature = &line.as_str()[TX_PREFIX.len()..];
                             let mut client_clone = client.clone();
 
-                            let mut get_tx_request = Request::new(GetTradeRequest {signature: signature.to_string()});
+                            let mut get_tx_request = Request::new(GetTradeRequest {signature: signature.to_string(), wallet: None});
                             get_tx_request.metadata_mut().insert(
                                 "client-id",
                                 MetadataValue::try_from(client_id.clone().to_string())?,
@@ -173,14 +291,205 @@ pub async fn run_cli_client(cli: CliArgs) -> Result<(), Box<dyn std::error::Erro
 
                             match client_clone.get_trade(get_tx_request).await {
                                 Ok(resp ) => {
-                                    match resp.into_inner().trade {
-                                        Some(trade) => {
+                                    let resp = resp.into_inner();
+                                    match (resp.trade, resp.activity) {
+                                        (Some(trade), _) => {
                                             state.history_list.push(format!("*Check tx: {}", signature));
                                             for item in trade.to_string_lines().into_iter() {
                                                     state.history_list.push(item);
                                                 }
                                         }
-                                        None => state.history_list.push("*No trade detected.".to_string())
+                                        (None, Some(activity)) => {
+                                            state.history_list.push(format!("*Check tx: {}", signature));
+                                            for item in activity.to_string_lines().into_iter() {
+                                                state.history_list.push(item);
+                                            }
+                                        }
+                                        (None, None) => state.history_list.push("*No trade detected.".to_string())
+                                    }
+                                    state.history_list.push("".to_string());
+                                },
+                                Err(e) => tx_log.send(ClientEvent::Log(format!("Error: {e}"))).await?,
+                            }
+                        }
+                        else if line.as_str().starts_with(ADD_WALLETS_PREFIX) || line.as_str().starts_with(REMOVE_WALLETS_PREFIX) {
+                            let mut client_clone = client.clone();
+
+                            let result = if let Some(wallets) = line.as_str().strip_prefix(ADD_WALLETS_PREFIX) {
+                                let wallets = wallets.split_whitespace().map(|w| w.to_string()).collect();
+                                let mut add_request = Request::new(AddWalletsRequest { wallets });
+                                add_request.metadata_mut().insert(
+                                    "client-id",
+                                    MetadataValue::try_from(client_id.to_string())?,
+                                );
+                                client_clone.add_wallets(add_request).await
+                            } else {
+                                let wallets = line.as_str()[REMOVE_WALLETS_PREFIX.len()..].split_whitespace().map(|w| w.to_string()).collect();
+                                let mut remove_request = Request::new(RemoveWalletsRequest { wallets });
+                                remove_request.metadata_mut().insert(
+                                    "client-id",
+                                    MetadataValue::try_from(client_id.to_string())?,
+                                );
+                                client_clone.remove_wallets(remove_request).await
+                            };
+
+                            match result {
+                                Ok(resp) => tx_log.send(ClientEvent::Log(format!("Wallets: {}", resp.into_inner().wallets.join(", ")))).await?,
+                                Err(e) => tx_log.send(ClientEvent::Log(format!("Error: {e}"))).await?,
+                            }
+                        }
+                        else if line.as_str().starts_with(ADD_TOKENS_PREFIX) || line.as_str().starts_with(REMOVE_TOKENS_PREFIX) {
+                            let mut client_clone = client.clone();
+
+                            let result = if let Some(tokens) = line.as_str().strip_prefix(ADD_TOKENS_PREFIX) {
+                                let tokens = tokens.split_whitespace().map(|t| t.to_string()).collect();
+                                let mut add_request = Request::new(AddTokensRequest { tokens });
+                                add_request.metadata_mut().insert(
+                                    "client-id",
+                                    MetadataValue::try_from(client_id.to_string())?,
+                                );
+                                client_clone.add_tokens(add_request).await
+                            } else {
+                                let tokens = line.as_str()[REMOVE_TOKENS_PREFIX.len()..].split_whitespace().map(|t| t.to_string()).collect();
+                                let mut remove_request = Request::new(RemoveTokensRequest { tokens });
+                                remove_request.metadata_mut().insert(
+                                    "client-id",
+                                    MetadataValue::try_from(client_id.to_string())?,
+                                );
+                                client_clone.remove_tokens(remove_request).await
+                            };
+
+                            match result {
+                                Ok(resp) => tx_log.send(ClientEvent::Log(format!("Tokens: {}", resp.into_inner().tokens.join(", ")))).await?,
+                                Err(e) => tx_log.send(ClientEvent::Log(format!("Error: {e}"))).await?,
+                            }
+                        }
+                        else if line.as_str().starts_with(ALERT_PREFIX) || line.as_str().starts_with(REMOVE_ALERT_PREFIX) {
+                            let mut client_clone = client.clone();
+
+                            let result = if let Some(args) = line.as_str().strip_prefix(ALERT_PREFIX) {
+                                let set_alert_request = match SetAlertRequest::parse(args) {
+                                    Ok(set_alert_request) => set_alert_request,
+                                    Err(e) => {
+                                        let _ = tx_log.send(ClientEvent::Log(e)).await;
+                                        continue;
+                                    }
+                                };
+                                let mut set_alert_request = Request::new(set_alert_request);
+                                set_alert_request.metadata_mut().insert(
+                                    "client-id",
+                                    MetadataValue::try_from(client_id.to_string())?,
+                                );
+                                client_clone.set_alert(set_alert_request).await.map(|resp| resp.into_inner().alert.into_iter().collect::<Vec<_>>())
+                            } else {
+                                let Ok(id) = line.as_str()[REMOVE_ALERT_PREFIX.len()..].trim().parse::<u64>() else {
+                                    let _ = tx_log.send(ClientEvent::Log("Invalid alert id. Use: rmalert [id]".to_string())).await;
+                                    continue;
+                                };
+                                let mut delete_alert_request = Request::new(DeleteAlertRequest { id });
+                                delete_alert_request.metadata_mut().insert(
+                                    "client-id",
+                                    MetadataValue::try_from(client_id.to_string())?,
+                                );
+                                client_clone.delete_alert(delete_alert_request).await.map(|resp| resp.into_inner().alerts)
+                            };
+
+                            match result {
+                                Ok(alerts) => tx_log.send(ClientEvent::Log(format!("Alerts: {}", alerts.iter().map(|alert| alert.to_string()).collect::<Vec<_>>().join(", ")))).await?,
+                                Err(e) => tx_log.send(ClientEvent::Log(format!("Error: {e}"))).await?,
+                            }
+                        }
+                        else if line.as_str() == PORTFOLIO_HIST_CMD || line.as_str().starts_with(PORTFOLIO_HIST_PREFIX) {
+                            let hours = match line.as_str().strip_prefix(PORTFOLIO_HIST_PREFIX).map(|h| h.trim().parse::<i64>()) {
+                                Some(Ok(hours)) if hours > 0 => Some(hours),
+                                Some(_) => {
+                                    let _ = tx_log.send(ClientEvent::Log("Invalid hours. Use: phist [hours]".to_string())).await;
+                                    continue;
+                                }
+                                None => None,
+                            };
+
+                            let mut client_clone = client.clone();
+
+                            // hourly points of the last day by default
+                            let from = hours.map(|hours| {
+                                let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0);
+                                now - hours * 3_600
+                            });
+                            let mut history_request = Request::new(GetPortfolioHistoryRequest { from, to: None, resolution: 0, wallet: None });
+                            history_request.metadata_mut().insert(
+                                "client-id",
+                                MetadataValue::try_from(client_id.to_string())?,
+                            );
+
+                            match client_clone.get_portfolio_history(history_request).await {
+                                Ok(resp) => {
+                                    let points = resp.into_inner().points;
+                                    if points.is_empty() {
+                                        state.history_list.push("*No portfolio snapshots yet.".to_string());
+                                    } else {
+                                        state.history_list.push("*Portfolio history:".to_string());
+                                        for point in points.iter() {
+                                            for item in point.to_string_lines().into_iter() {
+                                                state.history_list.push(item);
+                                            }
+                                        }
+                                    }
+                                    state.history_list.push("".to_string());
+                                },
+                                Err(e) => tx_log.send(ClientEvent::Log(format!("Error: {e}"))).await?,
+                            }
+                        }
+                        else if line.as_str() == HIST_CMD || line.as_str().starts_with(HIST_PREFIX) {
+                            let limit = match line.as_str().strip_prefix(HIST_PREFIX).map(|l| l.trim().parse::<u32>()) {
+                                Some(Ok(limit)) => limit,
+                                Some(Err(_)) => {
+                                    let _ = tx_log.send(ClientEvent::Log("Invalid limit. Use: hist [limit]".to_string())).await;
+                                    continue;
+                                }
+                                None => 0,
+                            };
+
+                            let _ = tx_log.send(ClientEvent::Log("Trade history request has been sent".to_string())).await;
+                            let mut client_clone = client.clone();
+
+                            let mut history_request = Request::new(GetTradeHistoryRequest { limit, before: None, since: None, wallet: None });
+                            history_request.metadata_mut().insert(
+                                "client-id",
+                                MetadataValue::try_from(client_id.to_string())?,
+                            );
+
+                            match client_clone.get_trade_history(history_request).await {
+                                Ok(resp) => {
+                                    let mut stream = resp.into_inner();
+                                    let mut count = 0;
+                                    state.history_list.push("*Wallet history:".to_string());
+                                    loop {
+                                        match stream.message().await {
+                                            Ok(Some(item)) => {
+                                                if let Some(trade) = item.trade {
+                                                    count += 1;
+                                                    state.history_list.push(format!("Tx: {}", trade.signature));
+                                                    for item in trade.to_string_lines().into_iter() {
+                                                        state.history_list.push(item);
+                                                    }
+                                                } else if let Some(activity) = item.activity {
+                                                    count += 1;
+                                                    state.history_list.push(format!("Tx: {}", activity.signature));
+                                                    for item in activity.to_string_lines().into_iter() {
+                                                        state.history_list.push(item);
+                                                    }
+                                                }
+                                            }
+                                            Ok(None) => break,
+                                            Err(e) => {
+                                                tx_log.send(ClientEvent::Log(format!("Error: {e}"))).await?;
+                                                break;
+                                            }
+                                        }
+                                    }
+                                    if count == 0 {
+                                        state.history_list.push("*No activity detected.".to_string());
                                     }
                                     state.history_list.push("".to_string());
                                 },
@@ -204,7 +513,9 @@ pub async fn run_cli_client(cli: CliArgs) -> Result<(), Box<dyn std::error::Erro
                                         }
 
                                         let _ = tx_log.send(ClientEvent::Log("Subscription request has been sent".to_string())).await;
-                                        let mut subscribe_request = Request::new(SubscribeRequest {});
+                                        let mut subscribe_request = Request::new(SubscribeRequest {
+                                            filter: stream_filter.clone(),
+                                        });
                                         subscribe_request.metadata_mut().insert(
                                             "client-id",
                                             MetadataValue::try_from(client_id.clone().to_string())?,
@@ -222,7 +533,7 @@ pub async fn run_cli_client(cli: CliArgs) -> Result<(), Box<dyn std::error::Erro
                                                     }
                                                     _ = sleep(Duration::from_millis(500)) => {
                                                         while let Ok(Some(item)) = stream.message().await {
-                                                            tx_stream.send(ClientEvent::SubscriptionMsg(item.message)).await.unwrap();
+                                                            tx_stream.send(ClientEvent::SubscriptionMsg(item.to_stream_string())).await.unwrap();
                                                     }
                                                     }
                                                 }
@@ -250,11 +561,11 @@ pub async fn run_cli_client(cli: CliArgs) -> Result<(), Box<dyn std::error::Erro
                                         tx_log.send(ClientEvent::Log("No active subscription to unsubscribe.".to_string())).await?;
                                     }
                                 }
-                                "hold" => {
+                                "hold" | "hold all" => {
                                     let _ = tx_log.send(ClientEvent::Log("Holdings request has been sent".to_string())).await;
                                     let mut client_clone = client.clone();
 
-                                    let mut holdings_request = Request::new(HoldingsRequest {});
+                                    let mut holdings_request = Request::new(HoldingsRequest { all: line.as_str() == "hold all" });
                                     holdings_request.metadata_mut().insert(
                                         "client-id",
                                         MetadataValue::try_from(client_id.clone().to_string())?,
@@ -278,8 +589,61 @@ pub async fn run_cli_client(cli: CliArgs) -> Result<(), Box<dyn std::error::Erro
                                         Err(e) => tx_log.send(ClientEvent::Log(format!("Error: {e}"))).await?,
                                     }
                                 }
+                                "pos" => {
+                                    let _ = tx_log.send(ClientEvent::Log("Positions request has been sent".to_string())).await;
+                                    let mut client_clone = client.clone();
+
+                                    let mut positions_request = Request::new(GetPositionsRequest {});
+                                    positions_request.metadata_mut().insert(
+                                        "client-id",
+                                        MetadataValue::try_from(client_id.to_string())?,
+                                    );
+
+                                    match client_clone.get_positions(positions_request).await {
+                                        Ok(resp) => {
+                                            let positions: Vec<String> = resp.into_inner().positions.into_iter().map(|p| {
+                                                p.to_string()
+                                            }).collect();
+                                            if positions.is_empty() {
+                                                state.history_list.push("*No positions found.".to_string());
+                                            } else {
+                                                state.history_list.push("*Positions:".to_string());
+                                                for item in positions.into_iter() {
+                                                    state.history_list.push(item);
+                                                }
+                                            };
+                                            state.history_list.push("".to_string());
+                                        },
+                                        Err(e) => tx_log.send(ClientEvent::Log(format!("Error: {e}"))).await?,
+                                    }
+                                }
+                                "alerts" => {
+                                    let mut client_clone = client.clone();
+
+                                    let mut list_alerts_request = Request::new(ListAlertsRequest {});
+                                    list_alerts_request.metadata_mut().insert(
+                                        "client-id",
+                                        MetadataValue::try_from(client_id.to_string())?,
+                                    );
+
+                                    match client_clone.list_alerts(list_alerts_request).await {
+                                        Ok(resp) => {
+                                            let alerts = resp.into_inner().alerts;
+                                            if alerts.is_empty() {
+                                                state.history_list.push("*No alerts set.".to_string());
+                                            } else {
+                                                state.history_list.push("*Alerts:".to_string());
+                                                for alert in alerts.iter() {
+                                                    state.history_list.push(alert.to_string());
+                                                }
+                                            }
+                                            state.history_list.push("".to_string());
+                                        },
+                                        Err(e) => tx_log.send(ClientEvent::Log(format!("Error: {e}"))).await?,
+                                    }
+                                }
                                 _ => {
-                                    let _ = tx_log.send(ClientEvent::Log("Unknown command. Use: sub | unsub | hold | exit | quit".to_string())).await;
+                                    let _ = tx_log.send(ClientEvent::Log("Unknown command. Use: sub | unsub | hold [all] | pos | tx [hash] | hist [limit] | phist [hours] | addw [wallets] | rmw [wallets] | addt [tokens] | rmt [tokens] | alert [mint] [above|below|up|down] [value] [repeat] | alerts | rmalert [id] | exit | quit".to_string())).await;
                                 }
                             }
                         }
@@ -0,0 +1,38 @@
+use tonic::{
+    Request, Status,
+    codegen::InterceptedService,
+    metadata::{Ascii, MetadataValue, errors::InvalidMetadataValue},
+    service::Interceptor,
+    transport::Channel,
+};
+
+use crate::proto::cli_service_client::CliServiceClient;
+
+pub type AuthenticatedClient = CliServiceClient<InterceptedService<Channel, ApiKeyAuth>>;
+
+// sends the API key as a bearer token with every request
+#[derive(Clone, Default)]
+pub struct ApiKeyAuth {
+    authorization: Option<MetadataValue<Ascii>>,
+}
+
+impl ApiKeyAuth {
+    pub fn new(api_key: Option<&str>) -> Result<Self, InvalidMetadataValue> {
+        let authorization = api_key
+            .map(|key| MetadataValue::try_from(format!("Bearer {}", key)))
+            .transpose()?;
+
+        Ok(Self { authorization })
+    }
+}
+
+impl Interceptor for ApiKeyAuth {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(authorization) = &self.authorization {
+            request
+                .metadata_mut()
+                .insert("authorization", authorization.clone());
+        }
+        Ok(request)
+    }
+}
@@ -2,8 +2,31 @@ use serde::Deserialize;
 
 #[derive(Deserialize, Debug)]
 pub struct Config {
+    #[serde(default)]
     pub wallet: String,
+    // additional wallets tracked in the same session
+    #[serde(default)]
+    pub wallets: Vec<String>,
     pub token_mints: Vec<String>,
+    // rules of the live stream, everything is streamed without it
+    #[serde(default)]
+    pub stream_filter: Option<StreamFilterConfig>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct StreamFilterConfig {
+    #[serde(default)]
+    pub tracked_tokens_only: bool,
+    pub min_usd_value: Option<f64>,
+    // "buy" or "sell"
+    pub side: Option<String>,
+    // e.g. "jupiter", "raydium_amm", "orca_whirlpool"
+    #[serde(default)]
+    pub venues: Vec<String>,
+    #[serde(default)]
+    pub exclude_dust: bool,
+    #[serde(default)]
+    pub trades_only: bool,
 }
 
 impl Config {
@@ -1,4 +1,5 @@
 pub mod app;
+pub mod auth;
 pub mod config;
 pub mod proto_ext;
 pub mod render;
@@ -7,6 +8,7 @@ pub mod utils;
 
 pub use app::CliArgs;
 pub use app::run_cli_client;
+pub use auth::*;
 pub use config::*;
 pub use render::*;
 pub use state::*;
@@ -1,10 +1,14 @@
-use crate::client::{Config, fmt_token, fmt_usd};
-use crate::proto::{Holding, InitRequest, Trade, Transfer};
+use crate::client::{Config, StreamFilterConfig, fmt_token, fmt_token_amount, fmt_usd};
+use crate::proto::{
+    Activity, AlertCondition, ExecutionPath, Holding, InitRequest, PortfolioPoint, Position,
+    SetAlertRequest, StreamFilter, SubscribeResponse, Trade, TradeSide, Transfer, Venue,
+    subscribe_response::Event,
+};
 use std::fmt;
 
 impl InitRequest {
     pub fn build(config: Config) -> Result<Self, String> {
-        if config.wallet.is_empty() {
+        if config.wallet.is_empty() && config.wallets.is_empty() {
             return Err("Wallet address is required in config".to_string());
         }
 
@@ -15,10 +19,99 @@ impl InitRequest {
         Ok(Self {
             wallet: config.wallet,
             tokens: config.token_mints,
+            wallets: config.wallets,
         })
     }
 }
 
+impl SetAlertRequest {
+    // <mint> <above|below|up|down> <value> [repeat]
+    pub fn parse(args: &str) -> Result<Self, String> {
+        const USAGE: &str = "Use: alert [mint] [above|below|up|down] [value] [repeat]";
+
+        let args: Vec<&str> = args.split_whitespace().collect();
+        let (mint, condition, value, repeat) = match args.as_slice() {
+            [mint, condition, value] => (mint, condition, value, false),
+            [mint, condition, value, "repeat"] => (mint, condition, value, true),
+            _ => return Err(format!("Invalid alert. {}", USAGE)),
+        };
+
+        let condition = match *condition {
+            "above" => AlertCondition::PriceAbove,
+            "below" => AlertCondition::PriceBelow,
+            "up" => AlertCondition::Change24hUp,
+            "down" => AlertCondition::Change24hDown,
+            _ => return Err(format!("Invalid alert condition. {}", USAGE)),
+        };
+
+        let value = value
+            .parse::<f64>()
+            .map_err(|_| format!("Invalid alert value. {}", USAGE))?;
+
+        let mut request = Self {
+            mint: mint.to_string(),
+            value,
+            repeat,
+            ..Default::default()
+        };
+        request.set_condition(condition);
+        Ok(request)
+    }
+}
+
+impl StreamFilter {
+    pub fn build(config: &StreamFilterConfig) -> Result<Self, String> {
+        let mut filter = Self {
+            tracked_tokens_only: config.tracked_tokens_only,
+            min_usd_value: config.min_usd_value,
+            exclude_dust: config.exclude_dust,
+            trades_only: config.trades_only,
+            ..Default::default()
+        };
+
+        if let Some(side) = &config.side {
+            let side = TradeSide::from_str_name(&side.to_uppercase())
+                .ok_or(format!("Error: Invalid side in stream filter: {}", side))?;
+            filter.set_side(side);
+        }
+
+        for venue in config.venues.iter() {
+            let venue = Venue::from_str_name(&venue.to_uppercase())
+                .ok_or(format!("Error: Invalid venue in stream filter: {}", venue))?;
+            filter.venues.push(venue as i32);
+        }
+
+        Ok(filter)
+    }
+}
+
+impl SubscribeResponse {
+    pub fn to_stream_string(&self) -> String {
+        match &self.event {
+            Some(Event::Trade(trade)) => {
+                let mut lines = vec![format!(
+                    "Trade detected: {} (wallet: {})",
+                    trade.signature, trade.wallet
+                )];
+                lines.extend(trade.to_string_lines());
+                lines.join("\n")
+            }
+            Some(Event::Activity(activity)) => {
+                let mut lines = vec![format!(
+                    "Activity detected: {} (wallet: {})",
+                    activity.signature, activity.wallet
+                )];
+                lines.extend(activity.to_string_lines());
+                lines.join("\n")
+            }
+            Some(Event::Alert(alert_event)) => format!("Alert triggered: {}", alert_event),
+            Some(Event::Error(error)) => format!("Error: {}", error.message),
+            Some(Event::Status(status)) => status.message.clone(),
+            None => self.message.clone(),
+        }
+    }
+}
+
 impl fmt::Display for Holding {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let price_str = if let Some(price) = self.usd_price {
@@ -39,19 +132,54 @@ impl fmt::Display for Holding {
     }
 }
 
+impl PortfolioPoint {
+    pub fn to_string_lines(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "Time: {}\tValue: {}",
+            self.timestamp,
+            fmt_usd(self.usd_value)
+        )];
+        for holding in self.holdings.iter() {
+            lines.push(holding.to_string());
+        }
+        lines
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let token_info = match (&self.name, &self.symbol) {
+            (Some(name), Some(symbol)) => format!("{} ({})", name, symbol),
+            (Some(name), None) => name.clone(),
+            (None, Some(symbol)) => symbol.clone(),
+            (None, None) => self.mint.clone(),
+        };
+        write!(
+            f,
+            "  {} - Qty: {}, Avg Price: {}, Realized PnL: {}, Fees: {} SOL, Trades: {}",
+            token_info,
+            fmt_token_amount(self.raw_quantity, self.decimals),
+            fmt_usd(self.avg_entry_price),
+            fmt_usd(self.realized_pnl),
+            self.fees_paid,
+            self.trade_count
+        )
+    }
+}
+
 impl Transfer {
     fn to_short_string(&self) -> String {
         let mut token_info = String::new();
         if let Some(name) = &self.name {
-            token_info.push_str(&name);
+            token_info.push_str(name);
         }
         if let Some(symbol) = &self.symbol {
-            if token_info.len() > 0 {
-                token_info.push_str(" ");
+            if !token_info.is_empty() {
+                token_info.push(' ');
             }
             token_info.push_str(&format!("[{symbol}]"));
         }
-        if token_info.len() == 0 {
+        if token_info.is_empty() {
             token_info.push_str(&self.mint[0..4]);
             token_info.push_str("...");
             token_info.push_str(&self.mint[&self.mint.len() - 4..]);
@@ -63,11 +191,17 @@ impl Transfer {
             "N/A".to_string()
         };
 
+        let transfer_fee_str = match self.transfer_fee {
+            Some(transfer_fee) => format!(" Transfer fee: {}", fmt_token(transfer_fee)),
+            None => String::new(),
+        };
+
         format!(
-            "  {} Amount: {} Current Value: {}",
+            "  {} Amount: {} Value: {}{}",
             token_info,
-            fmt_token(self.amount),
+            fmt_token_amount(self.raw_amount, self.decimals),
             value_str,
+            transfer_fee_str,
         )
     }
 }
@@ -86,6 +220,18 @@ impl Trade {
             res.push("Multi Swap".to_string());
         }
 
+        if self.venue() != Venue::UnknownVenue
+            && let Some(kind) = res.last_mut()
+        {
+            kind.push_str(&format!(" on {}", self.venue()));
+        }
+
+        if self.execution_path() != ExecutionPath::Direct
+            && let Some(kind) = res.last_mut()
+        {
+            kind.push_str(&format!(" ({})", self.execution_path()));
+        }
+
         res.push("From:".to_string());
 
         for item in self.from.iter() {
@@ -98,6 +244,36 @@ impl Trade {
             res.push(item.to_short_string());
         }
 
+        if let Some(sol_costs) = &self.sol_costs {
+            res.push(format!("SOL {}", sol_costs));
+        }
+
+        res
+    }
+}
+
+impl Activity {
+    pub fn to_string_lines(&self) -> Vec<String> {
+        let mut res = vec![self.kind().to_string()];
+
+        if let Some(counterparty) = &self.counterparty {
+            res.push(format!("Counterparty: {}", counterparty));
+        }
+
+        if !self.outgoing.is_empty() {
+            res.push("Out:".to_string());
+            for item in self.outgoing.iter() {
+                res.push(item.to_short_string());
+            }
+        }
+
+        if !self.incoming.is_empty() {
+            res.push("In:".to_string());
+            for item in self.incoming.iter() {
+                res.push(item.to_short_string());
+            }
+        }
+
         res
     }
 }
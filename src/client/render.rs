@@ -8,11 +8,11 @@ use ratatui::{
 
 use crate::client::{AppState, Panel, ScrollState};
 
-fn render_panel<'a>(
+fn render_panel(
     f: &mut Frame,
     area: Rect,
     title: &str,
-    items: &Vec<String>,
+    items: &[String],
     scroll: &ScrollState,
     focused: bool,
 ) {
@@ -69,7 +69,7 @@ pub fn ui(f: &mut Frame, app_state: &mut AppState) {
         container[0],
         "Stream",
         &app_state.stream_list,
-        &mut app_state.stream_scroll,
+        &app_state.stream_scroll,
         matches!(app_state.focused, Panel::Stream),
     );
 
@@ -78,7 +78,7 @@ pub fn ui(f: &mut Frame, app_state: &mut AppState) {
         right_side[0],
         "Repl History",
         &app_state.history_list,
-        &mut app_state.history_scroll,
+        &app_state.history_scroll,
         matches!(app_state.focused, Panel::History),
     );
 
@@ -87,7 +87,7 @@ pub fn ui(f: &mut Frame, app_state: &mut AppState) {
         right_side[1],
         "Logs",
         &app_state.logs,
-        &mut app_state.log_scroll,
+        &app_state.log_scroll,
         matches!(app_state.focused, Panel::Log),
     );
 
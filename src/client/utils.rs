@@ -4,6 +4,20 @@ pub fn fmt_token(value: f64) -> String {
     fmt_ccy(value, "")
 }
 
+// exact decimal of a raw amount, with at least two decimals
+pub fn fmt_token_amount(raw: u64, decimals: u32) -> String {
+    let raw = raw as u128;
+    let denom = 10u128.pow(decimals);
+    let frac_str = format!("{:0width$}", raw % denom, width = decimals as usize);
+    let frac_str = frac_str.trim_end_matches('0');
+
+    format!(
+        "{}.{:0<2}",
+        (raw / denom).to_formatted_string(&Locale::en),
+        frac_str
+    )
+}
+
 pub fn fmt_usd(value: f64) -> String {
     fmt_ccy(value, "$")
 }
@@ -0,0 +1,174 @@
+use std::{fmt, sync::Arc};
+
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+use tonic::{Request, Status, service::Interceptor};
+
+use crate::server::config::AuthConfig;
+
+const BEARER_PREFIX: &str = "Bearer ";
+
+// the owner of the client sessions created with an API key
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Principal(String);
+
+impl Principal {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self(name.into())
+    }
+
+    // the sessions of a server without API keys
+    pub fn anonymous() -> Self {
+        Self("anonymous".to_string())
+    }
+}
+
+impl fmt::Display for Principal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+// resolves the `authorization: Bearer <key>` metadata into the Principal of the request extensions
+#[derive(Clone, Default)]
+pub struct ApiKeyInterceptor {
+    // key -> principal
+    api_keys: Arc<Vec<(String, Principal)>>,
+}
+
+impl ApiKeyInterceptor {
+    pub fn new(auth: &AuthConfig) -> Self {
+        Self {
+            api_keys: Arc::new(
+                auth.api_keys
+                    .iter()
+                    .map(|api_key| (api_key.key.clone(), Principal::new(&api_key.principal)))
+                    .collect(),
+            ),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.api_keys.is_empty()
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn authenticate<T>(&self, request: &Request<T>) -> Result<Principal, Status> {
+        if !self.is_enabled() {
+            return Ok(Principal::anonymous());
+        }
+
+        let token = request
+            .metadata()
+            .get("authorization")
+            .ok_or_else(|| Status::unauthenticated("missing API key"))?
+            .to_str()
+            .ok()
+            .and_then(|value| value.strip_prefix(BEARER_PREFIX))
+            .ok_or_else(|| Status::unauthenticated("malformed authorization header"))?;
+
+        // every key is compared to not leak the matching prefix length through timing
+        let mut principal = None;
+        for (key, key_principal) in self.api_keys.iter() {
+            if constant_time_eq(key.as_bytes(), token.as_bytes()) {
+                principal = Some(key_principal.clone());
+            }
+        }
+
+        principal.ok_or_else(|| Status::unauthenticated("invalid API key"))
+    }
+}
+
+impl Interceptor for ApiKeyInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        match self.authenticate(&request) {
+            Ok(principal) => {
+                request.extensions_mut().insert(principal);
+                Ok(request)
+            }
+            Err(status) => {
+                tracing::warn!("Rejected request: {}", status.message());
+                Err(status)
+            }
+        }
+    }
+}
+
+// the digests have a fixed length, so the key length does not leak either
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    Sha256::digest(a)
+        .as_slice()
+        .ct_eq(Sha256::digest(b).as_slice())
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::config::ApiKeyConfig;
+    use tonic::metadata::MetadataValue;
+
+    fn request(authorization: Option<&str>) -> Request<()> {
+        let mut request = Request::new(());
+        if let Some(authorization) = authorization {
+            request.metadata_mut().insert(
+                "authorization",
+                MetadataValue::try_from(authorization).unwrap(),
+            );
+        }
+        request
+    }
+
+    fn principal(interceptor: &mut ApiKeyInterceptor, authorization: Option<&str>) -> Principal {
+        interceptor
+            .call(request(authorization))
+            .unwrap()
+            .extensions()
+            .get::<Principal>()
+            .cloned()
+            .unwrap()
+    }
+
+    #[test]
+    fn resolves_principal_of_api_key() {
+        let mut interceptor = ApiKeyInterceptor::new(&AuthConfig {
+            api_keys: vec![
+                ApiKeyConfig {
+                    principal: "desk-a".to_string(),
+                    key: "key-a".to_string(),
+                },
+                ApiKeyConfig {
+                    principal: "desk-b".to_string(),
+                    key: "key-b".to_string(),
+                },
+            ],
+        });
+
+        assert_eq!(
+            Principal::new("desk-b"),
+            principal(&mut interceptor, Some("Bearer key-b"))
+        );
+        for authorization in [
+            None,
+            Some("key-a"),
+            Some("Bearer key-c"),
+            Some("Bearer "),
+            Some("Bearer key"),
+            Some("Bearer key-a-longer"),
+        ] {
+            let status = interceptor.call(request(authorization)).unwrap_err();
+            assert_eq!(tonic::Code::Unauthenticated, status.code());
+        }
+    }
+
+    #[test]
+    fn anonymous_without_api_keys() {
+        let mut interceptor = ApiKeyInterceptor::new(&AuthConfig::default());
+
+        assert_eq!(Principal::anonymous(), principal(&mut interceptor, None));
+        assert_eq!(
+            Principal::anonymous(),
+            principal(&mut interceptor, Some("Bearer key-a"))
+        );
+    }
+}
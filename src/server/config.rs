@@ -0,0 +1,664 @@
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+};
+
+use clap::Parser;
+use serde::Deserialize;
+use thiserror::Error;
+use tonic::transport::{Certificate, Identity, ServerTlsConfig};
+
+use crate::server::utils::constants::{
+    SERVER_CONFIG, SNAPSHOT_STORE_DB, SOLANA_RPC_URL, SOLANA_WS_URL, TOKEN_STORE_DB,
+};
+
+// e.g. SOL_TRACE__COMMITMENT__TRANSACTION=finalized overrides `transaction` in [commitment]
+const ENV_OVERRIDE_PREFIX: &str = "SOL_TRACE__";
+
+#[derive(Parser, Debug, Default)]
+#[command(name = "Sol-trace server")]
+#[command(about = "Solana wallet tracker gRPC server", long_about = None)]
+pub struct ServerArgs {
+    // SERVER_CONFIG if not given
+    #[arg(long, short)]
+    pub config: Option<String>,
+
+    #[arg(long, short)]
+    pub listen_addr: Option<String>,
+
+    // replaces the RPC endpoints of the config, can be repeated
+    #[arg(long)]
+    pub rpc: Vec<String>,
+
+    // replaces the WebSocket endpoints of the config, can be repeated
+    #[arg(long)]
+    pub ws: Vec<String>,
+
+    // the PEM certificate chain of the server, enables TLS
+    #[arg(long)]
+    pub tls_cert: Option<String>,
+
+    // the PEM private key of the server
+    #[arg(long)]
+    pub tls_key: Option<String>,
+
+    // the PEM CA of the client certificates, requires clients to present one
+    #[arg(long)]
+    pub tls_client_ca: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Failed to read the server config {0}: {1}")]
+    Read(String, std::io::Error),
+    #[error("Invalid server config {0}: {1}")]
+    Parse(String, String),
+    #[error("Invalid value of {0}: {1}")]
+    Override(String, String),
+    #[error("Invalid server config: {0}")]
+    Invalid(String),
+}
+
+// defaults < config file < SOL_TRACE__ env vars < CLI flags
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub listen_addr: String,
+    pub endpoints: EndpointsConfig,
+    pub commitment: CommitmentConfig,
+    pub channels: ChannelsConfig,
+    pub websocket: WebSocketConfig,
+    pub store: StoreConfig,
+    pub auth: AuthConfig,
+    pub tls: TlsConfig,
+    pub sessions: SessionsConfig,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            listen_addr: "127.0.0.1:50051".to_string(),
+            endpoints: EndpointsConfig::default(),
+            commitment: CommitmentConfig::default(),
+            channels: ChannelsConfig::default(),
+            websocket: WebSocketConfig::default(),
+            store: StoreConfig::default(),
+            auth: AuthConfig::default(),
+            tls: TlsConfig::default(),
+            sessions: SessionsConfig::default(),
+        }
+    }
+}
+
+// the providers of a kind are tried in the order of their health and latency
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct EndpointsConfig {
+    pub rpc: Vec<String>,
+    pub ws: Vec<String>,
+    pub jupiter_api: String,
+    // the price history
+    pub birdeye_api: String,
+    // the requests of all clients share a bucket per API: a burst, refilled per second
+    pub jupiter_rate_limit_burst: u32,
+    pub jupiter_rate_limit_per_sec: f64,
+    pub birdeye_rate_limit_burst: u32,
+    pub birdeye_rate_limit_per_sec: f64,
+    // a provider that accepts the connection and then hangs fails over after these
+    pub connect_timeout_secs: u64,
+    pub request_timeout_secs: u64,
+}
+
+impl Default for EndpointsConfig {
+    fn default() -> Self {
+        Self {
+            rpc: vec![],
+            ws: vec![],
+            jupiter_api: "https://lite-api.jup.ag".to_string(),
+            birdeye_api: "https://public-api.birdeye.so".to_string(),
+            jupiter_rate_limit_burst: 10,
+            jupiter_rate_limit_per_sec: 1.0,
+            birdeye_rate_limit_burst: 1,
+            birdeye_rate_limit_per_sec: 1.0,
+            connect_timeout_secs: 5,
+            request_timeout_secs: 15,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Commitment {
+    Processed,
+    Confirmed,
+    Finalized,
+}
+
+impl Commitment {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Commitment::Processed => "processed",
+            Commitment::Confirmed => "confirmed",
+            Commitment::Finalized => "finalized",
+        }
+    }
+}
+
+// per RPC call group
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct CommitmentConfig {
+    // getTransaction, `processed` is not supported
+    pub transaction: Commitment,
+    // getBalance, getTokenAccountBalance
+    pub balances: Commitment,
+    // getTokenAccountsByOwner, getAccountInfo, getMultipleAccounts
+    pub accounts: Commitment,
+    // getSignaturesForAddress
+    pub signatures: Commitment,
+    // logsSubscribe
+    pub logs: Commitment,
+}
+
+impl Default for CommitmentConfig {
+    fn default() -> Self {
+        Self {
+            transaction: Commitment::Confirmed,
+            balances: Commitment::Finalized,
+            accounts: Commitment::Finalized,
+            signatures: Commitment::Finalized,
+            logs: Commitment::Finalized,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChannelsConfig {
+    // the live stream of a client
+    pub subscription: usize,
+    // the history stream of a request
+    pub history: usize,
+    // the outgoing messages of a WebSocket connection
+    pub ws_writer: usize,
+}
+
+impl Default for ChannelsConfig {
+    fn default() -> Self {
+        Self {
+            subscription: 10,
+            history: 10,
+            ws_writer: 3,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebSocketConfig {
+    pub ping_interval_secs: u64,
+}
+
+impl Default for WebSocketConfig {
+    fn default() -> Self {
+        Self {
+            ping_interval_secs: 20,
+        }
+    }
+}
+
+// a session without a live stream expires after ttl_secs without a request
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SessionsConfig {
+    pub ttl_secs: u64,
+    // the dropped streams and the expired sessions are freed at this interval
+    pub reaper_interval_secs: u64,
+}
+
+impl Default for SessionsConfig {
+    fn default() -> Self {
+        Self {
+            ttl_secs: 600,
+            reaper_interval_secs: 30,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StoreBackend {
+    #[default]
+    Memory,
+    Sqlite,
+}
+
+// the token metadata and the portfolio snapshots share `path` unless a store has its own
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct StoreConfig {
+    // memory if not set, unless TOKEN_STORE_DB or SNAPSHOT_STORE_DB is
+    pub backend: Option<StoreBackend>,
+    pub path: Option<String>,
+    pub token_path: Option<String>,
+    pub snapshot_path: Option<String>,
+}
+
+impl StoreConfig {
+    pub fn backend(&self) -> StoreBackend {
+        self.backend.unwrap_or_default()
+    }
+
+    pub fn token_path(&self) -> Option<&str> {
+        self.token_path.as_deref().or(self.path.as_deref())
+    }
+
+    pub fn snapshot_path(&self) -> Option<&str> {
+        self.snapshot_path.as_deref().or(self.path.as_deref())
+    }
+
+    // each store falls back to its own variable, a backend set by the config wins
+    fn apply_fallbacks(&mut self, token_db: Option<String>, snapshot_db: Option<String>) {
+        if self.backend.is_some() {
+            return;
+        }
+        if self.token_path().is_none() {
+            self.token_path = token_db;
+        }
+        if self.snapshot_path().is_none() {
+            self.snapshot_path = snapshot_db;
+        }
+        if self.token_path().is_some() || self.snapshot_path().is_some() {
+            self.backend = Some(StoreBackend::Sqlite);
+        }
+    }
+}
+
+// every client session belongs to the principal of the API key that created it,
+// the server is open to anyone if no key is configured
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    pub api_keys: Vec<ApiKeyConfig>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ApiKeyConfig {
+    pub principal: String,
+    pub key: String,
+}
+
+// plaintext without a certificate, mutual TLS with a client CA
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    pub cert: Option<String>,
+    pub key: Option<String>,
+    pub client_ca: Option<String>,
+}
+
+impl TlsConfig {
+    pub fn load(&self) -> Result<Option<ServerTlsConfig>, ConfigError> {
+        let (Some(cert), Some(key)) = (&self.cert, &self.key) else {
+            return Ok(None);
+        };
+
+        let mut tls =
+            ServerTlsConfig::new().identity(Identity::from_pem(read_pem(cert)?, read_pem(key)?));
+        if let Some(client_ca) = &self.client_ca {
+            tls = tls.client_ca_root(Certificate::from_pem(read_pem(client_ca)?));
+        }
+
+        Ok(Some(tls))
+    }
+}
+
+fn read_pem(path: &str) -> Result<Vec<u8>, ConfigError> {
+    std::fs::read(path).map_err(|e| ConfigError::Read(path.to_string(), e))
+}
+
+impl ServerConfig {
+    pub fn build(args: &ServerArgs) -> Result<Self, ConfigError> {
+        let path = args.config.clone().or_else(|| SERVER_CONFIG.clone());
+
+        let mut value = match &path {
+            Some(path) => {
+                let config_data = std::fs::read_to_string(path)
+                    .map_err(|e| ConfigError::Read(path.clone(), e))?;
+                toml::from_str::<toml::Value>(&config_data)
+                    .map_err(|e| ConfigError::Parse(path.clone(), e.to_string()))?
+            }
+            None => toml::Value::Table(toml::value::Table::new()),
+        };
+
+        apply_env_overrides(&mut value, std::env::vars())?;
+
+        let mut config: ServerConfig = value.try_into().map_err(|e: toml::de::Error| {
+            ConfigError::Parse(path.unwrap_or("from env".to_string()), e.to_string())
+        })?;
+
+        config.apply_args(args);
+        config.apply_fallbacks();
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    fn apply_args(&mut self, args: &ServerArgs) {
+        if let Some(listen_addr) = &args.listen_addr {
+            self.listen_addr = listen_addr.clone();
+        }
+        if !args.rpc.is_empty() {
+            self.endpoints.rpc = args.rpc.clone();
+        }
+        if !args.ws.is_empty() {
+            self.endpoints.ws = args.ws.clone();
+        }
+        if args.tls_cert.is_some() {
+            self.tls.cert = args.tls_cert.clone();
+        }
+        if args.tls_key.is_some() {
+            self.tls.key = args.tls_key.clone();
+        }
+        if args.tls_client_ca.is_some() {
+            self.tls.client_ca = args.tls_client_ca.clone();
+        }
+    }
+
+    // the single endpoint and store variables of the earlier versions
+    fn apply_fallbacks(&mut self) {
+        if self.endpoints.rpc.is_empty() {
+            self.endpoints.rpc.extend(SOLANA_RPC_URL.clone());
+        }
+        if self.endpoints.ws.is_empty() {
+            self.endpoints.ws.extend(SOLANA_WS_URL.clone());
+        }
+        self.store
+            .apply_fallbacks(TOKEN_STORE_DB.clone(), SNAPSHOT_STORE_DB.clone());
+    }
+
+    pub fn listen_addr(&self) -> SocketAddr {
+        // validated
+        self.listen_addr.parse().expect("Invalid listen address")
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |msg: String| Err(ConfigError::Invalid(msg));
+
+        if self.listen_addr.parse::<SocketAddr>().is_err() {
+            return invalid(format!(
+                "listen_addr {} is not an address",
+                self.listen_addr
+            ));
+        }
+
+        if self.endpoints.rpc.is_empty() {
+            return invalid(
+                "no RPC endpoint, set [endpoints] rpc, --rpc or SOLANA_RPC_URL".to_string(),
+            );
+        }
+        if self.endpoints.ws.is_empty() {
+            return invalid(
+                "no WebSocket endpoint, set [endpoints] ws, --ws or SOLANA_WS_URL".to_string(),
+            );
+        }
+        for rpc in self.endpoints.rpc.iter() {
+            validate_url(rpc, &["http", "https"])?;
+        }
+        for ws in self.endpoints.ws.iter() {
+            validate_url(ws, &["ws", "wss"])?;
+        }
+        validate_url(&self.endpoints.jupiter_api, &["http", "https"])?;
+        validate_url(&self.endpoints.birdeye_api, &["http", "https"])?;
+        if self.endpoints.jupiter_rate_limit_burst == 0
+            || self.endpoints.birdeye_rate_limit_burst == 0
+            || self.endpoints.jupiter_rate_limit_per_sec <= 0.0
+            || self.endpoints.birdeye_rate_limit_per_sec <= 0.0
+        {
+            return invalid("the rate limits must be positive".to_string());
+        }
+        if self.endpoints.connect_timeout_secs == 0 || self.endpoints.request_timeout_secs == 0 {
+            return invalid("the endpoint timeouts must be positive".to_string());
+        }
+
+        if self.commitment.transaction == Commitment::Processed {
+            return invalid("getTransaction does not support the processed commitment".to_string());
+        }
+
+        if self.channels.subscription == 0 || self.channels.history == 0 {
+            return invalid("the channel sizes must be positive".to_string());
+        }
+        if self.channels.ws_writer == 0 {
+            return invalid("the channel sizes must be positive".to_string());
+        }
+        if self.websocket.ping_interval_secs == 0 {
+            return invalid("ping_interval_secs must be positive".to_string());
+        }
+        if self.sessions.ttl_secs == 0 || self.sessions.reaper_interval_secs == 0 {
+            return invalid("the session ttl and reaper interval must be positive".to_string());
+        }
+
+        if self.store.backend() == StoreBackend::Sqlite
+            && self.store.token_path().is_none()
+            && self.store.snapshot_path().is_none()
+        {
+            return invalid("the sqlite store needs a path".to_string());
+        }
+
+        if self.tls.cert.is_some() != self.tls.key.is_some() {
+            return invalid("TLS needs both a certificate and a key".to_string());
+        }
+        if self.tls.client_ca.is_some() && self.tls.cert.is_none() {
+            return invalid(
+                "client certificates need TLS, set a certificate and a key".to_string(),
+            );
+        }
+
+        let mut keys = HashSet::new();
+        for api_key in self.auth.api_keys.iter() {
+            if api_key.principal.is_empty() {
+                return invalid("an API key has no principal".to_string());
+            }
+            // sent as a bearer token in the authorization header
+            if api_key.key.is_empty() || !api_key.key.bytes().all(|b| b.is_ascii_graphic()) {
+                return invalid(format!(
+                    "the API key of {} must be printable ASCII without spaces",
+                    api_key.principal
+                ));
+            }
+            if !keys.insert(api_key.key.as_str()) {
+                return invalid(format!(
+                    "the API key of {} is not unique",
+                    api_key.principal
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn validate_url(url: &str, schemes: &[&str]) -> Result<(), ConfigError> {
+    match url::Url::parse(url) {
+        Ok(parsed) if schemes.contains(&parsed.scheme()) => Ok(()),
+        _ => Err(ConfigError::Invalid(format!(
+            "{} is not a {} url",
+            url,
+            schemes.join("/")
+        ))),
+    }
+}
+
+// the value is read as TOML (numbers, booleans, arrays) and as a plain string otherwise
+fn apply_env_overrides(
+    value: &mut toml::Value,
+    vars: impl Iterator<Item = (String, String)>,
+) -> Result<(), ConfigError> {
+    let overrides: HashMap<String, String> = vars
+        .filter(|(key, _)| key.starts_with(ENV_OVERRIDE_PREFIX))
+        .collect();
+
+    for (key, raw) in overrides {
+        let path: Vec<String> = key[ENV_OVERRIDE_PREFIX.len()..]
+            .split("__")
+            .map(|part| part.to_lowercase())
+            .collect();
+
+        let override_value = toml::from_str::<toml::Value>(&format!("value = {}", raw))
+            .ok()
+            .and_then(|parsed| parsed.get("value").cloned())
+            .unwrap_or_else(|| toml::Value::String(raw.clone()));
+
+        let mut table = value
+            .as_table_mut()
+            .ok_or_else(|| ConfigError::Override(key.clone(), "not a table".to_string()))?;
+        let (last, sections) = path
+            .split_last()
+            .filter(|(last, _)| !last.is_empty())
+            .ok_or_else(|| ConfigError::Override(key.clone(), "empty key".to_string()))?;
+
+        for section in sections {
+            table = table
+                .entry(section.clone())
+                .or_insert_with(|| toml::Value::Table(toml::value::Table::new()))
+                .as_table_mut()
+                .ok_or_else(|| {
+                    ConfigError::Override(key.clone(), format!("{} is not a section", section))
+                })?;
+        }
+        table.insert(last.clone(), override_value);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(config_data: &str, vars: &[(&str, &str)]) -> Result<ServerConfig, ConfigError> {
+        let mut value = toml::from_str::<toml::Value>(config_data)
+            .map_err(|e| ConfigError::Parse("test".to_string(), e.to_string()))?;
+        apply_env_overrides(
+            &mut value,
+            vars.iter()
+                .map(|(key, value)| (key.to_string(), value.to_string())),
+        )?;
+        let config: ServerConfig = value
+            .try_into()
+            .map_err(|e: toml::de::Error| ConfigError::Parse("test".to_string(), e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    const CONFIG: &str = r#"
+        listen_addr = "0.0.0.0:50051"
+
+        [endpoints]
+        rpc = ["https://api.mainnet-beta.solana.com"]
+        ws = ["wss://api.mainnet-beta.solana.com"]
+
+        [commitment]
+        transaction = "finalized"
+
+        [store]
+        backend = "sqlite"
+        path = "sol-trace.db"
+    "#;
+
+    #[test]
+    fn config_file_with_defaults() {
+        let config = parse(CONFIG, &[]).unwrap();
+
+        assert_eq!(
+            "0.0.0.0:50051".parse::<SocketAddr>().unwrap(),
+            config.listen_addr()
+        );
+        assert_eq!(Commitment::Finalized, config.commitment.transaction);
+        assert_eq!(Commitment::Finalized, config.commitment.balances);
+        assert_eq!(ChannelsConfig::default(), config.channels);
+        assert_eq!(StoreBackend::Sqlite, config.store.backend());
+    }
+
+    #[test]
+    fn store_fallbacks() {
+        let fallbacks = |store: &str| {
+            let config_data = CONFIG.replace(
+                "backend = \"sqlite\"\n        path = \"sol-trace.db\"",
+                store,
+            );
+            let mut config = parse(&config_data, &[]).unwrap();
+            config.store.apply_fallbacks(
+                Some("tokens.db".to_string()),
+                Some("snapshots.db".to_string()),
+            );
+            config.store
+        };
+
+        // each store falls back to its own variable
+        let store = fallbacks("");
+        assert_eq!(StoreBackend::Sqlite, store.backend());
+        assert_eq!(Some("tokens.db"), store.token_path());
+        assert_eq!(Some("snapshots.db"), store.snapshot_path());
+
+        let store = fallbacks(r#"token_path = "sol-trace.db""#);
+        assert_eq!(Some("sol-trace.db"), store.token_path());
+        assert_eq!(Some("snapshots.db"), store.snapshot_path());
+
+        // a configured backend ignores the variables
+        let store = fallbacks(r#"backend = "memory""#);
+        assert_eq!(StoreBackend::Memory, store.backend());
+        assert_eq!(None, store.token_path());
+        assert_eq!(None, store.snapshot_path());
+    }
+
+    #[test]
+    fn env_overrides() {
+        let config = parse(
+            CONFIG,
+            &[
+                ("SOL_TRACE__LISTEN_ADDR", "127.0.0.1:6000"),
+                ("SOL_TRACE__CHANNELS__SUBSCRIPTION", "100"),
+                ("SOL_TRACE__WEBSOCKET__PING_INTERVAL_SECS", "5"),
+                (
+                    "SOL_TRACE__ENDPOINTS__RPC",
+                    r#"["https://a.rpc", "https://b.rpc"]"#,
+                ),
+                ("OTHER_VAR", "ignored"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!("127.0.0.1:6000", config.listen_addr);
+        assert_eq!(100, config.channels.subscription);
+        assert_eq!(5, config.websocket.ping_interval_secs);
+        assert_eq!(vec!["https://a.rpc", "https://b.rpc"], config.endpoints.rpc);
+    }
+
+    #[test]
+    fn invalid_config() {
+        let error = |vars: &[(&str, &str)]| parse(CONFIG, vars).unwrap_err().to_string();
+
+        assert!(error(&[("SOL_TRACE__LISTEN_ADDR", "localhost")]).contains("listen_addr"));
+        assert!(error(&[("SOL_TRACE__COMMITMENT__LOGS", "final")]).contains("final"));
+        assert!(error(&[("SOL_TRACE__CHANNELS__HISTORY", "0")]).contains("channel"));
+        assert!(
+            error(&[("SOL_TRACE__ENDPOINTS__BIRDEYE_RATE_LIMIT_PER_SEC", "0.0")])
+                .contains("rate limits")
+        );
+        assert!(error(&[("SOL_TRACE__ENDPOINTS__CONNECT_TIMEOUT_SECS", "0")]).contains("timeouts"));
+        assert!(error(&[("SOL_TRACE__SESSIONS__TTL_SECS", "0")]).contains("ttl"));
+        assert!(error(&[("SOL_TRACE__ENDPOINTS__WS", r#"["https://a.rpc"]"#)]).contains("ws/wss"));
+        assert!(error(&[("SOL_TRACE__STORE__PATHS", "a.db")]).contains("paths"));
+        assert!(
+            error(&[(
+                "SOL_TRACE__AUTH__API_KEYS",
+                r#"[{ principal = "a", key = "k" }, { principal = "b", key = "k" }]"#
+            )])
+            .contains("not unique")
+        );
+        assert!(error(&[("SOL_TRACE__TLS__CERT", "server.pem")]).contains("key"));
+        assert!(error(&[("SOL_TRACE__TLS__CLIENT_CA", "ca.pem")]).contains("TLS"));
+    }
+}
@@ -1,13 +1,15 @@
 use thiserror::Error;
 use tonic::{Code, Status};
 
-use crate::server::domain::TokenInfo;
+use crate::server::domain::{PortfolioSnapshot, TokenInfo};
 
 #[async_trait::async_trait]
 pub trait TokenStore {
     async fn add_token(&mut self, token: TokenInfo) -> Result<(), TokenStoreError>;
-    async fn get_token(&self, id: &String) -> Result<TokenInfo, TokenStoreError>;
-    async fn has_token(&self, id: &String) -> bool;
+    // replaces a stored token, e.g. once its mint info is known
+    async fn update_token(&mut self, token: TokenInfo) -> Result<(), TokenStoreError>;
+    async fn get_token(&self, id: &str) -> Result<TokenInfo, TokenStoreError>;
+    async fn has_token(&self, id: &str) -> bool;
 }
 
 #[derive(Debug, PartialEq, Error)]
@@ -36,3 +38,30 @@ impl From<TokenStoreError> for Status {
         }
     }
 }
+
+#[async_trait::async_trait]
+pub trait SnapshotStore {
+    async fn add_snapshot(&mut self, snapshot: PortfolioSnapshot)
+    -> Result<(), SnapshotStoreError>;
+    // the snapshots of the wallets taken between `from` and `to`, inclusive, oldest first
+    async fn get_snapshots(
+        &self,
+        wallets: &[String],
+        from: i64,
+        to: i64,
+    ) -> Result<Vec<PortfolioSnapshot>, SnapshotStoreError>;
+}
+
+#[derive(Debug, PartialEq, Error)]
+pub enum SnapshotStoreError {
+    #[error("Unexpected error")]
+    UnexpectedError,
+}
+
+impl From<SnapshotStoreError> for Status {
+    fn from(err: SnapshotStoreError) -> Self {
+        match err {
+            SnapshotStoreError::UnexpectedError => Status::new(Code::Unknown, "Unexpected error"),
+        }
+    }
+}
@@ -5,10 +5,16 @@ use tonic::Status;
 pub enum InputValidationError {
     #[error("Invalid wallet address")]
     InvalidWalletAddress,
+    #[error("Missing wallets")]
+    MissingWallets,
     #[error("Missing tokens")]
     MissingTokens,
     #[error("{0}")]
     InvalidTokenAddress(String),
+    #[error("Invalid alert value")]
+    InvalidAlertValue,
+    #[error("Invalid time range")]
+    InvalidTimeRange,
 }
 
 impl From<InputValidationError> for Status {
@@ -17,8 +23,15 @@ impl From<InputValidationError> for Status {
             InputValidationError::InvalidWalletAddress => {
                 Status::invalid_argument("Invalid wallet address")
             }
+            InputValidationError::MissingWallets => Status::invalid_argument("Missing wallets"),
             InputValidationError::MissingTokens => Status::invalid_argument("Missing tokens"),
             InputValidationError::InvalidTokenAddress(msg) => Status::invalid_argument(msg),
+            InputValidationError::InvalidAlertValue => {
+                Status::invalid_argument("Invalid alert value")
+            }
+            InputValidationError::InvalidTimeRange => {
+                Status::invalid_argument("Invalid time range")
+            }
         }
     }
 }
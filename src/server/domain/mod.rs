@@ -2,20 +2,26 @@ pub mod data_stores;
 pub mod errors;
 pub mod off_chain_rpc_client;
 pub mod on_chain_rpc_client;
+pub mod portfolio_snapshot;
 pub mod proto_ext;
 pub mod solana_api_messages;
 pub mod subscription_input;
+pub mod token_amount;
 pub mod token_info;
 pub mod token_price;
+pub mod wallet_event;
 pub mod ws_client;
 
 pub use data_stores::*;
 pub use errors::*;
 pub use off_chain_rpc_client::*;
 pub use on_chain_rpc_client::*;
+pub use portfolio_snapshot::*;
 pub use solana_api_messages::*;
 pub use subscription_input::*;
+pub use token_amount::*;
 pub use token_info::*;
 pub use token_price::*;
+pub use wallet_event::*;
 
 pub use ws_client::*;
@@ -15,4 +15,11 @@ pub trait OffChainRpcClient: Send + Sync {
         &self,
         tokens: Vec<String>,
     ) -> Result<HashMap<String, TokenPrice>, Box<dyn std::error::Error + Send + Sync>>;
+
+    // usd price of the token at the given unix timestamp, None if it is unknown
+    async fn get_price_at(
+        &self,
+        token: String,
+        timestamp: u64,
+    ) -> Result<Option<f64>, Box<dyn std::error::Error + Send + Sync>>;
 }
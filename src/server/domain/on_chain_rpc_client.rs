@@ -1,6 +1,9 @@
 use async_trait::async_trait;
 
-use crate::server::domain::{BalanceResponse, TokenAccountBalanceResponse, TransactionResponse};
+use crate::server::domain::{
+    AccountInfoResponse, BalanceResponse, RawAccount, SignaturesForAddressResponse,
+    TokenAccountBalanceResponse, TokenAccountsByOwnerResponse, TransactionResponse,
+};
 
 #[async_trait]
 pub trait OnChainRpcClient: Send + Sync {
@@ -18,4 +21,30 @@ pub trait OnChainRpcClient: Send + Sync {
         &self,
         pub_key: String,
     ) -> Result<BalanceResponse, Box<dyn std::error::Error + Send + Sync>>;
+
+    async fn get_signatures_for_address(
+        &self,
+        pub_key: String,
+        before: Option<String>,
+        until: Option<String>,
+        limit: usize,
+    ) -> Result<SignaturesForAddressResponse, Box<dyn std::error::Error + Send + Sync>>;
+
+    // token accounts of the owner under the given token program
+    async fn get_token_accounts_by_owner(
+        &self,
+        owner: String,
+        program_id: String,
+    ) -> Result<TokenAccountsByOwnerResponse, Box<dyn std::error::Error + Send + Sync>>;
+
+    async fn get_account_info(
+        &self,
+        pub_key: String,
+    ) -> Result<AccountInfoResponse, Box<dyn std::error::Error + Send + Sync>>;
+
+    // in the order of the keys, `None` for the missing accounts
+    async fn get_multiple_accounts(
+        &self,
+        pub_keys: Vec<String>,
+    ) -> Result<Vec<Option<RawAccount>>, Box<dyn std::error::Error + Send + Sync>>;
 }
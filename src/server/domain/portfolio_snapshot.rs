@@ -0,0 +1,10 @@
+use crate::proto::Holding;
+
+// the holdings of a wallet at a point in time
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortfolioSnapshot {
+    pub wallet: String,
+    // unix seconds
+    pub timestamp: i64,
+    pub holdings: Vec<Holding>,
+}
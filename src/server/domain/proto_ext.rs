@@ -1,33 +1,100 @@
+use std::fmt;
+
 use crate::{
-    proto::{Trade, Transfer},
-    server::utils::{fmt_token, fmt_usd},
+    proto::{
+        Activity, ActivityKind, Alert, AlertCondition, AlertEvent, ErrorEvent, ExecutionPath,
+        InitRequest, SolCosts, StatusEvent, SubscribeResponse, Trade, Transfer, Venue,
+        subscribe_response::Event,
+    },
+    server::{
+        domain::TokenAmount,
+        utils::{fmt_token, fmt_token_amount, fmt_usd},
+    },
 };
 
 impl Transfer {
-    pub fn new(mint: String, amount: f64) -> Self {
+    pub fn new(mint: String, amount: TokenAmount) -> Self {
+        let amount = amount.abs();
         Self {
             mint,
             symbol: None,
             name: None,
-            amount,
+            amount: amount.to_f64(),
             usd_price: None,
+            transfer_fee: None,
+            raw_amount: amount.unsigned_raw(),
+            decimals: amount.decimals as u32,
         }
     }
+
+    pub fn token_amount(&self) -> TokenAmount {
+        TokenAmount::new(self.raw_amount as i128, self.decimals as u8)
+    }
 }
 
-impl Transfer {
-    fn to_string(&self) -> String {
+impl InitRequest {
+    // `wallet` and `wallets` merged, without empty and duplicated entries
+    pub fn all_wallets(&self) -> Vec<String> {
+        let mut wallets: Vec<String> = vec![];
+        for wallet in std::iter::once(&self.wallet).chain(self.wallets.iter()) {
+            if !wallet.is_empty() && !wallets.contains(wallet) {
+                wallets.push(wallet.clone());
+            }
+        }
+        wallets
+    }
+}
+
+impl SubscribeResponse {
+    pub fn trade(trade: Trade) -> Self {
+        Self {
+            message: format!("Trade detected: {}", trade),
+            event: Some(Event::Trade(trade)),
+        }
+    }
+
+    pub fn activity(activity: Activity) -> Self {
+        Self {
+            message: format!("Activity detected: {}", activity),
+            event: Some(Event::Activity(activity)),
+        }
+    }
+
+    pub fn alert(alert_event: AlertEvent) -> Self {
+        Self {
+            message: format!("Alert triggered: {}", alert_event),
+            event: Some(Event::Alert(alert_event)),
+        }
+    }
+
+    pub fn status(message: String) -> Self {
+        Self {
+            message: message.clone(),
+            event: Some(Event::Status(StatusEvent { message })),
+        }
+    }
+
+    pub fn error(message: String) -> Self {
+        Self {
+            message: message.clone(),
+            event: Some(Event::Error(ErrorEvent { message })),
+        }
+    }
+}
+
+impl fmt::Display for Transfer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut token_info = String::new();
         if let Some(name) = &self.name {
-            token_info.push_str(&name);
+            token_info.push_str(name);
         }
         if let Some(symbol) = &self.symbol {
-            if token_info.len() > 0 {
-                token_info.push_str(" ");
+            if !token_info.is_empty() {
+                token_info.push(' ');
             }
             token_info.push_str(&format!("[{symbol}]"));
         }
-        if token_info.len() == 0 {
+        if token_info.is_empty() {
             token_info.push_str(&self.mint[0..4]);
             token_info.push_str("...");
             token_info.push_str(&self.mint[&self.mint.len() - 4..]);
@@ -39,18 +106,154 @@ impl Transfer {
             ("N/A".to_string(), "N/A".to_string())
         };
 
-        format!(
-            "  {} Amount: {} Current Value: {} (Price: {})",
+        write!(
+            f,
+            "  {} Amount: {} Value: {} (Price: {})",
             token_info,
-            fmt_token(self.amount),
+            fmt_token_amount(&self.token_amount()),
             value_str,
             price_str,
+        )?;
+
+        if let Some(transfer_fee) = self.transfer_fee {
+            write!(f, " Transfer fee: {}", fmt_token(transfer_fee))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for ExecutionPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecutionPath::Direct => write!(f, "direct"),
+            ExecutionPath::Relayed => write!(f, "relayed"),
+            ExecutionPath::KeeperFilled => write!(f, "keeper-filled"),
+        }
+    }
+}
+
+impl fmt::Display for SolCosts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Fee: {} + {} priority",
+            fmt_token(self.base_fee),
+            fmt_token(self.priority_fee)
+        )?;
+
+        if self.tip > 0.0 {
+            write!(f, " Tip: {}", fmt_token(self.tip))?;
+        }
+        if self.rent_created > 0.0 {
+            write!(f, " Rent paid: {}", fmt_token(self.rent_created))?;
+        }
+        if self.rent_reclaimed > 0.0 {
+            write!(f, " Rent reclaimed: {}", fmt_token(self.rent_reclaimed))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for AlertCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlertCondition::PriceAbove => write!(f, "price above"),
+            AlertCondition::PriceBelow => write!(f, "price below"),
+            AlertCondition::Change24hUp => write!(f, "24h change up"),
+            AlertCondition::Change24hDown => write!(f, "24h change down"),
+        }
+    }
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match self.condition() {
+            AlertCondition::PriceAbove | AlertCondition::PriceBelow => fmt_usd(self.value),
+            AlertCondition::Change24hUp | AlertCondition::Change24hDown => {
+                format!("{}%", self.value)
+            }
+        };
+
+        write!(
+            f,
+            "#{} {} {} {}{}",
+            self.id,
+            self.mint,
+            self.condition(),
+            value,
+            if self.repeat { " (repeat)" } else { "" }
         )
     }
 }
 
-impl Trade {
-    pub fn to_string(&self) -> String {
+impl fmt::Display for AlertEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(alert) = &self.alert {
+            write!(f, "{} ", alert)?;
+        }
+
+        write!(f, "Price: {}", fmt_usd(self.usd_price))?;
+
+        if let Some(price_change_24h) = self.price_change_24h {
+            write!(f, " 24h: {:.2}%", price_change_24h)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Venue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Venue::UnknownVenue => write!(f, "unknown"),
+            Venue::Jupiter => write!(f, "Jupiter"),
+            Venue::RaydiumAmm => write!(f, "Raydium AMM"),
+            Venue::RaydiumClmm => write!(f, "Raydium CLMM"),
+            Venue::RaydiumCpmm => write!(f, "Raydium CPMM"),
+            Venue::OrcaWhirlpool => write!(f, "Orca Whirlpool"),
+            Venue::MeteoraDlmm => write!(f, "Meteora DLMM"),
+            Venue::MeteoraPools => write!(f, "Meteora Pools"),
+            Venue::PumpFun => write!(f, "Pump.fun"),
+            Venue::PumpSwap => write!(f, "PumpSwap"),
+            Venue::Phoenix => write!(f, "Phoenix"),
+            Venue::Openbook => write!(f, "OpenBook"),
+        }
+    }
+}
+
+impl fmt::Display for ActivityKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActivityKind::TransferIn => write!(f, "Transfer In"),
+            ActivityKind::TransferOut => write!(f, "Transfer Out"),
+            ActivityKind::Airdrop => write!(f, "Airdrop"),
+            ActivityKind::AccountClose => write!(f, "Account Close"),
+            ActivityKind::Stake => write!(f, "Stake"),
+            ActivityKind::Unstake => write!(f, "Unstake"),
+        }
+    }
+}
+
+impl fmt::Display for Activity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut res = format!("{}\t", self.kind());
+
+        if let Some(counterparty) = &self.counterparty {
+            res.push_str(&format!("Counterparty: {}\t", counterparty));
+        }
+
+        for item in self.outgoing.iter().chain(self.incoming.iter()) {
+            res.push_str(&format!("\n{}", item));
+        }
+
+        write!(f, "{}", res)
+    }
+}
+
+impl fmt::Display for Trade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut res = String::new();
 
         if self.from.len() == 1 && self.to.len() == 1 {
@@ -63,6 +266,14 @@ impl Trade {
             res.push_str("Multi Swap\t");
         }
 
+        if self.venue() != Venue::UnknownVenue {
+            res.push_str(&format!("on {}\t", self.venue()));
+        }
+
+        if self.execution_path() != ExecutionPath::Direct {
+            res.push_str(&format!("({})\t", self.execution_path()));
+        }
+
         res.push_str("\nFrom:\t");
 
         for item in self.from.iter() {
@@ -75,6 +286,10 @@ impl Trade {
             res.push_str(item.to_string().as_str());
         }
 
-        res
+        if let Some(sol_costs) = &self.sol_costs {
+            res.push_str(&format!("\nSOL:\t{}", sol_costs));
+        }
+
+        write!(f, "{}", res)
     }
 }
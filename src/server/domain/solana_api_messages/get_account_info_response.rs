@@ -0,0 +1,138 @@
+use serde::Deserialize;
+
+use crate::server::domain::ErrorResponse;
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum GetAccountInfoResponse {
+    AccountInfo(AccountInfoResponse),
+    Error(ErrorResponse),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AccountInfoResponse {
+    pub result: Option<AccountInfoResult>,
+    pub id: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AccountInfoResult {
+    pub value: Option<AccountInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AccountInfo {
+    // the owning program, the token program in case of a mint
+    pub owner: String,
+    pub data: AccountData,
+}
+
+// jsonParsed encoding falls back to base64 for accounts the node can not parse
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum AccountData {
+    Mint(ParsedMintData),
+    Raw(serde_json::Value),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ParsedMintData {
+    pub parsed: ParsedMint,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ParsedMint {
+    pub info: MintAccountInfo,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MintAccountInfo {
+    pub decimals: u8,
+    // Token-2022 only
+    #[serde(default)]
+    pub extensions: Vec<MintExtension>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MintExtension {
+    pub extension: String,
+    #[serde(default)]
+    pub state: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TransferFeeConfig {
+    #[serde(rename = "newerTransferFee")]
+    pub newer_transfer_fee: TransferFeeState,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TransferFeeState {
+    pub epoch: u64,
+    #[serde(rename = "maximumFee")]
+    pub maximum_fee: u64,
+    #[serde(rename = "transferFeeBasisPoints")]
+    pub transfer_fee_basis_points: u16,
+}
+
+impl AccountInfo {
+    // the newer fee is used regardless of its activation epoch, they only differ
+    // for the two epochs following a fee change
+    pub fn transfer_fee_config(&self) -> Option<TransferFeeConfig> {
+        let AccountData::Mint(data) = &self.data else {
+            return None;
+        };
+
+        data.parsed
+            .info
+            .extensions
+            .iter()
+            .find(|extension| extension.extension == "transferFeeConfig")
+            .and_then(|extension| serde_json::from_value(extension.state.clone()).ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transfer_fee_config_of_token_2022_mint() {
+        let json = r#"{"jsonrpc":"2.0","id":1,"result":{"context":{"slot":1},"value":{
+            "owner":"TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb","lamports":1,"executable":false,
+            "data":{"program":"spl-token-2022","space":278,"parsed":{"type":"mint","info":{
+                "decimals":6,"supply":"1000","isInitialized":true,
+                "extensions":[{"extension":"transferFeeConfig","state":{
+                    "newerTransferFee":{"epoch":600,"maximumFee":5000000,"transferFeeBasisPoints":50},
+                    "olderTransferFee":{"epoch":500,"maximumFee":5000000,"transferFeeBasisPoints":50},
+                    "withheldAmount":0}}]}}}}}}"#;
+
+        let GetAccountInfoResponse::AccountInfo(response) = serde_json::from_str(json).unwrap()
+        else {
+            panic!("not an account info response");
+        };
+        let account_info = response.result.unwrap().value.unwrap();
+        let config = account_info.transfer_fee_config().unwrap();
+
+        assert_eq!(50, config.newer_transfer_fee.transfer_fee_basis_points);
+        assert_eq!(5_000_000, config.newer_transfer_fee.maximum_fee);
+
+        let json = r#"{"jsonrpc":"2.0","id":1,"result":{"context":{"slot":1},"value":{
+            "owner":"11111111111111111111111111111111","lamports":1,"executable":false,
+            "data":["","base64"]}}}"#;
+
+        let GetAccountInfoResponse::AccountInfo(response) = serde_json::from_str(json).unwrap()
+        else {
+            panic!("not an account info response");
+        };
+        assert!(
+            response
+                .result
+                .unwrap()
+                .value
+                .unwrap()
+                .transfer_fee_config()
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde::Deserialize;
+use spl_token::{solana_program::program_pack::Pack, state::Account};
+
+use crate::server::{
+    domain::{Context, ErrorResponse},
+    utils::constants::{TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID},
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum GetMultipleAccountsResponse {
+    Accounts(MultipleAccountsResponse),
+    Error(ErrorResponse),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MultipleAccountsResponse {
+    pub result: Option<MultipleAccountsResult>,
+    pub id: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MultipleAccountsResult {
+    pub context: Context,
+    // in the order of the requested keys, null for the missing accounts
+    pub value: Vec<Option<RawAccount>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RawAccount {
+    pub lamports: u64,
+    pub owner: String,
+    // [data, encoding]
+    pub data: (String, String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenAccountState {
+    pub mint: String,
+    pub owner: String,
+    pub amount: u64,
+}
+
+impl RawAccount {
+    // Token-2022 accounts share the SPL Token layout, the extensions follow it
+    pub fn token_account_state(&self) -> Option<TokenAccountState> {
+        if self.owner != TOKEN_PROGRAM_ID && self.owner != TOKEN_2022_PROGRAM_ID {
+            return None;
+        }
+        if self.data.1 != "base64" {
+            return None;
+        }
+
+        let data = STANDARD.decode(&self.data.0).ok()?;
+        let account = Account::unpack_from_slice(data.get(..Account::LEN)?).ok()?;
+
+        Some(TokenAccountState {
+            mint: account.mint.to_string(),
+            owner: account.owner.to_string(),
+            amount: account.amount,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use spl_token::{solana_program::pubkey::Pubkey, state::AccountState};
+
+    use super::*;
+    use crate::server::utils::constants::test::solana_data::{
+        SPL_TOKEN_ACCOUNT_DATA, TOKEN_2022_ACCOUNT_DATA, TOKEN_2022_MINT, TOKEN1, WALLET,
+    };
+
+    fn raw_account(owner: &str, extensions: &[u8]) -> RawAccount {
+        let account = Account {
+            mint: TOKEN1.parse::<Pubkey>().unwrap(),
+            owner: WALLET.parse::<Pubkey>().unwrap(),
+            amount: 1_234_567,
+            state: AccountState::Initialized,
+            ..Default::default()
+        };
+        let mut data = vec![0; Account::LEN];
+        Account::pack(account, &mut data).unwrap();
+        data.extend_from_slice(extensions);
+
+        RawAccount {
+            lamports: 2_039_280,
+            owner: owner.to_string(),
+            data: (STANDARD.encode(data), "base64".to_string()),
+        }
+    }
+
+    #[test]
+    fn decode_token_account() {
+        let expected = TokenAccountState {
+            mint: TOKEN1.to_string(),
+            owner: WALLET.to_string(),
+            amount: 1_234_567,
+        };
+
+        assert_eq!(
+            Some(expected.clone()),
+            raw_account(TOKEN_PROGRAM_ID, &[]).token_account_state()
+        );
+        assert_eq!(
+            Some(expected),
+            raw_account(TOKEN_2022_PROGRAM_ID, &[2, 0, 0, 0]).token_account_state()
+        );
+    }
+
+    #[test]
+    fn decode_token_account_data() {
+        let json = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"result":{{"context":{{"slot":1}},"value":[
+            {{"owner":"{}","lamports":2039280,"executable":false,"rentEpoch":0,"space":165,
+             "data":["{}","base64"]}},
+            {{"owner":"{}","lamports":2157600,"executable":false,"rentEpoch":0,"space":182,
+             "data":["{}","base64"]}}]}}}}"#,
+            TOKEN_PROGRAM_ID,
+            SPL_TOKEN_ACCOUNT_DATA,
+            TOKEN_2022_PROGRAM_ID,
+            TOKEN_2022_ACCOUNT_DATA
+        );
+
+        let GetMultipleAccountsResponse::Accounts(response) = serde_json::from_str(&json).unwrap()
+        else {
+            panic!("not a multiple accounts response");
+        };
+        let states: Vec<_> = response
+            .result
+            .unwrap()
+            .value
+            .into_iter()
+            .map(|account| account.and_then(|account| account.token_account_state()))
+            .collect();
+
+        assert_eq!(
+            vec![
+                Some(TokenAccountState {
+                    mint: TOKEN1.to_string(),
+                    owner: WALLET.to_string(),
+                    amount: 123_456_789,
+                }),
+                Some(TokenAccountState {
+                    mint: TOKEN_2022_MINT.to_string(),
+                    owner: WALLET.to_string(),
+                    amount: 2_500_000,
+                }),
+            ],
+            states
+        );
+    }
+
+    #[test]
+    fn decode_non_token_account() {
+        let json = r#"{"jsonrpc":"2.0","id":1,"result":{"context":{"slot":1},"value":[
+            {"owner":"11111111111111111111111111111111","lamports":1500000000,"executable":false,
+             "rentEpoch":0,"space":0,"data":["","base64"]},
+            null]}}"#;
+
+        let GetMultipleAccountsResponse::Accounts(response) = serde_json::from_str(json).unwrap()
+        else {
+            panic!("not a multiple accounts response");
+        };
+        let accounts = response.result.unwrap().value;
+
+        assert_eq!(2, accounts.len());
+        let wallet = accounts[0].as_ref().unwrap();
+        assert_eq!(1_500_000_000, wallet.lamports);
+        assert!(wallet.token_account_state().is_none());
+        assert!(accounts[1].is_none());
+    }
+}
@@ -0,0 +1,25 @@
+use serde::Deserialize;
+
+use crate::server::domain::ErrorResponse;
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum GetSignaturesForAddressResponse {
+    Signatures(SignaturesForAddressResponse),
+    Error(ErrorResponse),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SignaturesForAddressResponse {
+    pub result: Vec<SignatureInfo>,
+    pub id: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SignatureInfo {
+    pub signature: String,
+    pub slot: u64,
+    pub err: Option<serde_json::Value>,
+    #[serde(rename = "blockTime")]
+    pub block_time: Option<u64>,
+}
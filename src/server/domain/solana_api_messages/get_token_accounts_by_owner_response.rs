@@ -0,0 +1,51 @@
+use serde::Deserialize;
+
+use crate::server::domain::{ErrorResponse, UiTokenAmount};
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum GetTokenAccountsByOwnerResponse {
+    Accounts(TokenAccountsByOwnerResponse),
+    Error(ErrorResponse),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TokenAccountsByOwnerResponse {
+    pub result: Option<TokenAccountsByOwnerResult>,
+    pub id: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TokenAccountsByOwnerResult {
+    pub value: Vec<TokenAccount>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TokenAccount {
+    pub pubkey: String,
+    pub account: TokenAccountData,
+}
+
+// jsonParsed encoding of the account data
+#[derive(Debug, Deserialize)]
+pub struct TokenAccountData {
+    pub data: ParsedTokenAccountData,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ParsedTokenAccountData {
+    pub parsed: ParsedTokenAccount,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ParsedTokenAccount {
+    pub info: TokenAccountInfo,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TokenAccountInfo {
+    pub mint: String,
+    pub owner: String,
+    #[serde(rename = "tokenAmount")]
+    pub token_amount: UiTokenAmount,
+}
@@ -1,11 +1,11 @@
 use serde::Deserialize;
 
-use crate::server::domain::ErrorResponse;
+use crate::server::domain::{ErrorResponse, TokenAmount};
 
 #[derive(Debug, Deserialize)]
 #[serde(untagged)]
 pub enum GetTransactionResponse {
-    Transaction(TransactionResponse),
+    Transaction(Box<TransactionResponse>),
     Error(ErrorResponse),
 }
 
@@ -35,6 +35,78 @@ pub struct EncodedTransaction {
 pub struct TransactionMessage {
     #[serde(rename = "accountKeys")]
     pub account_keys: Vec<String>,
+    #[serde(default)]
+    pub header: MessageHeader,
+    #[serde(default)]
+    pub instructions: Vec<CompiledInstruction>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompiledInstruction {
+    // index into the account keys, loaded addresses included
+    #[serde(rename = "programIdIndex")]
+    pub program_id_index: usize,
+}
+
+// instructions invoked by the top level instruction at `index`
+#[derive(Debug, Deserialize)]
+pub struct InnerInstructions {
+    pub index: usize,
+    pub instructions: Vec<CompiledInstruction>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct MessageHeader {
+    // the first account keys are the signers, the fee payer is the first one
+    #[serde(rename = "numRequiredSignatures")]
+    pub num_required_signatures: usize,
+}
+
+// accounts loaded from address lookup tables by v0 transactions
+#[derive(Debug, Default, Deserialize)]
+pub struct LoadedAddresses {
+    pub writable: Vec<String>,
+    pub readonly: Vec<String>,
+}
+
+impl TransactionResult {
+    // static keys followed by the loaded ones, in the order of the balances in the meta
+    pub fn account_keys(&self) -> Vec<&String> {
+        let mut account_keys: Vec<&String> = self.transaction.message.account_keys.iter().collect();
+
+        if let Some(loaded_addresses) = self.meta.as_ref().map(|meta| &meta.loaded_addresses) {
+            account_keys.extend(loaded_addresses.writable.iter());
+            account_keys.extend(loaded_addresses.readonly.iter());
+        }
+
+        account_keys
+    }
+
+    // programs in the order of execution, the inner instructions follow their parent
+    pub fn invoked_programs(&self) -> Vec<&String> {
+        let account_keys = self.account_keys();
+        let inner_instructions = self
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.inner_instructions.as_ref());
+
+        let mut programs: Vec<&String> = vec![];
+        for (index, instruction) in self.transaction.message.instructions.iter().enumerate() {
+            let inner = inner_instructions
+                .into_iter()
+                .flatten()
+                .filter(|inner| inner.index == index)
+                .flat_map(|inner| inner.instructions.iter());
+
+            for instruction in std::iter::once(instruction).chain(inner) {
+                if let Some(program) = account_keys.get(instruction.program_id_index) {
+                    programs.push(program);
+                }
+            }
+        }
+
+        programs
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -53,11 +125,19 @@ pub struct TransactionMeta {
 
     #[serde(rename = "postTokenBalances")]
     pub post_token_balances: Vec<TokenBalance>,
+
+    #[serde(rename = "loadedAddresses", default)]
+    pub loaded_addresses: LoadedAddresses,
+
+    // null when the transaction predates inner instruction recording
+    #[serde(rename = "innerInstructions", default)]
+    pub inner_instructions: Option<Vec<InnerInstructions>>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct TokenBalance {
-    //pub account_index: u64,
+    #[serde(rename = "accountIndex")]
+    pub account_index: usize,
     pub mint: String,
     pub owner: Option<String>,
     #[serde(rename = "uiTokenAmount")]
@@ -75,9 +155,19 @@ pub struct UiTokenAmount {
 }
 
 impl UiTokenAmount {
+    // None if the node sent an amount that is not a raw u64
+    pub fn to_token_amount(&self) -> Option<TokenAmount> {
+        match self.amount.parse::<u64>() {
+            Ok(raw) => Some(TokenAmount::new(raw as i128, self.decimals)),
+            Err(e) => {
+                tracing::warn!("Invalid token amount {:?}: {}", self.amount, e);
+                None
+            }
+        }
+    }
+
     pub fn to_f64(&self) -> f64 {
-        let raw: u64 = self.amount.parse().unwrap_or(0);
-        raw as f64 / 10f64.powi(self.decimals as i32)
+        self.to_token_amount().map_or(0.0, |amount| amount.to_f64())
     }
 }
 
@@ -123,3 +213,23 @@ pub struct BalanceResult {
     pub context: Context,
     pub value: u64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unparsable_token_amount() {
+        let amount = |amount: &str| UiTokenAmount {
+            decimals: 6,
+            amount: amount.to_string(),
+        };
+
+        assert_eq!(
+            Some(TokenAmount::new(2_500_000, 6)),
+            amount("2500000").to_token_amount()
+        );
+        assert_eq!(None, amount("2.5").to_token_amount());
+        assert_eq!(None, amount("").to_token_amount());
+    }
+}
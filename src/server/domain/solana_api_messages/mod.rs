@@ -1,7 +1,15 @@
 pub mod error_response;
+pub mod get_account_info_response;
+pub mod get_multiple_accounts_response;
+pub mod get_signatures_for_address_response;
+pub mod get_token_accounts_by_owner_response;
 pub mod get_transaction_response;
 pub mod logs_subscription;
 
 pub use error_response::*;
+pub use get_account_info_response::*;
+pub use get_multiple_accounts_response::*;
+pub use get_signatures_for_address_response::*;
+pub use get_token_accounts_by_owner_response::*;
 pub use get_transaction_response::*;
 pub use logs_subscription::*;
@@ -0,0 +1,107 @@
+use std::{
+    fmt,
+    ops::{AddAssign, Neg, SubAssign},
+};
+
+// exact amount in the smallest unit of the token, negative for outgoing balance changes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenAmount {
+    pub raw: i128,
+    pub decimals: u8,
+}
+
+impl TokenAmount {
+    pub fn new(raw: i128, decimals: u8) -> Self {
+        Self { raw, decimals }
+    }
+
+    // lossy, for valuation only
+    pub fn to_f64(&self) -> f64 {
+        self.raw as f64 / 10f64.powi(self.decimals as i32)
+    }
+
+    pub fn abs(&self) -> Self {
+        Self::new(self.raw.abs(), self.decimals)
+    }
+
+    // balances and transfers of a single token fit in the u64 supply
+    pub fn unsigned_raw(&self) -> u64 {
+        self.raw.unsigned_abs().min(u64::MAX as u128) as u64
+    }
+
+    pub fn is_positive(&self) -> bool {
+        self.raw > 0
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.raw < 0
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.raw == 0
+    }
+}
+
+impl AddAssign for TokenAmount {
+    fn add_assign(&mut self, other: Self) {
+        debug_assert!(self.raw == 0 || self.decimals == other.decimals);
+        self.raw += other.raw;
+        self.decimals = other.decimals;
+    }
+}
+
+impl SubAssign for TokenAmount {
+    fn sub_assign(&mut self, other: Self) {
+        *self += -other;
+    }
+}
+
+impl Neg for TokenAmount {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.raw, self.decimals)
+    }
+}
+
+// the exact decimal, without trailing zeros
+impl fmt::Display for TokenAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.is_negative() { "-" } else { "" };
+        let denom = 10u128.pow(self.decimals as u32);
+        let int_part = self.raw.unsigned_abs() / denom;
+        let frac_part = self.raw.unsigned_abs() % denom;
+
+        if frac_part == 0 {
+            return write!(f, "{}{}", sign, int_part);
+        }
+
+        let frac_str = format!("{:0width$}", frac_part, width = self.decimals as usize);
+        write!(f, "{}{}.{}", sign, int_part, frac_str.trim_end_matches('0'))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_decimal() {
+        // 10^15 raw units of a 9 decimals token plus dust, beyond the f64 precision
+        let amount = TokenAmount::new(1_000_000_000_000_001, 9);
+        assert_eq!("1000000.000000001", amount.to_string());
+        assert_eq!("-0.5", TokenAmount::new(-500_000, 6).to_string());
+        assert_eq!("42", TokenAmount::new(42, 0).to_string());
+    }
+
+    #[test]
+    fn balance_change() {
+        let mut change = TokenAmount::default();
+        change -= TokenAmount::new(5_000_000, 6);
+        change += TokenAmount::new(2_000_001, 6);
+
+        assert_eq!(TokenAmount::new(-2_999_999, 6), change);
+        assert!(change.is_negative());
+        assert_eq!(2_999_999, change.unsigned_raw());
+    }
+}
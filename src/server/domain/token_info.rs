@@ -1,10 +1,109 @@
 use serde::Deserialize;
 
-#[derive(Clone, Debug, Deserialize, PartialEq)]
+use crate::server::domain::TokenAmount;
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
 pub struct TokenInfo {
     pub id: String,
     pub name: String,
     pub symbol: String,
     pub icon: Option<String>,
     pub decimals: u8,
+    // read from the mint account, not from the token API
+    #[serde(skip)]
+    pub token_program: Option<String>,
+    #[serde(skip)]
+    pub transfer_fee: Option<TransferFee>,
+}
+
+// Token-2022 transfer fee extension, the fee is withheld from the transferred amount
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransferFee {
+    pub basis_points: u16,
+    pub maximum_fee: u64,
+}
+
+const MAX_BASIS_POINTS: u128 = 10_000;
+
+impl TransferFee {
+    // fee withheld when `amount` is sent
+    pub fn calc_fee(&self, amount: u64) -> u64 {
+        if self.basis_points == 0 || amount == 0 {
+            return 0;
+        }
+        let fee = (amount as u128 * self.basis_points as u128).div_ceil(MAX_BASIS_POINTS);
+        fee.min(self.maximum_fee as u128) as u64
+    }
+
+    // fee withheld when `net_amount` is received
+    pub fn calc_inverse_fee(&self, net_amount: u64) -> u64 {
+        if self.basis_points == 0 || net_amount == 0 {
+            return 0;
+        }
+        if self.basis_points as u128 >= MAX_BASIS_POINTS {
+            return self.maximum_fee;
+        }
+        let gross = (net_amount as u128 * MAX_BASIS_POINTS)
+            .div_ceil(MAX_BASIS_POINTS - self.basis_points as u128);
+        let fee = gross - net_amount as u128;
+        fee.min(self.maximum_fee as u128) as u64
+    }
+
+    // fee of a balance change: a received amount is already net of the fee
+    pub fn calc_fee_for_change(&self, change: &TokenAmount) -> TokenAmount {
+        let amount = change.unsigned_raw();
+        let fee = if change.is_positive() {
+            self.calc_inverse_fee(amount)
+        } else {
+            self.calc_fee(amount)
+        };
+        TokenAmount::new(fee as i128, change.decimals)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transfer_fee_is_capped() {
+        let transfer_fee = TransferFee {
+            basis_points: 100,
+            maximum_fee: 5_000,
+        };
+
+        assert_eq!(100, transfer_fee.calc_fee(10_000));
+        assert_eq!(1, transfer_fee.calc_fee(1));
+        assert_eq!(5_000, transfer_fee.calc_fee(1_000_000));
+    }
+
+    #[test]
+    fn inverse_transfer_fee() {
+        let transfer_fee = TransferFee {
+            basis_points: 100,
+            maximum_fee: 5_000,
+        };
+
+        assert_eq!(100, transfer_fee.calc_inverse_fee(9_900));
+        assert_eq!(5_000, transfer_fee.calc_inverse_fee(1_000_000));
+    }
+
+    #[test]
+    fn transfer_fee_for_balance_change() {
+        let transfer_fee = TransferFee {
+            basis_points: 100,
+            maximum_fee: u64::MAX,
+        };
+
+        // 99 received from 100 sent, 100 sent of which 1 is withheld
+        let fee = TokenAmount::new(1_000_000, 6);
+        assert_eq!(
+            fee,
+            transfer_fee.calc_fee_for_change(&TokenAmount::new(99_000_000, 6))
+        );
+        assert_eq!(
+            fee,
+            transfer_fee.calc_fee_for_change(&TokenAmount::new(-100_000_000, 6))
+        );
+    }
 }
@@ -10,3 +10,16 @@ pub struct TokenPrice {
     #[serde(rename = "priceChange24h")]
     pub price_change_24h: Option<f64>,
 }
+
+#[derive(Debug, Deserialize)]
+pub struct HistoricalPriceResponse {
+    pub data: Option<HistoricalPrice>,
+    pub success: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct HistoricalPrice {
+    pub value: f64,
+    #[serde(rename = "updateUnixTime")]
+    pub update_unix_time: u64,
+}
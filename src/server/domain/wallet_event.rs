@@ -0,0 +1,38 @@
+use crate::proto::{Activity, GetTradeHistoryResponse, GetTradeResponse, Trade};
+
+// what a transaction meant for the wallet
+#[derive(Clone, Debug)]
+pub enum WalletEvent {
+    Trade(Trade),
+    Activity(Activity),
+}
+
+impl From<WalletEvent> for GetTradeResponse {
+    fn from(event: WalletEvent) -> Self {
+        match event {
+            WalletEvent::Trade(trade) => Self {
+                trade: Some(trade),
+                activity: None,
+            },
+            WalletEvent::Activity(activity) => Self {
+                trade: None,
+                activity: Some(activity),
+            },
+        }
+    }
+}
+
+impl From<WalletEvent> for GetTradeHistoryResponse {
+    fn from(event: WalletEvent) -> Self {
+        match event {
+            WalletEvent::Trade(trade) => Self {
+                trade: Some(trade),
+                activity: None,
+            },
+            WalletEvent::Activity(activity) => Self {
+                trade: None,
+                activity: Some(activity),
+            },
+        }
+    }
+}
@@ -8,7 +8,10 @@ use crate::{
     proto::SubscribeResponse,
     server::{
         domain::SubscriptionInput,
-        states::app_state::{OffChainRpcClientType, OnChainRpcClientType, TokenStoreType},
+        states::{
+            EventFilterType, TradeLedgerType,
+            app_state::{OffChainRpcClientType, OnChainRpcClientType, TokenStoreType},
+        },
     },
 };
 
@@ -16,12 +19,15 @@ pub type WSCResult<T> = std::result::Result<T, Box<dyn std::error::Error + Send
 
 #[async_trait]
 pub trait WebSocketClient {
+    #[allow(clippy::too_many_arguments)]
     async fn logs_subscribe(
         &mut self,
         subscription_input: Arc<SubscriptionInput>,
         off_chain_rpc_client: OffChainRpcClientType,
         tokens_tore: TokenStoreType,
         on_chain_rpc_client: OnChainRpcClientType,
+        trade_ledger: TradeLedgerType,
+        event_filter: EventFilterType,
         tx: mpsc::Sender<Result<SubscribeResponse, Status>>,
     ) -> WSCResult<u64>;
 
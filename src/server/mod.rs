@@ -1,3 +1,5 @@
+pub mod auth;
+pub mod config;
 pub mod domain;
 pub mod services;
 pub mod states;
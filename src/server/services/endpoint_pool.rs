@@ -0,0 +1,179 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::server::utils::constants::{ENDPOINT_COOLDOWN_BASE_SECS, ENDPOINT_COOLDOWN_MAX_SECS};
+
+// weight of the newest sample in the latency average
+const LATENCY_SMOOTHING: f64 = 0.3;
+
+#[derive(Default)]
+struct EndpointHealth {
+    // moving average, unknown until the first success
+    latency_ms: Option<f64>,
+    consecutive_failures: u32,
+    cooldown_until: Option<Instant>,
+}
+
+struct Endpoint {
+    url: String,
+    health: Mutex<EndpointHealth>,
+}
+
+// the endpoints of a provider kind (RPC or WebSocket), ranked by health and latency
+pub struct EndpointPool {
+    endpoints: Vec<Endpoint>,
+}
+
+impl EndpointPool {
+    pub fn new(urls: Vec<String>) -> Self {
+        Self {
+            endpoints: urls
+                .into_iter()
+                .map(|url| Endpoint {
+                    url,
+                    health: Mutex::new(EndpointHealth::default()),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn urls(&self) -> Vec<String> {
+        self.endpoints
+            .iter()
+            .map(|endpoint| endpoint.url.clone())
+            .collect()
+    }
+
+    // the order to try the endpoints in: the healthy ones by latency (untried first),
+    // then the ones cooling down by the end of their cooldown
+    pub fn ranked(&self) -> Vec<String> {
+        self.ranked_at(Instant::now())
+    }
+
+    fn ranked_at(&self, now: Instant) -> Vec<String> {
+        let mut ranked: Vec<(Option<Instant>, f64, usize)> = self
+            .endpoints
+            .iter()
+            .enumerate()
+            .map(|(index, endpoint)| {
+                let health = endpoint.health.lock().unwrap_or_else(|e| e.into_inner());
+                let cooldown_until = health.cooldown_until.filter(|until| *until > now);
+                (cooldown_until, health.latency_ms.unwrap_or(0.0), index)
+            })
+            .collect();
+
+        ranked.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)).then(a.2.cmp(&b.2)));
+
+        ranked
+            .into_iter()
+            .map(|(_, _, index)| self.endpoints[index].url.clone())
+            .collect()
+    }
+
+    pub fn record_success(&self, url: &str, latency: Duration) {
+        if let Some(mut health) = self.health(url) {
+            let sample = latency.as_secs_f64() * 1_000.0;
+            health.latency_ms = Some(match health.latency_ms {
+                Some(latency_ms) => latency_ms + LATENCY_SMOOTHING * (sample - latency_ms),
+                None => sample,
+            });
+            health.consecutive_failures = 0;
+            health.cooldown_until = None;
+        }
+    }
+
+    // errors and rate limits put the endpoint on an increasing cooldown
+    pub fn record_failure(&self, url: &str) {
+        self.record_failure_at(url, Instant::now());
+    }
+
+    fn record_failure_at(&self, url: &str, now: Instant) {
+        if let Some(mut health) = self.health(url) {
+            health.consecutive_failures += 1;
+            let cooldown = cooldown(health.consecutive_failures);
+            health.cooldown_until = Some(now + cooldown);
+            tracing::warn!("Endpoint {} is cooling down for {:?}", url, cooldown);
+        }
+    }
+
+    fn health(&self, url: &str) -> Option<std::sync::MutexGuard<'_, EndpointHealth>> {
+        self.endpoints
+            .iter()
+            .find(|endpoint| endpoint.url == url)
+            .map(|endpoint| endpoint.health.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+fn cooldown(consecutive_failures: u32) -> Duration {
+    let secs = ENDPOINT_COOLDOWN_BASE_SECS
+        .saturating_mul(1 << consecutive_failures.saturating_sub(1).min(16));
+    Duration::from_secs(secs.min(ENDPOINT_COOLDOWN_MAX_SECS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRIMARY: &str = "https://primary.rpc";
+    const BACKUP: &str = "https://backup.rpc";
+    const SPARE: &str = "https://spare.rpc";
+
+    fn pool() -> EndpointPool {
+        EndpointPool::new(vec![
+            PRIMARY.to_string(),
+            BACKUP.to_string(),
+            SPARE.to_string(),
+        ])
+    }
+
+    #[test]
+    fn ranked_by_latency() {
+        let pool = pool();
+        assert_eq!(vec![PRIMARY, BACKUP, SPARE], pool.ranked());
+
+        pool.record_success(PRIMARY, Duration::from_millis(300));
+        pool.record_success(BACKUP, Duration::from_millis(100));
+        // not tried yet, it's probed first
+        assert_eq!(vec![SPARE, BACKUP, PRIMARY], pool.ranked());
+
+        pool.record_success(SPARE, Duration::from_millis(200));
+        assert_eq!(vec![BACKUP, SPARE, PRIMARY], pool.ranked());
+    }
+
+    #[test]
+    fn failover_and_recovery() {
+        let pool = pool();
+        let now = Instant::now();
+
+        pool.record_failure_at(PRIMARY, now);
+        pool.record_failure_at(BACKUP, now);
+        pool.record_failure_at(BACKUP, now);
+        // all cooling down endpoints are still ranked, the soonest recovery first
+        assert_eq!(vec![SPARE, PRIMARY, BACKUP], pool.ranked_at(now));
+
+        let recovered = now + cooldown(1);
+        assert_eq!(vec![PRIMARY, SPARE, BACKUP], pool.ranked_at(recovered));
+
+        pool.record_success(BACKUP, Duration::from_millis(10));
+        pool.record_success(PRIMARY, Duration::from_millis(50));
+        assert_eq!(vec![SPARE, BACKUP, PRIMARY], pool.ranked_at(recovered));
+    }
+
+    #[test]
+    fn cooldown_backoff() {
+        assert_eq!(
+            Duration::from_secs(ENDPOINT_COOLDOWN_BASE_SECS),
+            cooldown(1)
+        );
+        assert_eq!(
+            Duration::from_secs(ENDPOINT_COOLDOWN_BASE_SECS * 4),
+            cooldown(3)
+        );
+        assert_eq!(
+            Duration::from_secs(ENDPOINT_COOLDOWN_MAX_SECS),
+            cooldown(100)
+        );
+    }
+}
@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+use crate::server::domain::{PortfolioSnapshot, SnapshotStore, SnapshotStoreError};
+
+// snapshots of the wallets, oldest first
+#[derive(Default, Clone)]
+pub struct HashmapSnapshotStore {
+    snapshots: HashMap<String, Vec<PortfolioSnapshot>>,
+}
+
+#[async_trait::async_trait]
+impl SnapshotStore for HashmapSnapshotStore {
+    #[tracing::instrument(name = "Add snapshot", skip_all)]
+    async fn add_snapshot(
+        &mut self,
+        snapshot: PortfolioSnapshot,
+    ) -> Result<(), SnapshotStoreError> {
+        let snapshots = self.snapshots.entry(snapshot.wallet.clone()).or_default();
+        let index = snapshots.partition_point(|other| other.timestamp <= snapshot.timestamp);
+        snapshots.insert(index, snapshot);
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Get snapshots", skip_all)]
+    async fn get_snapshots(
+        &self,
+        wallets: &[String],
+        from: i64,
+        to: i64,
+    ) -> Result<Vec<PortfolioSnapshot>, SnapshotStoreError> {
+        let mut snapshots: Vec<PortfolioSnapshot> = wallets
+            .iter()
+            .filter_map(|wallet| self.snapshots.get(wallet))
+            .flatten()
+            .filter(|snapshot| snapshot.timestamp >= from && snapshot.timestamp <= to)
+            .cloned()
+            .collect();
+        snapshots.sort_by_key(|snapshot| snapshot.timestamp);
+        Ok(snapshots)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::server::utils::constants::test::{
+        fixtures,
+        solana_data::{OTHER_WALLET, WALLET},
+    };
+
+    fn snapshot(wallet: &str, timestamp: i64) -> PortfolioSnapshot {
+        fixtures::snapshot(wallet, timestamp, 100_000, 1.0)
+    }
+
+    #[tokio::test]
+    async fn test_get_snapshots() {
+        let mut store = HashmapSnapshotStore::default();
+        for (wallet, timestamp) in [(WALLET, 300), (WALLET, 100), (OTHER_WALLET, 200)] {
+            store
+                .add_snapshot(snapshot(wallet, timestamp))
+                .await
+                .unwrap();
+        }
+
+        let snapshots = store
+            .get_snapshots(&[WALLET.to_string(), OTHER_WALLET.to_string()], 100, 250)
+            .await
+            .unwrap();
+        assert_eq!(
+            vec![snapshot(WALLET, 100), snapshot(OTHER_WALLET, 200)],
+            snapshots
+        );
+
+        let snapshots = store
+            .get_snapshots(&[WALLET.to_string()], 0, 1000)
+            .await
+            .unwrap();
+        assert_eq!(
+            vec![snapshot(WALLET, 100), snapshot(WALLET, 300)],
+            snapshots
+        );
+    }
+}
@@ -19,8 +19,22 @@ impl TokenStore for HashmapTokenStore {
         Ok(())
     }
 
+    #[tracing::instrument(name = "Update token", skip_all)]
+    async fn update_token(&mut self, token: TokenInfo) -> Result<(), TokenStoreError> {
+        match self.tokens.get_mut(&token.id) {
+            Some(stored) => {
+                *stored = token;
+                Ok(())
+            }
+            None => {
+                tracing::error!("Token {} not found", token.id);
+                Err(TokenStoreError::TokenNotFound)
+            }
+        }
+    }
+
     #[tracing::instrument(name = "Get token", skip_all)]
-    async fn get_token(&self, address: &String) -> Result<TokenInfo, TokenStoreError> {
+    async fn get_token(&self, address: &str) -> Result<TokenInfo, TokenStoreError> {
         match self.tokens.get(address) {
             Some(token) => Ok(token.clone()),
             None => {
@@ -30,7 +44,7 @@ impl TokenStore for HashmapTokenStore {
         }
     }
 
-    async fn has_token(&self, address: &String) -> bool {
+    async fn has_token(&self, address: &str) -> bool {
         self.tokens.contains_key(address)
     }
 }
@@ -51,6 +65,7 @@ mod tests {
                 "https://arweave.net/hQiPZOsRZXGXBJd_82PhVdlM_hACsT_q6wqwf5cSY7I".to_owned(),
             ),
             decimals: 5,
+            ..Default::default()
         };
         let token_clone = token.clone();
 
@@ -73,6 +88,7 @@ mod tests {
             symbol: "Bonk".to_owned(),
             icon: None,
             decimals: 5,
+            ..Default::default()
         };
         let token_clone = token.clone();
         store.add_token(token).await.unwrap();
@@ -100,6 +116,7 @@ mod tests {
             symbol: "Bonk".to_owned(),
             icon: None,
             decimals: 5,
+            ..Default::default()
         };
 
         assert!(!store.has_token(&token_mint).await);
@@ -1,60 +1,234 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use async_trait::async_trait;
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder, Response, StatusCode, header::RETRY_AFTER};
 
-use crate::server::domain::{OffChainRpcClient, TokenInfo, TokenPrice};
+use crate::server::{
+    config::EndpointsConfig,
+    domain::{HistoricalPriceResponse, OffChainRpcClient, TokenInfo, TokenPrice},
+    utils::{
+        RateLimiter,
+        constants::{
+            BIRDEYE_API_KEY, JUPITER_MAX_PRICES_PER_REQUEST, JUPITER_MAX_RETRIES,
+            JUPITER_MAX_TOKENS_PER_REQUEST, JUPITER_RETRY_BASE_DELAY_MS,
+            JUPITER_RETRY_MAX_DELAY_MS, RECENT_PRICE_WINDOW_SECS,
+        },
+    },
+};
 
 pub struct JupiterRpcClient {
     token_api_url: String,
     price_api_url: String,
+    // jupiter has no price history, it is queried from birdeye
+    price_history_api_url: String,
+    price_history_api_key: Option<String>,
     client: Client,
+    // one instance serves every client of the server, so the limits are shared
+    rate_limiter: RateLimiter,
+    price_history_rate_limiter: RateLimiter,
 }
 
 impl JupiterRpcClient {
-    pub fn build(client: Client) -> Self {
-        let base_url = "https://lite-api.jup.ag";
+    pub fn build(client: Client, endpoints: &EndpointsConfig) -> Self {
+        let base_url = endpoints.jupiter_api.trim_end_matches('/');
         Self {
             token_api_url: format! {"{}/tokens/v2/search", base_url},
             price_api_url: format! {"{}/price/v3", base_url},
+            price_history_api_url: format!(
+                "{}/defi/historical_price_unix",
+                endpoints.birdeye_api.trim_end_matches('/')
+            ),
+            price_history_api_key: BIRDEYE_API_KEY.clone(),
             client,
+            rate_limiter: RateLimiter::new(
+                endpoints.jupiter_rate_limit_burst,
+                endpoints.jupiter_rate_limit_per_sec,
+            ),
+            price_history_rate_limiter: RateLimiter::new(
+                endpoints.birdeye_rate_limit_burst,
+                endpoints.birdeye_rate_limit_per_sec,
+            ),
         }
     }
+
+    // rate limited, 429 and 5xx responses are retried with exponential backoff
+    async fn send(
+        &self,
+        rate_limiter: &RateLimiter,
+        request: RequestBuilder,
+    ) -> Result<Response, Box<dyn std::error::Error + Send + Sync>> {
+        let mut attempt = 0;
+
+        loop {
+            rate_limiter.acquire().await;
+
+            let response = request
+                .try_clone()
+                .ok_or("Request can not be retried")?
+                .send()
+                .await?;
+
+            let status = response.status();
+            if status.is_success() {
+                return Ok(response);
+            }
+            if !is_retryable(status) || attempt >= JUPITER_MAX_RETRIES {
+                return Err(format!("Request failed with status: {}", status).into());
+            }
+
+            let retry_after = response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse::<u64>().ok());
+            let delay = retry_delay(attempt, retry_after);
+            tracing::warn!(
+                "Request failed with status: {}, retrying in {:?}",
+                status,
+                delay
+            );
+
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+// the server's Retry-After wins over the backoff
+fn retry_delay(attempt: u32, retry_after_secs: Option<u64>) -> Duration {
+    let delay = match retry_after_secs {
+        Some(secs) => secs.saturating_mul(1_000),
+        None => JUPITER_RETRY_BASE_DELAY_MS.saturating_mul(1 << attempt.min(16)),
+    };
+    Duration::from_millis(delay.min(JUPITER_RETRY_MAX_DELAY_MS))
 }
 
 #[async_trait]
 impl OffChainRpcClient for JupiterRpcClient {
-    //TODO: improve to handle the api token's limit (100) for one rq with using chunks
+    // the search may match other tokens too, only the requested ones are kept
     #[tracing::instrument(name = "Get tokens", skip_all, fields(tokens))]
     async fn get_tokens(
         &self,
         tokens: Vec<String>,
     ) -> Result<Vec<TokenInfo>, Box<dyn std::error::Error + Send + Sync>> {
-        let tokens = self
-            .client
-            .get(self.token_api_url.as_str())
-            .query(&[("query", tokens.join(","))])
-            .send()
-            .await?
-            .json::<Vec<TokenInfo>>()
-            .await?;
-        tracing::info!("Fetched tokens: {:?}", tokens);
-        Ok(tokens)
+        let mut missing: HashSet<&String> = tokens.iter().collect();
+        let mut token_infos: Vec<TokenInfo> = vec![];
+
+        for chunk in tokens.chunks(JUPITER_MAX_TOKENS_PER_REQUEST) {
+            let request = self
+                .client
+                .get(self.token_api_url.as_str())
+                .query(&[("query", chunk.join(","))]);
+
+            for token_info in self
+                .send(&self.rate_limiter, request)
+                .await?
+                .json::<Vec<TokenInfo>>()
+                .await?
+            {
+                if missing.remove(&token_info.id) {
+                    token_infos.push(token_info);
+                }
+            }
+        }
+
+        if !missing.is_empty() {
+            tracing::warn!("Tokens not found: {:?}", missing);
+        }
+        tracing::info!("Fetched tokens: {:?}", token_infos);
+        Ok(token_infos)
     }
+
+    // tokens without a price are left out of the map
     #[tracing::instrument(name = "Get prices" skip_all, fields(tokens))]
     async fn get_prices(
         &self,
         tokens: Vec<String>,
     ) -> Result<HashMap<String, TokenPrice>, Box<dyn std::error::Error + Send + Sync>> {
-        let prices = self
-            .client
-            .get(self.price_api_url.as_str())
-            .query(&[("ids", tokens.join(","))])
-            .send()
-            .await?
-            .json::<HashMap<String, TokenPrice>>()
-            .await?;
+        let mut prices: HashMap<String, TokenPrice> = HashMap::new();
+
+        for chunk in tokens.chunks(JUPITER_MAX_PRICES_PER_REQUEST) {
+            let request = self
+                .client
+                .get(self.price_api_url.as_str())
+                .query(&[("ids", chunk.join(","))]);
+
+            // unknown tokens are omitted or null
+            let chunk_prices = self
+                .send(&self.rate_limiter, request)
+                .await?
+                .json::<HashMap<String, Option<TokenPrice>>>()
+                .await?;
+            prices.extend(
+                chunk_prices
+                    .into_iter()
+                    .filter_map(|(token, price)| price.map(|price| (token, price))),
+            );
+        }
+
+        let missing: Vec<&String> = tokens
+            .iter()
+            .filter(|token| !prices.contains_key(*token))
+            .collect();
+        if !missing.is_empty() {
+            tracing::debug!("Prices not found: {:?}", missing);
+        }
         tracing::info!("Fetched prices: {:?}", prices);
         Ok(prices)
     }
+
+    #[tracing::instrument(name = "Get price at", skip_all, fields(token, timestamp))]
+    async fn get_price_at(
+        &self,
+        token: String,
+        timestamp: u64,
+    ) -> Result<Option<f64>, Box<dyn std::error::Error + Send + Sync>> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+        if now.saturating_sub(timestamp) <= RECENT_PRICE_WINDOW_SECS {
+            let prices = self.get_prices(vec![token.clone()]).await?;
+            return Ok(prices.get(&token).map(|price| price.usd_price));
+        }
+
+        let Some(api_key) = self.price_history_api_key.as_ref() else {
+            tracing::warn!("No price history api key, price of {} is unknown", token);
+            return Ok(None);
+        };
+
+        let request = self
+            .client
+            .get(self.price_history_api_url.as_str())
+            .header("X-API-KEY", api_key)
+            .header("x-chain", "solana")
+            .query(&[("address", token), ("unixtime", timestamp.to_string())]);
+        let response = self.send(&self.price_history_rate_limiter, request).await?;
+
+        let price = response.json::<HistoricalPriceResponse>().await?;
+        Ok(price.data.filter(|_| price.success).map(|data| data.value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff() {
+        assert_eq!(Duration::from_millis(1_000), retry_delay(0, None));
+        assert_eq!(Duration::from_millis(4_000), retry_delay(2, None));
+        assert_eq!(Duration::from_millis(30_000), retry_delay(10, None));
+        assert_eq!(Duration::from_secs(5), retry_delay(0, Some(5)));
+        assert_eq!(Duration::from_secs(30), retry_delay(0, Some(600)));
+
+        assert!(is_retryable(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable(StatusCode::BAD_GATEWAY));
+        assert!(!is_retryable(StatusCode::BAD_REQUEST));
+    }
 }
@@ -1,9 +1,18 @@
+pub mod endpoint_pool;
+pub mod hashmap_snapshot_store;
 pub mod hashmap_token_store;
 pub mod jupiter_rpc_client;
 pub mod solana_rpc_client;
 pub mod solana_ws_client;
+mod sqlite_migrations;
+pub mod sqlite_snapshot_store;
+pub mod sqlite_token_store;
 
+pub use endpoint_pool::*;
+pub use hashmap_snapshot_store::*;
 pub use hashmap_token_store::*;
 pub use jupiter_rpc_client::*;
 pub use solana_rpc_client::*;
 pub use solana_ws_client::*;
+pub use sqlite_snapshot_store::*;
+pub use sqlite_token_store::*;
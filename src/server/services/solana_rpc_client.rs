@@ -1,28 +1,156 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
 use async_trait::async_trait;
-use reqwest::Client;
+use futures_util::future::join_all;
+use reqwest::{Client, StatusCode};
 use serde_json::Deserializer;
 use serde_path_to_error::deserialize;
 
 use crate::server::{
+    config::CommitmentConfig,
     domain::{
-        BalanceResponse, GetBalanceResponse, GetTokenAccountBalanceResponse,
-        GetTransactionResponse, OnChainRpcClient, TokenAccountBalanceResponse, TransactionResponse,
+        AccountInfoResponse, BalanceResponse, ErrorResponse, GetAccountInfoResponse,
+        GetBalanceResponse, GetMultipleAccountsResponse, GetSignaturesForAddressResponse,
+        GetTokenAccountBalanceResponse, GetTokenAccountsByOwnerResponse, GetTransactionResponse,
+        OnChainRpcClient, RawAccount, SignaturesForAddressResponse, TokenAccountBalanceResponse,
+        TokenAccountsByOwnerResponse, TransactionResponse,
+    },
+    services::EndpointPool,
+    utils::constants::{
+        ENDPOINT_HEALTH_CHECK_INTERVAL_SECS, FAILOVER_RPC_ERROR_CODES, MAX_MULTIPLE_ACCOUNTS,
     },
-    utils::constants::SOLANA_RPC_URL,
 };
 
 pub struct SolanaRpcClient {
-    solana_url: String,
+    pool: Arc<EndpointPool>,
+    commitment: CommitmentConfig,
     client: Client,
 }
 
 impl SolanaRpcClient {
-    pub fn build(client: Client) -> Self {
+    pub fn build(client: Client, pool: Arc<EndpointPool>, commitment: CommitmentConfig) -> Self {
         Self {
-            solana_url: SOLANA_RPC_URL.clone(),
+            pool,
+            commitment,
             client,
         }
     }
+
+    // the endpoints are tried in rank order, transport errors, 429, 5xx and the JSON-RPC
+    // errors of a lagging or rate limited node fail over to the next; returns the body
+    async fn post(
+        &self,
+        request_body: &serde_json::Value,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let mut last_error: Box<dyn std::error::Error + Send + Sync> =
+            "No RPC endpoint configured".into();
+
+        for url in self.pool.ranked() {
+            let started_at = Instant::now();
+            let response = match self.client.post(&url).json(request_body).send().await {
+                Ok(response) if !is_failover_status(response.status()) => response,
+                Ok(response) => {
+                    tracing::warn!(
+                        "RPC endpoint {} failed with status: {}",
+                        url,
+                        response.status()
+                    );
+                    self.pool.record_failure(&url);
+                    last_error =
+                        format!("Request failed with status: {}", response.status()).into();
+                    continue;
+                }
+                Err(e) => {
+                    tracing::warn!("RPC endpoint {} failed: {}", url, e);
+                    self.pool.record_failure(&url);
+                    last_error = e.into();
+                    continue;
+                }
+            };
+
+            let status = response.status();
+            let body = match response.text().await {
+                Ok(body) => body,
+                Err(e) => {
+                    tracing::warn!("RPC endpoint {} failed: {}", url, e);
+                    self.pool.record_failure(&url);
+                    last_error = e.into();
+                    continue;
+                }
+            };
+
+            if let Some(error) = failover_rpc_error(&body) {
+                tracing::warn!("RPC endpoint {} failed: {}", url, error);
+                self.pool.record_failure(&url);
+                last_error = error.into();
+                continue;
+            }
+
+            self.pool.record_success(&url, started_at.elapsed());
+            if !status.is_success() {
+                return Err(format!("Request failed with status: {}", status).into());
+            }
+            return Ok(body);
+        }
+
+        Err(last_error)
+    }
+
+    pub async fn watch_health(self: Arc<Self>) {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(ENDPOINT_HEALTH_CHECK_INTERVAL_SECS));
+
+        loop {
+            interval.tick().await;
+            self.check_health().await;
+        }
+    }
+
+    // a node that is behind answers getHealth with an error
+    #[tracing::instrument(name = "Check RPC health", skip_all)]
+    async fn check_health(&self) {
+        let request_body = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getHealth",
+        });
+
+        // probed together, a hung endpoint only delays itself until the request timeout
+        join_all(self.pool.urls().into_iter().map(|url| {
+            let request_body = &request_body;
+            async move {
+                let started_at = Instant::now();
+                let healthy = match self.client.post(&url).json(request_body).send().await {
+                    Ok(response) if response.status().is_success() => response
+                        .json::<serde_json::Value>()
+                        .await
+                        .is_ok_and(|body| body.get("result").is_some()),
+                    _ => false,
+                };
+
+                if healthy {
+                    self.pool.record_success(&url, started_at.elapsed());
+                } else {
+                    self.pool.record_failure(&url);
+                }
+            }
+        }))
+        .await;
+    }
+}
+
+fn is_failover_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+fn failover_rpc_error(body: &str) -> Option<String> {
+    let error = serde_json::from_str::<ErrorResponse>(body).ok()?.error;
+    FAILOVER_RPC_ERROR_CODES
+        .contains(&error.code)
+        .then(|| format!("RPC error {}: {}", error.code, error.message))
 }
 
 #[async_trait]
@@ -37,19 +165,12 @@ impl OnChainRpcClient for SolanaRpcClient {
             "id": 1,
             "method": "getTransaction",
             "params": [ signature,{
-                "commitment": "confirmed", // 31 blocks finality -> confirmed ?
+                "commitment": self.commitment.transaction.as_str(),
                 "maxSupportedTransactionVersion": 0,
                 "encoding": "json"}]
         });
 
-        let response = self
-            .client
-            .post(&self.solana_url)
-            .json(&request_body)
-            .send()
-            .await?;
-
-        let text = response.text().await?;
+        let text = self.post(&request_body).await?;
         //tracing::info!("raw response: {}", text);
 
         //let parsed: Result<GetTransactionResponse, serde_json::Error> = serde_json::from_str(&text);
@@ -58,7 +179,7 @@ impl OnChainRpcClient for SolanaRpcClient {
         let parsed: Result<GetTransactionResponse, _> = deserialize(&mut deserializer);
 
         match parsed {
-            Ok(GetTransactionResponse::Transaction(resp)) => Ok(resp),
+            Ok(GetTransactionResponse::Transaction(resp)) => Ok(*resp),
             Ok(GetTransactionResponse::Error(resp)) => {
                 Err(format!("Transaction not found. Error: {}", resp.error.message).into())
             }
@@ -94,27 +215,18 @@ impl OnChainRpcClient for SolanaRpcClient {
             "method": "getTokenAccountBalance",
             "params": [ pub_key,
                 {
-                    "commitment": "finalized", // 31 blocks finality -> confirmed
+                    "commitment": self.commitment.balances.as_str(),
                 }
             ]
         });
 
-        let response = self
-            .client
-            .post(&self.solana_url)
-            .json(&request_body)
-            .send()
-            .await?;
-
-        if response.status().is_success() {
-            match response.json::<GetTokenAccountBalanceResponse>().await? {
-                GetTokenAccountBalanceResponse::Balance(resp) => Ok(resp),
-                GetTokenAccountBalanceResponse::Error(resp) => {
-                    Err(format!("Balance not found. Error: {}", resp.error.message).into())
-                }
+        let body = self.post(&request_body).await?;
+
+        match serde_json::from_str::<GetTokenAccountBalanceResponse>(&body)? {
+            GetTokenAccountBalanceResponse::Balance(resp) => Ok(resp),
+            GetTokenAccountBalanceResponse::Error(resp) => {
+                Err(format!("Balance not found. Error: {}", resp.error.message).into())
             }
-        } else {
-            Err(format!("Request failed with status: {}", response.status()).into())
         }
     }
 
@@ -129,27 +241,277 @@ impl OnChainRpcClient for SolanaRpcClient {
             "method": "getBalance",
             "params": [ pub_key,
                 {
-                    "commitment": "finalized", // 31 blocks finality -> confirmed
+                    "commitment": self.commitment.balances.as_str(),
                 }
             ]
         });
 
-        let response = self
-            .client
-            .post(&self.solana_url)
-            .json(&request_body)
-            .send()
-            .await?;
-
-        if response.status().is_success() {
-            match response.json::<GetBalanceResponse>().await? {
-                GetBalanceResponse::Balance(resp) => Ok(resp),
-                GetBalanceResponse::Error(resp) => {
-                    Err(format!("Sol balance not found. Error: {}", resp.error.message).into())
+        let body = self.post(&request_body).await?;
+
+        match serde_json::from_str::<GetBalanceResponse>(&body)? {
+            GetBalanceResponse::Balance(resp) => Ok(resp),
+            GetBalanceResponse::Error(resp) => {
+                Err(format!("Sol balance not found. Error: {}", resp.error.message).into())
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "Get signatures for address", skip_all)]
+    async fn get_signatures_for_address(
+        &self,
+        pub_key: String,
+        before: Option<String>,
+        until: Option<String>,
+        limit: usize,
+    ) -> Result<SignaturesForAddressResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let mut config = serde_json::json!({
+            "commitment": self.commitment.signatures.as_str(),
+            "limit": limit,
+        });
+
+        if let Some(before) = before {
+            config["before"] = serde_json::Value::String(before);
+        }
+
+        if let Some(until) = until {
+            config["until"] = serde_json::Value::String(until);
+        }
+
+        let request_body = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getSignaturesForAddress",
+            "params": [ pub_key, config ]
+        });
+
+        let body = self.post(&request_body).await?;
+
+        match serde_json::from_str::<GetSignaturesForAddressResponse>(&body)? {
+            GetSignaturesForAddressResponse::Signatures(resp) => Ok(resp),
+            GetSignaturesForAddressResponse::Error(resp) => {
+                Err(format!("Signatures not found. Error: {}", resp.error.message).into())
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "Get token accounts by owner", skip_all)]
+    async fn get_token_accounts_by_owner(
+        &self,
+        owner: String,
+        program_id: String,
+    ) -> Result<TokenAccountsByOwnerResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let request_body = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getTokenAccountsByOwner",
+            "params": [ owner,
+                { "programId": program_id },
+                {
+                    "commitment": self.commitment.accounts.as_str(),
+                    "encoding": "jsonParsed",
+                }
+            ]
+        });
+
+        let body = self.post(&request_body).await?;
+
+        match serde_json::from_str::<GetTokenAccountsByOwnerResponse>(&body)? {
+            GetTokenAccountsByOwnerResponse::Accounts(resp) => Ok(resp),
+            GetTokenAccountsByOwnerResponse::Error(resp) => {
+                Err(format!("Token accounts not found. Error: {}", resp.error.message).into())
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "Get account info", skip_all)]
+    async fn get_account_info(
+        &self,
+        pub_key: String,
+    ) -> Result<AccountInfoResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let request_body = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getAccountInfo",
+            "params": [ pub_key,
+                {
+                    "commitment": self.commitment.accounts.as_str(),
+                    "encoding": "jsonParsed",
+                }
+            ]
+        });
+
+        let body = self.post(&request_body).await?;
+
+        match serde_json::from_str::<GetAccountInfoResponse>(&body)? {
+            GetAccountInfoResponse::AccountInfo(resp) => Ok(resp),
+            GetAccountInfoResponse::Error(resp) => {
+                Err(format!("Account not found. Error: {}", resp.error.message).into())
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "Get multiple accounts", skip_all)]
+    async fn get_multiple_accounts(
+        &self,
+        pub_keys: Vec<String>,
+    ) -> Result<Vec<Option<RawAccount>>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut accounts: Vec<Option<RawAccount>> = Vec::with_capacity(pub_keys.len());
+
+        for chunk in pub_keys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let request_body = serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "getMultipleAccounts",
+                "params": [ chunk,
+                    {
+                        "commitment": self.commitment.accounts.as_str(),
+                        "encoding": "base64",
+                    }
+                ]
+            });
+
+            let body = self.post(&request_body).await?;
+
+            match serde_json::from_str::<GetMultipleAccountsResponse>(&body)? {
+                GetMultipleAccountsResponse::Accounts(resp) => {
+                    let value = resp.result.map(|res| res.value).unwrap_or_default();
+                    if value.len() != chunk.len() {
+                        return Err("Unexpected number of accounts".into());
+                    }
+                    accounts.extend(value);
+                }
+                GetMultipleAccountsResponse::Error(resp) => {
+                    return Err(format!("Accounts not found. Error: {}", resp.error.message).into());
                 }
             }
-        } else {
-            Err(format!("Request failed with status: {}", response.status()).into())
         }
+
+        Ok(accounts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+    use crate::server::utils::constants::test::solana_data::WALLET;
+
+    // answers every request with the body, over HTTP 200
+    async fn spawn_rpc_node(body: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+
+                // the request is read up to its body before answering
+                let mut request = vec![];
+                let mut buf = [0; 1024];
+                while let Ok(read) = stream.read(&mut buf).await
+                    && read > 0
+                {
+                    request.extend_from_slice(&buf[..read]);
+                    let text = String::from_utf8_lossy(&request);
+                    if let Some((headers, content)) = text.split_once("\r\n\r\n")
+                        && headers
+                            .lines()
+                            .filter_map(|line| line.split_once(": "))
+                            .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                            .and_then(|(_, length)| length.parse::<usize>().ok())
+                            .is_some_and(|length| content.len() >= length)
+                    {
+                        break;
+                    }
+                }
+
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\n\
+                     content-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+
+        url
+    }
+
+    // accepts every connection and never answers
+    async fn spawn_hung_node() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            let mut connections = vec![];
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                connections.push(stream);
+            }
+        });
+
+        url
+    }
+
+    #[tokio::test]
+    async fn fails_over_on_rpc_error() {
+        let behind = spawn_rpc_node(
+            r#"{"jsonrpc":"2.0","error":{"code":-32005,"message":"Node is behind by 42 slots"},"id":1}"#,
+        )
+        .await;
+        let healthy = spawn_rpc_node(
+            r#"{"jsonrpc":"2.0","result":{"context":{"slot":1},"value":1500000000},"id":1}"#,
+        )
+        .await;
+        let pool = Arc::new(EndpointPool::new(vec![behind.clone(), healthy.clone()]));
+        let client = SolanaRpcClient::build(Client::new(), pool.clone(), Default::default());
+
+        let balance = client.get_balance(WALLET.to_string()).await.unwrap();
+
+        assert_eq!(1_500_000_000, balance.result.unwrap().value);
+        assert_eq!(vec![healthy, behind], pool.ranked());
+    }
+
+    #[tokio::test]
+    async fn keeps_endpoint_on_request_error() {
+        let url = spawn_rpc_node(
+            r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Invalid param"},"id":1}"#,
+        )
+        .await;
+        let other = spawn_rpc_node(
+            r#"{"jsonrpc":"2.0","result":{"context":{"slot":1},"value":1500000000},"id":1}"#,
+        )
+        .await;
+        let pool = Arc::new(EndpointPool::new(vec![url, other]));
+        let client = SolanaRpcClient::build(Client::new(), pool, Default::default());
+
+        // the other endpoint would have answered with the balance
+        let balance = client.get_balance(WALLET.to_string()).await.unwrap();
+        assert!(balance.result.is_none());
+    }
+
+    #[tokio::test]
+    async fn fails_over_on_timeout() {
+        let hung = spawn_hung_node().await;
+        let healthy = spawn_rpc_node(
+            r#"{"jsonrpc":"2.0","result":{"context":{"slot":1},"value":1500000000},"id":1}"#,
+        )
+        .await;
+        let pool = Arc::new(EndpointPool::new(vec![hung.clone(), healthy.clone()]));
+        let http_client = Client::builder()
+            .timeout(Duration::from_millis(500))
+            .build()
+            .unwrap();
+        let client = SolanaRpcClient::build(http_client, pool.clone(), Default::default());
+
+        let balance = client.get_balance(WALLET.to_string()).await.unwrap();
+
+        assert_eq!(1_500_000_000, balance.result.unwrap().value);
+        assert_eq!(vec![healthy, hung], pool.ranked());
     }
 }
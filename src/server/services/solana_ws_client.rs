@@ -1,41 +1,103 @@
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 use async_trait::async_trait;
+use futures_util::future::join_all;
+use futures_util::stream::{SplitSink, SplitStream};
 use futures_util::{SinkExt, StreamExt};
 use serde_json::json;
+use tokio::net::TcpStream;
 use tokio::sync::{Mutex, mpsc};
-use tokio::time::{Duration, interval};
-use tokio_tungstenite::connect_async;
+use tokio::time::{Duration, interval, sleep, timeout};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async};
 use tonic::Status;
 use tungstenite::protocol::Message;
 
 use crate::proto::SubscribeResponse;
-use crate::server::domain::SubscriptionInput;
+use crate::server::config::{Commitment, ServerConfig};
 use crate::server::domain::solana_api_messages::LogSubscribeWsMessage;
 use crate::server::domain::ws_client::WSCResult;
 use crate::server::domain::ws_client::WebSocketClient;
+use crate::server::domain::{SubscriptionInput, WalletEvent};
+use crate::server::services::EndpointPool;
 use crate::server::states::app_state::{
     OffChainRpcClientType, OnChainRpcClientType, TokenStoreType,
 };
-use crate::server::utils::handle_transaction;
+use crate::server::states::{EventFilterType, TradeLedgerType};
+use crate::server::utils::constants::{
+    ENDPOINT_HEALTH_CHECK_INTERVAL_SECS, MAX_GAP_FILL_SIGNATURES, WS_RECONNECT_BASE_DELAY_MS,
+    WS_RECONNECT_MAX_DELAY_MS,
+};
+use crate::server::utils::{collect_signatures, handle_transaction};
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+type WsWriteStream = SplitSink<WsStream, Message>;
+type WsReadStream = SplitStream<WsStream>;
+
+// the remote (solana) subscription id changes on every reconnect,
+// the client only sees the local id
+struct SubscriptionHandle {
+    remote_id: u64,
+    write_tx: mpsc::Sender<Message>,
+}
+
+type SubscriptionsType = Arc<Mutex<HashMap<u64, SubscriptionHandle>>>;
+
+#[derive(Clone, Copy)]
+pub struct WebSocketSettings {
+    pub commitment: Commitment,
+    pub ping_interval: Duration,
+    pub writer_channel_size: usize,
+    // the handshake, then the subscription confirmation
+    pub connect_timeout: Duration,
+    pub request_timeout: Duration,
+}
+
+impl From<&ServerConfig> for WebSocketSettings {
+    fn from(config: &ServerConfig) -> Self {
+        Self {
+            commitment: config.commitment.logs,
+            ping_interval: Duration::from_secs(config.websocket.ping_interval_secs),
+            writer_channel_size: config.channels.ws_writer,
+            connect_timeout: Duration::from_secs(config.endpoints.connect_timeout_secs),
+            request_timeout: Duration::from_secs(config.endpoints.request_timeout_secs),
+        }
+    }
+}
 
 pub struct SolanaWebSocketClient {
-    url: String,
-    next_req_id: u64,
-    write_channel: Arc<Mutex<HashMap<u64, mpsc::Sender<Message>>>>,
+    pool: Arc<EndpointPool>,
+    settings: WebSocketSettings,
+    next_req_id: Arc<AtomicU64>,
+    next_sub_id: u64,
+    subscriptions: SubscriptionsType,
 }
 
 impl SolanaWebSocketClient {
-    pub fn new(url: &str) -> Self {
+    pub fn new(pool: Arc<EndpointPool>, settings: WebSocketSettings) -> Self {
         Self {
-            url: url.to_string(),
-            next_req_id: 1,
-            write_channel: Arc::new(Mutex::new(HashMap::new())),
+            pool,
+            settings,
+            next_req_id: Arc::new(AtomicU64::new(1)),
+            next_sub_id: 1,
+            subscriptions: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 }
 
+#[derive(Clone)]
+struct SubscriptionContext {
+    subscription_input: Arc<SubscriptionInput>,
+    off_chain_rpc_client: OffChainRpcClientType,
+    token_store: TokenStoreType,
+    on_chain_rpc_client: OnChainRpcClientType,
+    trade_ledger: TradeLedgerType,
+    event_filter: EventFilterType,
+    tx: mpsc::Sender<Result<SubscribeResponse, Status>>,
+}
+
 #[async_trait]
 impl WebSocketClient for SolanaWebSocketClient {
     #[tracing::instrument(name = "Logs subscribe", skip_all)]
@@ -45,174 +107,516 @@ impl WebSocketClient for SolanaWebSocketClient {
         off_chain_rpc_client: OffChainRpcClientType,
         token_store: TokenStoreType,
         on_chain_rpc_client: OnChainRpcClientType,
+        trade_ledger: TradeLedgerType,
+        event_filter: EventFilterType,
         tx: mpsc::Sender<Result<SubscribeResponse, Status>>,
     ) -> WSCResult<u64> {
-        let (ws_stream, _) = connect_async(&self.url).await?;
-        tracing::info!("WebSocket connected to {}", &self.url);
-        let req_id = self.next_req_id;
-        self.next_req_id += 1;
+        let req_id = self.next_req_id.fetch_add(1, Ordering::Relaxed);
+
+        let (url, remote_id, write_stream, read_stream) = match connect_and_subscribe(
+            &self.pool,
+            &subscription_input.wallet,
+            req_id,
+            &self.settings,
+        )
+        .await
+        {
+            Ok(connection) => connection,
+            Err(e) => {
+                tracing::error!("logs subscription request failed");
+                return Err(e);
+            }
+        };
+
+        let sub_id = self.next_sub_id;
+        self.next_sub_id += 1;
+
+        self.subscriptions.lock().await.insert(
+            sub_id,
+            SubscriptionHandle {
+                remote_id,
+                write_tx: spawn_writer(write_stream, self.settings.writer_channel_size),
+            },
+        );
+
+        let context = SubscriptionContext {
+            subscription_input,
+            off_chain_rpc_client,
+            token_store,
+            on_chain_rpc_client,
+            trade_ledger,
+            event_filter,
+            tx,
+        };
+
+        tokio::spawn(run_subscription(
+            self.pool.clone(),
+            self.settings,
+            sub_id,
+            url,
+            read_stream,
+            self.subscriptions.clone(),
+            self.next_req_id.clone(),
+            context,
+        ));
+
+        self.ping(sub_id).await;
+
+        Ok(sub_id)
+    }
+
+    #[tracing::instrument(name = "Logs unsubscribe", skip_all)]
+    async fn logs_unsubscribe(&mut self, sub_id: u64) -> WSCResult<()> {
+        close_subscription(&self.subscriptions, &self.next_req_id, sub_id).await
+    }
+}
+
+// the ping task and the writer end with the handle
+async fn close_subscription(
+    subscriptions: &SubscriptionsType,
+    next_req_id: &AtomicU64,
+    sub_id: u64,
+) -> WSCResult<()> {
+    if let Some(handle) = subscriptions.lock().await.remove(&sub_id) {
+        let req_id = next_req_id.fetch_add(1, Ordering::Relaxed);
 
         let req = json!({
             "jsonrpc": "2.0",
             "id": req_id,
-            "method": "logsSubscribe",
-            "params": [
-                    { "mentions": [subscription_input.clone().wallet.clone()] },
-                    { "commitment": "finalized" }
-                ]
+            "method": "logsUnsubscribe",
+            "params": [handle.remote_id],
         });
 
-        let (mut write_stream, mut read_stream) = ws_stream.split();
+        handle.write_tx.send(Message::Text(req.to_string())).await?;
+        handle.write_tx.send(Message::Close(None)).await?;
+    }
+    Ok(())
+}
 
-        write_stream.send(Message::Text(req.to_string())).await?;
+impl SolanaWebSocketClient {
+    #[tracing::instrument(name = "Ping", skip_all)]
+    async fn ping(&mut self, sub_id: u64) {
+        let subscriptions = self.subscriptions.clone();
+        let ping_interval = self.settings.ping_interval;
+        tokio::spawn(async move {
+            let mut ticker = interval(ping_interval);
+            loop {
+                ticker.tick().await;
+
+                if let Some(handle) = subscriptions.lock().await.get(&sub_id) {
+                    // the connection might be down, the reconnect replaces the writer
+                    if let Err(e) = handle.write_tx.send(Message::Ping(vec![])).await {
+                        tracing::error!("Ping error: {:?}", e);
+                    }
+                } else {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+// the endpoints are tried in rank order, the first one that subscribes wins
+async fn connect_and_subscribe(
+    pool: &EndpointPool,
+    wallet: &str,
+    req_id: u64,
+    settings: &WebSocketSettings,
+) -> WSCResult<(String, u64, WsWriteStream, WsReadStream)> {
+    let mut last_error = "No WebSocket endpoint configured".into();
+
+    for url in pool.ranked() {
+        let started_at = Instant::now();
+        match subscribe_endpoint(&url, wallet, req_id, settings).await {
+            Ok((remote_id, write_stream, read_stream)) => {
+                pool.record_success(&url, started_at.elapsed());
+                return Ok((url, remote_id, write_stream, read_stream));
+            }
+            Err(e) => {
+                tracing::warn!("WebSocket endpoint {} failed: {}", url, e);
+                pool.record_failure(&url);
+                last_error = e;
+            }
+        }
+    }
 
-        let mut sub_id: Option<u64> = None;
+    Err(last_error)
+}
 
-        let (write_tx, mut write_rx) = mpsc::channel::<Message>(3);
+async fn subscribe_endpoint(
+    url: &str,
+    wallet: &str,
+    req_id: u64,
+    settings: &WebSocketSettings,
+) -> WSCResult<(u64, WsWriteStream, WsReadStream)> {
+    // an endpoint that accepts the connection and then hangs must not stall the failover
+    let (ws_stream, _) = timeout(settings.connect_timeout, connect_async(url))
+        .await
+        .map_err(|_| format!("No handshake within {:?}", settings.connect_timeout))??;
+    tracing::info!("WebSocket connected to {}", url);
 
-        if let Some(msg) = read_stream.next().await {
-            if let Ok(tungstenite::Message::Text(txt)) = msg {
-                if let Ok(LogSubscribeWsMessage::Subscribed(resp)) =
-                    serde_json::from_str::<LogSubscribeWsMessage>(&txt)
-                {
-                    let subscription_id = resp.result;
-                    let write_tx_clone = write_tx.clone();
+    let req = json!({
+        "jsonrpc": "2.0",
+        "id": req_id,
+        "method": "logsSubscribe",
+        "params": [
+                { "mentions": [wallet] },
+                { "commitment": settings.commitment.as_str() }
+            ]
+    });
 
-                    self.write_channel
-                        .clone()
-                        .lock()
-                        .await
-                        .insert(subscription_id, write_tx_clone);
+    let (mut write_stream, mut read_stream) = ws_stream.split();
 
-                    sub_id = Some(subscription_id);
+    write_stream.send(Message::Text(req.to_string())).await?;
 
-                    tokio::spawn(async move {
-                        while let Some(msg) = write_rx.recv().await {
-                            if let Err(_e) = write_stream.send(msg).await {
-                                break;
-                            }
-                        }
-                    });
+    let ack = timeout(settings.request_timeout, read_stream.next())
+        .await
+        .map_err(|_| format!("No subscription within {:?}", settings.request_timeout))?;
+    if let Some(Ok(tungstenite::Message::Text(txt))) = ack
+        && let Ok(LogSubscribeWsMessage::Subscribed(resp)) =
+            serde_json::from_str::<LogSubscribeWsMessage>(&txt)
+    {
+        return Ok((resp.result, write_stream, read_stream));
+    }
+
+    Err("logsSubscribe subscription request failed".into())
+}
+
+fn spawn_writer(mut write_stream: WsWriteStream, channel_size: usize) -> mpsc::Sender<Message> {
+    let (write_tx, mut write_rx) = mpsc::channel::<Message>(channel_size);
+
+    tokio::spawn(async move {
+        while let Some(msg) = write_rx.recv().await {
+            if let Err(_e) = write_stream.send(msg).await {
+                break;
+            }
+        }
+    });
+
+    write_tx
+}
+
+pub async fn watch_ws_health(pool: Arc<EndpointPool>, connect_timeout: Duration) {
+    let mut ticker = interval(Duration::from_secs(ENDPOINT_HEALTH_CHECK_INTERVAL_SECS));
+
+    loop {
+        ticker.tick().await;
+        check_ws_health(&pool, connect_timeout).await;
+    }
+}
+
+// an endpoint is healthy if the handshake succeeds in time,
+// the endpoints are probed together so a hung one does not delay the others
+#[tracing::instrument(name = "Check WebSocket health", skip_all)]
+async fn check_ws_health(pool: &EndpointPool, connect_timeout: Duration) {
+    join_all(pool.urls().into_iter().map(|url| async move {
+        let started_at = Instant::now();
+        match timeout(connect_timeout, connect_async(url.as_str())).await {
+            Ok(Ok((mut ws_stream, _))) => {
+                pool.record_success(&url, started_at.elapsed());
+                let _ = ws_stream.close(None).await;
+            }
+            Ok(Err(e)) => {
+                tracing::warn!("WebSocket endpoint {} is unhealthy: {}", url, e);
+                pool.record_failure(&url);
+            }
+            Err(_) => {
+                tracing::warn!(
+                    "WebSocket endpoint {} is unhealthy: no handshake within {:?}",
+                    url,
+                    connect_timeout
+                );
+                pool.record_failure(&url);
+            }
+        }
+    }))
+    .await;
+}
+
+fn reconnect_delay(attempt: u32) -> Duration {
+    let delay = WS_RECONNECT_BASE_DELAY_MS.saturating_mul(1 << attempt.min(16));
+    Duration::from_millis(delay.min(WS_RECONNECT_MAX_DELAY_MS))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// the subscription is closed when the reading ends, e.g. the client's stream is dropped
+#[tracing::instrument(name = "Run subscription", skip_all, fields(sub_id))]
+#[allow(clippy::too_many_arguments)]
+async fn run_subscription(
+    pool: Arc<EndpointPool>,
+    settings: WebSocketSettings,
+    sub_id: u64,
+    url: String,
+    read_stream: WsReadStream,
+    subscriptions: SubscriptionsType,
+    next_req_id: Arc<AtomicU64>,
+    context: SubscriptionContext,
+) {
+    read_logs(
+        pool,
+        settings,
+        sub_id,
+        url,
+        read_stream,
+        subscriptions.clone(),
+        next_req_id.clone(),
+        context,
+    )
+    .await;
+
+    if let Err(e) = close_subscription(&subscriptions, &next_req_id, sub_id).await {
+        tracing::warn!("Failed to close subscription {}: {}", sub_id, e);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn read_logs(
+    pool: Arc<EndpointPool>,
+    settings: WebSocketSettings,
+    sub_id: u64,
+    mut url: String,
+    mut read_stream: WsReadStream,
+    subscriptions: SubscriptionsType,
+    next_req_id: Arc<AtomicU64>,
+    context: SubscriptionContext,
+) {
+    let subscribed_at = now();
+    let mut last_signature: Option<String> = None;
+
+    loop {
+        while let Some(msg) = read_stream.next().await {
+            match msg {
+                Ok(tungstenite::Message::Text(txt)) => {
+                    if let Some(message) = context.handle_message(&txt, &mut last_signature).await
+                        && context.tx.send(Ok(message)).await.is_err()
+                    {
+                        return;
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::error!("WebSocket error {:?}", e);
+                    pool.record_failure(&url);
+                    break;
                 }
             }
         }
 
-        match sub_id {
-            Some(sub_id) => {
-                tokio::spawn(async move {
-                    while let Some(msg) = read_stream.next().await {
-                        match msg {
-                            Ok(tungstenite::Message::Text(txt)) => {
-                                let mut stream_message: Option<String> = None;
-                                match serde_json::from_str::<LogSubscribeWsMessage>(&txt) {
-                                    Ok(LogSubscribeWsMessage::Notification(resp)) => {
-                                        if resp.params.result.value.err.is_none() {
-                                            let signature = resp.params.result.value.signature;
-
-                                            handle_transaction(
-                                                signature,
-                                                subscription_input.clone(),
-                                                off_chain_rpc_client.clone(),
-                                                token_store.clone(),
-                                                on_chain_rpc_client.clone(),
-                                            )
-                                            .await
-                                            .ok()
-                                            .flatten()
-                                            .map(
-                                                |trade| {
-                                                    stream_message = Some(format!(
-                                                        "Trade detected: {}",
-                                                        trade.to_string()
-                                                    ))
-                                                },
-                                            );
-                                        }
-                                    }
-                                    Ok(LogSubscribeWsMessage::UnSubscribed(resp)) => {
-                                        // not sure we'll get it, the stream might end sooner
-                                        stream_message = Some(format!(
-                                            "Unsubscription success: {}",
-                                            resp.result
-                                        ));
-                                    }
-                                    Ok(LogSubscribeWsMessage::Error(resp)) => {
-                                        // ? ignore the transient stream error
-                                        stream_message =
-                                            Some(format!("Error response: {}", resp.error.message));
-                                    }
-                                    Ok(LogSubscribeWsMessage::Subscribed(_resp)) => {
-                                        //not possible, ignore it for now
-                                    }
-                                    Err(_) => {}
-                                }
-
-                                if let Some(message) = stream_message {
-                                    if tx.send(Ok(SubscribeResponse { message })).await.is_err() {
-                                        break;
-                                    }
-                                }
-                            }
-                            Ok(_) => {}
-                            Err(e) => {
-                                tracing::error!("WebSocket error {:?}", e);
-                                break;
-                            }
+        if !subscriptions.lock().await.contains_key(&sub_id) || context.tx.is_closed() {
+            // unsubscribed or the client is gone
+            return;
+        }
+
+        tracing::warn!("WebSocket connection lost, reconnecting");
+        let _ = context
+            .tx
+            .send(Ok(SubscribeResponse::error(
+                "Connection lost, reconnecting...".to_string(),
+            )))
+            .await;
+
+        let mut attempt = 0;
+        (url, read_stream) = loop {
+            sleep(reconnect_delay(attempt)).await;
+            attempt += 1;
+
+            if context.tx.is_closed() {
+                return;
+            }
+
+            let req_id = next_req_id.fetch_add(1, Ordering::Relaxed);
+            match connect_and_subscribe(
+                &pool,
+                &context.subscription_input.wallet,
+                req_id,
+                &settings,
+            )
+            .await
+            {
+                Ok((new_url, remote_id, write_stream, read_stream)) => {
+                    let write_tx = spawn_writer(write_stream, settings.writer_channel_size);
+                    match subscriptions.lock().await.get_mut(&sub_id) {
+                        Some(handle) => {
+                            handle.remote_id = remote_id;
+                            handle.write_tx = write_tx;
+                        }
+                        None => {
+                            // unsubscribed meanwhile
+                            let _ = write_tx.send(Message::Close(None)).await;
+                            return;
                         }
                     }
-                });
+                    tracing::info!("Resubscribed with remote id: {}", remote_id);
+                    break (new_url, read_stream);
+                }
+                Err(e) => tracing::warn!("Reconnect attempt {} failed: {}", attempt, e),
+            }
+        };
 
-                self.ping(sub_id).await;
+        let _ = context
+            .tx
+            .send(Ok(SubscribeResponse::status(
+                "Reconnected, subscription resumed".to_string(),
+            )))
+            .await;
+
+        if !context.fill_gap(&mut last_signature, subscribed_at).await {
+            return;
+        }
+    }
+}
 
-                Ok(sub_id)
+impl SubscriptionContext {
+    async fn handle_message(
+        &self,
+        txt: &str,
+        last_signature: &mut Option<String>,
+    ) -> Option<SubscribeResponse> {
+        match serde_json::from_str::<LogSubscribeWsMessage>(txt) {
+            Ok(LogSubscribeWsMessage::Notification(resp)) => {
+                let value = resp.params.result.value;
+                *last_signature = Some(value.signature.clone());
+                if value.err.is_none() {
+                    return self.handle_signature(value.signature).await;
+                }
+                None
             }
-            None => {
-                tracing::error!("logs subscription request failed");
-                Err("logsSubscribe subscription request failed".into())
+            Ok(LogSubscribeWsMessage::UnSubscribed(resp)) => {
+                // not sure we'll get it, the stream might end sooner
+                Some(SubscribeResponse::status(format!(
+                    "Unsubscription success: {}",
+                    resp.result
+                )))
+            }
+            Ok(LogSubscribeWsMessage::Error(resp)) => {
+                // ? ignore the transient stream error
+                Some(SubscribeResponse::error(format!(
+                    "Error response: {}",
+                    resp.error.message
+                )))
             }
+            Ok(LogSubscribeWsMessage::Subscribed(_resp)) => {
+                //not possible, ignore it for now
+                None
+            }
+            Err(_) => None,
         }
     }
 
-    #[tracing::instrument(name = "Logs unsubscribe", skip_all)]
-    async fn logs_unsubscribe(&mut self, sub_id: u64) -> WSCResult<()> {
-        if let Some(write_tx) = self.write_channel.lock().await.remove(&sub_id) {
-            let req_id = self.next_req_id;
-            self.next_req_id += 1;
-
-            let req = json!({
-                "jsonrpc": "2.0",
-                "id": req_id,
-                "method": "logsUnsubscribe",
-                "params": [sub_id],
-            });
-
-            write_tx.send(Message::Text(req.to_string())).await?;
-            write_tx.send(Message::Close(None)).await?;
+    // the ledger filters the events already sent (e.g. both gap-filled and notified),
+    // the events dropped by the stream filter are still recorded for the positions
+    async fn handle_signature(&self, signature: String) -> Option<SubscribeResponse> {
+        let event = handle_transaction(
+            signature,
+            self.subscription_input.clone(),
+            self.off_chain_rpc_client.clone(),
+            self.token_store.clone(),
+            self.on_chain_rpc_client.clone(),
+        )
+        .await
+        .ok()
+        .flatten()?;
+
+        let accepted = self.event_filter.read().await.accepts(&event);
+
+        let mut trade_ledger = self.trade_ledger.write().await;
+        let response = match event {
+            WalletEvent::Trade(trade) => trade_ledger
+                .add_trade(trade.clone())
+                .then(|| SubscribeResponse::trade(trade)),
+            WalletEvent::Activity(activity) => trade_ledger
+                .add_activity(activity.clone())
+                .then(|| SubscribeResponse::activity(activity)),
+        };
+
+        response.filter(|_| accepted)
+    }
+
+    // replays the signatures missed while the connection was down, oldest first;
+    // returns false if the client is gone
+    #[tracing::instrument(name = "Fill gap", skip_all)]
+    async fn fill_gap(&self, last_signature: &mut Option<String>, subscribed_at: u64) -> bool {
+        let since = if last_signature.is_none() {
+            Some(subscribed_at)
+        } else {
+            None
+        };
+
+        let signatures = match collect_signatures(
+            &self.subscription_input.wallet,
+            last_signature.clone(),
+            since,
+            self.on_chain_rpc_client.clone(),
+        )
+        .await
+        {
+            Ok(signatures) => signatures,
+            Err(e) => {
+                tracing::error!("Failed to collect missed signatures: {}", e);
+                return true;
+            }
+        };
+
+        tracing::info!("Filling gap of {} signatures", signatures.len());
+
+        // the older missed signatures are dropped
+        if signatures.len() >= MAX_GAP_FILL_SIGNATURES {
+            tracing::warn!(
+                "Gap fill capped at {} signatures, older events are skipped",
+                MAX_GAP_FILL_SIGNATURES
+            );
+            if self
+                .tx
+                .send(Ok(SubscribeResponse::status(format!(
+                    "Gap fill capped at the latest {} transactions, older events are skipped",
+                    MAX_GAP_FILL_SIGNATURES
+                ))))
+                .await
+                .is_err()
+            {
+                return false;
+            }
+        }
+
+        for signature_info in signatures.into_iter().rev() {
+            *last_signature = Some(signature_info.signature.clone());
+            if signature_info.err.is_some() {
+                continue;
+            }
+            if let Some(message) = self.handle_signature(signature_info.signature).await
+                && self.tx.send(Ok(message)).await.is_err()
+            {
+                return false;
+            }
         }
-        Ok(())
+
+        true
     }
 }
 
-impl SolanaWebSocketClient {
-    #[tracing::instrument(name = "Ping", skip_all)]
-    async fn ping(&mut self, sub_id: u64) {
-        let write_channel_clone = self.write_channel.clone();
-        tokio::spawn(async move {
-            let mut ticker = interval(Duration::from_secs(20));
-            loop {
-                ticker.tick().await;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-                if let Some(write_tx) = write_channel_clone.lock().await.get(&sub_id) {
-                    if let Err(e) = write_tx.send(Message::Ping(vec![])).await {
-                        tracing::error!("Ping error: {:?}", e);
-                        break;
-                    } else {
-                        //tracing::info!("Ping sent");
-                    }
-                } else {
-                    break;
-                }
-            }
-        });
+    #[test]
+    fn reconnect_backoff() {
+        assert_eq!(
+            Duration::from_millis(WS_RECONNECT_BASE_DELAY_MS),
+            reconnect_delay(0)
+        );
+        assert_eq!(
+            Duration::from_millis(WS_RECONNECT_BASE_DELAY_MS * 4),
+            reconnect_delay(2)
+        );
+        assert_eq!(
+            Duration::from_millis(WS_RECONNECT_MAX_DELAY_MS),
+            reconnect_delay(100)
+        );
     }
 }
@@ -0,0 +1,23 @@
+use rusqlite::Connection;
+
+// applied in order, the index + 1 is stored in the user_version pragma,
+// the stores can share a database file
+const MIGRATIONS: &[&str] = &[
+    include_str!("../../../migrations/0001_create_tokens.sql"),
+    include_str!("../../../migrations/0002_add_token_mint_info.sql"),
+    include_str!("../../../migrations/0003_create_portfolio_snapshots.sql"),
+];
+
+pub(crate) fn migrate(conn: &mut Connection) -> Result<(), rusqlite::Error> {
+    let version: usize = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", index + 1)?;
+        tx.commit()?;
+        tracing::info!("Applied database migration {}", index + 1);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,234 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use rusqlite::{Connection, params, params_from_iter, types::Value};
+
+use crate::{
+    proto::Holding,
+    server::{
+        domain::{PortfolioSnapshot, SnapshotStore, SnapshotStoreError, TokenAmount},
+        services::sqlite_migrations::migrate,
+    },
+};
+
+#[derive(Clone)]
+pub struct SqliteSnapshotStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteSnapshotStore {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, rusqlite::Error> {
+        Self::build(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, rusqlite::Error> {
+        Self::build(Connection::open_in_memory()?)
+    }
+
+    fn build(mut conn: Connection) -> Result<Self, rusqlite::Error> {
+        migrate(&mut conn)?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    async fn run<T, F>(&self, f: F) -> Result<T, SnapshotStoreError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T, rusqlite::Error> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn
+                .lock()
+                .map_err(|_| SnapshotStoreError::UnexpectedError)?;
+            f(&mut conn).map_err(|e| {
+                tracing::error!("Snapshot store query failed: {}", e);
+                SnapshotStoreError::UnexpectedError
+            })
+        })
+        .await
+        .map_err(|e| {
+            tracing::error!("Snapshot store task failed: {}", e);
+            SnapshotStoreError::UnexpectedError
+        })?
+    }
+}
+
+#[async_trait::async_trait]
+impl SnapshotStore for SqliteSnapshotStore {
+    #[tracing::instrument(name = "Add snapshot", skip_all)]
+    async fn add_snapshot(
+        &mut self,
+        snapshot: PortfolioSnapshot,
+    ) -> Result<(), SnapshotStoreError> {
+        self.run(move |conn| {
+            let tx = conn.transaction()?;
+            tx.execute(
+                "INSERT INTO portfolio_snapshots (wallet, taken_at) VALUES (?1, ?2)",
+                params![snapshot.wallet, snapshot.timestamp],
+            )?;
+            let snapshot_id = tx.last_insert_rowid();
+
+            for holding in snapshot.holdings.iter() {
+                // the raw balance may not fit the signed sqlite integer
+                tx.execute(
+                    "INSERT INTO portfolio_snapshot_holdings (snapshot_id, mint, name, symbol, \
+                     raw_balance, decimals, usd_price, usd_value) \
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    params![
+                        snapshot_id,
+                        holding.address,
+                        holding.name,
+                        holding.symbol,
+                        holding.raw_balance.to_string(),
+                        holding.decimals,
+                        holding.usd_price,
+                        holding.usd_value
+                    ],
+                )?;
+            }
+
+            tx.commit()
+        })
+        .await
+    }
+
+    #[tracing::instrument(name = "Get snapshots", skip_all)]
+    async fn get_snapshots(
+        &self,
+        wallets: &[String],
+        from: i64,
+        to: i64,
+    ) -> Result<Vec<PortfolioSnapshot>, SnapshotStoreError> {
+        if wallets.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let wallets = wallets.to_vec();
+        self.run(move |conn| {
+            let placeholders = vec!["?"; wallets.len()].join(", ");
+            let mut stmt = conn.prepare(&format!(
+                "SELECT s.id, s.wallet, s.taken_at, h.mint, h.name, h.symbol, h.raw_balance, \
+                 h.decimals, h.usd_price, h.usd_value FROM portfolio_snapshots s \
+                 LEFT JOIN portfolio_snapshot_holdings h ON h.snapshot_id = s.id \
+                 WHERE s.taken_at >= ? AND s.taken_at <= ? AND s.wallet IN ({}) \
+                 ORDER BY s.taken_at, s.id, h.mint",
+                placeholders
+            ))?;
+
+            let params = [Value::Integer(from), Value::Integer(to)]
+                .into_iter()
+                .chain(wallets.into_iter().map(Value::Text));
+
+            let mut rows = stmt.query(params_from_iter(params))?;
+
+            let mut snapshots: Vec<PortfolioSnapshot> = vec![];
+            let mut last_id: Option<i64> = None;
+            while let Some(row) = rows.next()? {
+                let id: i64 = row.get(0)?;
+                if last_id != Some(id) {
+                    last_id = Some(id);
+                    snapshots.push(PortfolioSnapshot {
+                        wallet: row.get(1)?,
+                        timestamp: row.get(2)?,
+                        holdings: vec![],
+                    });
+                }
+
+                // a snapshot of an empty wallet has no holdings
+                let Some(mint) = row.get::<_, Option<String>>(3)? else {
+                    continue;
+                };
+                let raw_balance: u64 = row.get::<_, String>(6)?.parse().unwrap_or_default();
+                let decimals: u32 = row.get(7)?;
+
+                if let Some(snapshot) = snapshots.last_mut() {
+                    snapshot.holdings.push(Holding {
+                        name: row.get(4)?,
+                        symbol: row.get(5)?,
+                        address: mint,
+                        balance: TokenAmount::new(raw_balance as i128, decimals as u8).to_string(),
+                        usd_price: row.get(8)?,
+                        usd_value: row.get(9)?,
+                        raw_balance,
+                        decimals,
+                    });
+                }
+            }
+
+            Ok(snapshots)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::server::utils::constants::test::{
+        fixtures::snapshot,
+        solana_data::{OTHER_WALLET, WALLET},
+    };
+
+    #[tokio::test]
+    async fn test_get_snapshots() {
+        let mut store = SqliteSnapshotStore::open_in_memory().unwrap();
+        // beyond the signed sqlite integer
+        let whale = snapshot(WALLET, 200, u64::MAX, 1.0);
+        let empty = PortfolioSnapshot {
+            wallet: WALLET.to_string(),
+            timestamp: 300,
+            holdings: vec![],
+        };
+        for snapshot in [
+            snapshot(WALLET, 100, 1_000_000, 0.2),
+            whale.clone(),
+            empty.clone(),
+        ] {
+            store.add_snapshot(snapshot).await.unwrap();
+        }
+
+        let snapshots = store
+            .get_snapshots(&[WALLET.to_string()], 150, 300)
+            .await
+            .unwrap();
+        assert_eq!(vec![whale, empty], snapshots);
+
+        assert!(
+            store
+                .get_snapshots(&[OTHER_WALLET.to_string()], 0, 1000)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_persist_snapshots() {
+        let path = std::env::temp_dir().join(format!("sol-trace-{}.db", Uuid::new_v4()));
+        let snapshot = snapshot(WALLET, 100, 1_000_000, 0.2);
+
+        {
+            let mut store = SqliteSnapshotStore::open(&path).unwrap();
+            store.add_snapshot(snapshot.clone()).await.unwrap();
+        }
+
+        let store = SqliteSnapshotStore::open(&path).unwrap();
+        assert_eq!(
+            vec![snapshot],
+            store
+                .get_snapshots(&[WALLET.to_string()], 0, 1000)
+                .await
+                .unwrap()
+        );
+
+        drop(store);
+        std::fs::remove_file(&path).ok();
+    }
+}
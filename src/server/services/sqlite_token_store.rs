@@ -0,0 +1,287 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use rusqlite::{Connection, ErrorCode, OptionalExtension, params};
+
+use crate::server::{
+    domain::{TokenInfo, TokenStore, TokenStoreError, TransferFee},
+    services::sqlite_migrations::migrate,
+};
+
+#[derive(Clone)]
+pub struct SqliteTokenStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteTokenStore {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, rusqlite::Error> {
+        Self::build(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, rusqlite::Error> {
+        Self::build(Connection::open_in_memory()?)
+    }
+
+    fn build(mut conn: Connection) -> Result<Self, rusqlite::Error> {
+        migrate(&mut conn)?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    async fn run<T, F>(&self, f: F) -> Result<T, TokenStoreError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T, TokenStoreError> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().map_err(|_| TokenStoreError::UnexpectedError)?;
+            f(&conn)
+        })
+        .await
+        .map_err(|e| {
+            tracing::error!("Token store task failed: {}", e);
+            TokenStoreError::UnexpectedError
+        })?
+    }
+}
+
+#[async_trait::async_trait]
+impl TokenStore for SqliteTokenStore {
+    #[tracing::instrument(name = "Add token", skip_all)]
+    async fn add_token(&mut self, token: TokenInfo) -> Result<(), TokenStoreError> {
+        self.run(move |conn| {
+            conn.execute(
+                "INSERT INTO tokens (id, name, symbol, icon, decimals, token_program, \
+                 transfer_fee_basis_points, transfer_fee_maximum) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    token.id,
+                    token.name,
+                    token.symbol,
+                    token.icon,
+                    token.decimals,
+                    token.token_program,
+                    token.transfer_fee.as_ref().map(|fee| fee.basis_points),
+                    token
+                        .transfer_fee
+                        .as_ref()
+                        .map(|fee| fee.maximum_fee as i64)
+                ],
+            )
+            .map_err(|e| match e.sqlite_error_code() {
+                Some(ErrorCode::ConstraintViolation) => {
+                    tracing::error!("Token {} already exists", token.id);
+                    TokenStoreError::TokenAlreadyExists
+                }
+                _ => {
+                    tracing::error!("Failed to insert token {}: {}", token.id, e);
+                    TokenStoreError::UnexpectedError
+                }
+            })?;
+            Ok(())
+        })
+        .await
+    }
+
+    #[tracing::instrument(name = "Update token", skip_all)]
+    async fn update_token(&mut self, token: TokenInfo) -> Result<(), TokenStoreError> {
+        self.run(move |conn| {
+            let updated = conn
+                .execute(
+                    "UPDATE tokens SET name = ?2, symbol = ?3, icon = ?4, decimals = ?5, \
+                     token_program = ?6, transfer_fee_basis_points = ?7, \
+                     transfer_fee_maximum = ?8 WHERE id = ?1",
+                    params![
+                        token.id,
+                        token.name,
+                        token.symbol,
+                        token.icon,
+                        token.decimals,
+                        token.token_program,
+                        token.transfer_fee.as_ref().map(|fee| fee.basis_points),
+                        token
+                            .transfer_fee
+                            .as_ref()
+                            .map(|fee| fee.maximum_fee as i64)
+                    ],
+                )
+                .map_err(|e| {
+                    tracing::error!("Failed to update token {}: {}", token.id, e);
+                    TokenStoreError::UnexpectedError
+                })?;
+            if updated == 0 {
+                tracing::error!("Token {} not found", token.id);
+                return Err(TokenStoreError::TokenNotFound);
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    #[tracing::instrument(name = "Get token", skip_all)]
+    async fn get_token(&self, address: &str) -> Result<TokenInfo, TokenStoreError> {
+        let address = address.to_string();
+        self.run(move |conn| {
+            conn.query_row(
+                "SELECT id, name, symbol, icon, decimals, token_program, \
+                 transfer_fee_basis_points, transfer_fee_maximum FROM tokens WHERE id = ?1",
+                params![address],
+                |row| {
+                    let basis_points: Option<u16> = row.get(6)?;
+                    let maximum_fee: Option<i64> = row.get(7)?;
+                    Ok(TokenInfo {
+                        id: row.get(0)?,
+                        name: row.get(1)?,
+                        symbol: row.get(2)?,
+                        icon: row.get(3)?,
+                        decimals: row.get(4)?,
+                        token_program: row.get(5)?,
+                        transfer_fee: basis_points.zip(maximum_fee).map(
+                            |(basis_points, maximum_fee)| TransferFee {
+                                basis_points,
+                                maximum_fee: maximum_fee as u64,
+                            },
+                        ),
+                    })
+                },
+            )
+            .optional()
+            .map_err(|e| {
+                tracing::error!("Failed to query token: {}", e);
+                TokenStoreError::UnexpectedError
+            })?
+            .ok_or_else(|| {
+                tracing::error!("Token not found");
+                TokenStoreError::TokenNotFound
+            })
+        })
+        .await
+    }
+
+    async fn has_token(&self, address: &str) -> bool {
+        let address = address.to_string();
+        self.run(move |conn| {
+            conn.query_row(
+                "SELECT 1 FROM tokens WHERE id = ?1",
+                params![address],
+                |_| Ok(()),
+            )
+            .optional()
+            .map(|row| row.is_some())
+            .map_err(|_| TokenStoreError::UnexpectedError)
+        })
+        .await
+        .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn bonk() -> TokenInfo {
+        TokenInfo {
+            id: "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263".to_owned(),
+            name: "Bonk".to_owned(),
+            symbol: "Bonk".to_owned(),
+            icon: Some(
+                "https://arweave.net/hQiPZOsRZXGXBJd_82PhVdlM_hACsT_q6wqwf5cSY7I".to_owned(),
+            ),
+            decimals: 5,
+            ..Default::default()
+        }
+    }
+
+    fn token_2022() -> TokenInfo {
+        TokenInfo {
+            id: "2b1kV6DkPAnxd5ixfnxCpjxmKwqjjaYmCZfHsFu24GXo".to_owned(),
+            name: "PayPal USD".to_owned(),
+            symbol: "PYUSD".to_owned(),
+            icon: None,
+            decimals: 6,
+            token_program: Some("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb".to_owned()),
+            transfer_fee: Some(TransferFee {
+                basis_points: 50,
+                maximum_fee: 5_000_000,
+            }),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_add_token() {
+        let mut store = SqliteTokenStore::open_in_memory().unwrap();
+
+        let result = store.add_token(bonk()).await;
+        assert!(result.is_ok());
+
+        let result = store.add_token(bonk()).await;
+        assert_eq!(result.unwrap_err(), TokenStoreError::TokenAlreadyExists);
+    }
+
+    #[tokio::test]
+    async fn test_get_token() {
+        let mut store = SqliteTokenStore::open_in_memory().unwrap();
+        let token = bonk();
+        store.add_token(token.clone()).await.unwrap();
+
+        assert_eq!(store.get_token(&token.id).await.unwrap(), token);
+
+        let other_token_mint = "EKpQGSJtjMFqKZ9KQanSqYXRcF8fBopzLHYxdM65zcjm";
+        assert_eq!(
+            store.get_token(other_token_mint).await.unwrap_err(),
+            TokenStoreError::TokenNotFound
+        );
+        assert!(store.has_token(&token.id).await);
+        assert!(!store.has_token(other_token_mint).await);
+
+        let token = token_2022();
+        store.add_token(token.clone()).await.unwrap();
+        assert_eq!(store.get_token(&token.id).await.unwrap(), token);
+    }
+
+    #[tokio::test]
+    async fn test_update_token() {
+        let mut store = SqliteTokenStore::open_in_memory().unwrap();
+        let token = token_2022();
+        assert_eq!(
+            store.update_token(token.clone()).await.unwrap_err(),
+            TokenStoreError::TokenNotFound
+        );
+
+        // stored without its mint info, as the rows before the token program column
+        store
+            .add_token(TokenInfo {
+                token_program: None,
+                transfer_fee: None,
+                ..token.clone()
+            })
+            .await
+            .unwrap();
+        store.update_token(token.clone()).await.unwrap();
+        assert_eq!(store.get_token(&token.id).await.unwrap(), token);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_persist_tokens() {
+        let path = std::env::temp_dir().join(format!("sol-trace-{}.db", Uuid::new_v4()));
+        let token = bonk();
+
+        {
+            let mut store = SqliteTokenStore::open(&path).unwrap();
+            store.add_token(token.clone()).await.unwrap();
+        }
+
+        let store = SqliteTokenStore::open(&path).unwrap();
+        assert_eq!(store.get_token(&token.id).await.unwrap(), token);
+
+        drop(store);
+        std::fs::remove_file(&path).ok();
+    }
+}
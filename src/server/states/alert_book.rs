@@ -0,0 +1,169 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::{
+    proto::{Alert, AlertCondition, AlertEvent},
+    server::domain::TokenPrice,
+};
+
+#[derive(Clone)]
+struct AlertState {
+    alert: Alert,
+    // a triggered repeating alert waits for its condition to clear
+    armed: bool,
+}
+
+// the price alerts of a client
+#[derive(Clone, Default)]
+pub struct AlertBook {
+    next_id: u64,
+    alerts: BTreeMap<u64, AlertState>,
+}
+
+impl AlertBook {
+    pub fn add(
+        &mut self,
+        mint: String,
+        condition: AlertCondition,
+        value: f64,
+        repeat: bool,
+    ) -> Alert {
+        self.next_id += 1;
+
+        let mut alert = Alert {
+            id: self.next_id,
+            mint,
+            value,
+            repeat,
+            ..Default::default()
+        };
+        alert.set_condition(condition);
+
+        self.alerts.insert(
+            alert.id,
+            AlertState {
+                alert: alert.clone(),
+                armed: true,
+            },
+        );
+        alert
+    }
+
+    pub fn remove(&mut self, id: u64) -> bool {
+        self.alerts.remove(&id).is_some()
+    }
+
+    pub fn list(&self) -> Vec<Alert> {
+        self.alerts
+            .values()
+            .map(|alert_state| alert_state.alert.clone())
+            .collect()
+    }
+
+    pub fn mints(&self) -> HashSet<String> {
+        self.alerts
+            .values()
+            .map(|alert_state| alert_state.alert.mint.clone())
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.alerts.is_empty()
+    }
+
+    // the triggered alerts, the one-shot ones are removed
+    pub fn check(&mut self, prices: &HashMap<String, TokenPrice>) -> Vec<AlertEvent> {
+        let mut events: Vec<AlertEvent> = vec![];
+
+        for alert_state in self.alerts.values_mut() {
+            let Some(price) = prices.get(&alert_state.alert.mint) else {
+                continue;
+            };
+
+            let met = is_met(&alert_state.alert, price);
+            if met && alert_state.armed {
+                events.push(AlertEvent {
+                    alert: Some(alert_state.alert.clone()),
+                    usd_price: price.usd_price,
+                    price_change_24h: price.price_change_24h,
+                });
+            }
+            alert_state.armed = !met;
+        }
+
+        self.alerts
+            .retain(|_, alert_state| alert_state.alert.repeat || alert_state.armed);
+
+        events
+    }
+}
+
+fn is_met(alert: &Alert, price: &TokenPrice) -> bool {
+    match alert.condition() {
+        AlertCondition::PriceAbove => price.usd_price >= alert.value,
+        AlertCondition::PriceBelow => price.usd_price <= alert.value,
+        AlertCondition::Change24hUp => price
+            .price_change_24h
+            .is_some_and(|change| change >= alert.value),
+        AlertCondition::Change24hDown => price
+            .price_change_24h
+            .is_some_and(|change| change <= -alert.value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::utils::constants::test::solana_data::TOKEN1;
+
+    fn prices(usd_price: f64, price_change_24h: Option<f64>) -> HashMap<String, TokenPrice> {
+        HashMap::from([(
+            TOKEN1.to_string(),
+            TokenPrice {
+                usd_price,
+                block_id: 0,
+                decimals: 6,
+                price_change_24h,
+            },
+        )])
+    }
+
+    #[test]
+    fn one_shot_alert() {
+        let mut alert_book = AlertBook::default();
+        alert_book.add(TOKEN1.to_string(), AlertCondition::PriceAbove, 2.0, false);
+
+        assert!(alert_book.check(&prices(1.5, None)).is_empty());
+
+        let events = alert_book.check(&prices(2.5, None));
+        assert_eq!(1, events.len());
+        assert_eq!(2.5, events[0].usd_price);
+        assert!(alert_book.is_empty());
+    }
+
+    #[test]
+    fn repeating_alert_is_rearmed() {
+        let mut alert_book = AlertBook::default();
+        alert_book.add(
+            TOKEN1.to_string(),
+            AlertCondition::Change24hDown,
+            10.0,
+            true,
+        );
+
+        assert_eq!(1, alert_book.check(&prices(1.0, Some(-12.0))).len());
+        // still down, not triggered again until the move clears
+        assert!(alert_book.check(&prices(1.0, Some(-15.0))).is_empty());
+        assert!(alert_book.check(&prices(1.0, Some(-5.0))).is_empty());
+        assert_eq!(1, alert_book.check(&prices(1.0, Some(-10.0))).len());
+        assert_eq!(1, alert_book.list().len());
+    }
+
+    #[test]
+    fn missing_price_change() {
+        let mut alert_book = AlertBook::default();
+        alert_book.add(TOKEN1.to_string(), AlertCondition::Change24hUp, 10.0, false);
+
+        assert!(alert_book.check(&prices(1.0, None)).is_empty());
+        assert!(!alert_book.is_empty());
+    }
+}
@@ -1,51 +1,135 @@
 use crate::{
     proto::InitRequest,
     server::{
+        auth::Principal,
         domain::{SubscriptionInput, WebSocketClient},
-        states::SubscriptionState,
+        states::{AlertBook, SubscriptionState, TradeLedger, TradeLedgerType},
         utils::gen_token_account,
     },
 };
 use std::{
-    collections::{HashMap, HashSet},
-    sync::Arc,
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
 };
 use tokio::sync::RwLock;
 
 pub type WebSocketClientType = Arc<RwLock<Box<dyn WebSocketClient + Send + Sync>>>;
 
 #[derive(Clone)]
-pub struct ClientState {
-    pub ws_client: WebSocketClientType,
+pub struct WalletState {
     pub subscription_input: Arc<SubscriptionInput>,
     pub token_account_map: Arc<HashMap<String, String>>,
+}
+
+impl WalletState {
+    // tokens: mint -> token program
+    pub fn build(wallet: String, tokens: &HashMap<String, String>) -> Self {
+        // !! mutabale only here
+        let mut token_account_map = HashMap::new();
+
+        for (token_mint, token_program) in tokens {
+            token_account_map.insert(
+                token_mint.clone(),
+                gen_token_account(&wallet, token_mint, token_program),
+            );
+        }
+
+        Self {
+            subscription_input: Arc::new(SubscriptionInput::new(
+                wallet,
+                tokens.keys().cloned().collect(),
+            )),
+            token_account_map: Arc::new(token_account_map),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ClientState {
+    // the sessions are only accessible with an API key of their principal
+    pub principal: Principal,
+    pub ws_client: WebSocketClientType,
+    // mint -> token program
+    pub tokens: HashMap<String, String>,
+    pub wallets: BTreeMap<String, WalletState>,
     pub logs_subscription: Option<SubscriptionState>,
+    pub trade_ledger: TradeLedgerType,
+    pub alerts: AlertBook,
+    // the last request of the client, shared to be touched under the read lock
+    pub last_seen: Arc<Mutex<Instant>>,
 }
 
 impl ClientState {
     pub fn build(
         request: InitRequest,
+        principal: Principal,
+        token_programs: HashMap<String, String>,
         factory: Arc<dyn Fn() -> Box<dyn WebSocketClient + Send + Sync> + Send + Sync>,
     ) -> Self {
         let ws_client = factory();
 
-        let tokens: HashSet<String> = request.tokens.into_iter().collect();
+        let mut client_state = Self {
+            principal,
+            ws_client: Arc::new(RwLock::new(ws_client)),
+            tokens: token_programs,
+            wallets: BTreeMap::new(),
+            logs_subscription: None,
+            trade_ledger: Arc::new(RwLock::new(TradeLedger::default())),
+            alerts: AlertBook::default(),
+            last_seen: Arc::new(Mutex::new(Instant::now())),
+        };
 
-        // !! mutabale only here
-        let mut token_account_map = HashMap::new();
+        for wallet in request.all_wallets() {
+            client_state.add_wallet(wallet);
+        }
 
-        for token_mint in &tokens {
-            token_account_map.insert(
-                token_mint.clone(),
-                gen_token_account(&request.wallet, token_mint),
-            );
+        client_state
+    }
+
+    pub fn add_wallet(&mut self, wallet: String) -> Option<&WalletState> {
+        if self.wallets.contains_key(&wallet) {
+            return None;
         }
 
-        Self {
-            ws_client: Arc::new(RwLock::new(ws_client)),
-            subscription_input: Arc::new(SubscriptionInput::new(request.wallet, tokens)),
-            token_account_map: Arc::new(token_account_map),
-            logs_subscription: None,
+        let wallet_state = WalletState::build(wallet.clone(), &self.tokens);
+        self.wallets.insert(wallet.clone(), wallet_state);
+        self.wallets.get(&wallet)
+    }
+
+    pub fn wallet_list(&self) -> Vec<String> {
+        self.wallets.keys().cloned().collect()
+    }
+
+    // the wallet states are rebuilt with the new token accounts, live subscriptions
+    // keep their input as the token list does not affect the trade detection,
+    // only their stream filter is updated
+    pub async fn set_tokens(&mut self, tokens: HashMap<String, String>) {
+        self.tokens = tokens;
+
+        for (wallet, wallet_state) in self.wallets.iter_mut() {
+            *wallet_state = WalletState::build(wallet.clone(), &self.tokens);
+        }
+
+        if let Some(subscription) = &self.logs_subscription {
+            subscription.event_filter.write().await.tokens = self.tokens.keys().cloned().collect();
         }
     }
+
+    pub fn touch(&self) {
+        *self.last_seen.lock().expect("Poisoned last seen lock") = Instant::now();
+    }
+
+    pub fn idle_for(&self) -> Duration {
+        self.last_seen
+            .lock()
+            .expect("Poisoned last seen lock")
+            .elapsed()
+    }
+
+    pub fn token_list(&self) -> Vec<String> {
+        let mut tokens: Vec<String> = self.tokens.keys().cloned().collect();
+        tokens.sort();
+        tokens
+    }
 }
@@ -0,0 +1,195 @@
+use std::{collections::HashSet, sync::Arc};
+
+use tokio::sync::RwLock;
+
+use crate::{
+    proto::{StreamFilter, Trade, TradeSide, Transfer},
+    server::{
+        domain::WalletEvent,
+        utils::constants::{DUST_USD_VALUE, USDC, WSOL},
+    },
+};
+
+pub type EventFilterType = Arc<RwLock<EventFilter>>;
+
+// the stream filter of a subscription, the tracked tokens follow the client's token list
+#[derive(Debug, Default)]
+pub struct EventFilter {
+    pub spec: StreamFilter,
+    pub tokens: HashSet<String>,
+}
+
+impl EventFilter {
+    pub fn new(spec: StreamFilter, tokens: HashSet<String>) -> Self {
+        Self { spec, tokens }
+    }
+
+    pub fn accepts(&self, event: &WalletEvent) -> bool {
+        let (trade, legs, value) = match event {
+            WalletEvent::Trade(trade) => (
+                Some(trade),
+                trade.from.iter().chain(trade.to.iter()).collect::<Vec<_>>(),
+                calc_trade_value(trade),
+            ),
+            WalletEvent::Activity(activity) => {
+                let legs: Vec<&Transfer> = activity
+                    .incoming
+                    .iter()
+                    .chain(activity.outgoing.iter())
+                    .collect();
+                let value = calc_value(&legs);
+                (None, legs, value)
+            }
+        };
+
+        if self.spec.trades_only && trade.is_none() {
+            return false;
+        }
+
+        if self.spec.tracked_tokens_only
+            && !legs
+                .iter()
+                .any(|transfer| transfer.mint != WSOL && self.tokens.contains(&transfer.mint))
+        {
+            return false;
+        }
+
+        if self.spec.side.is_some()
+            && !trade.is_some_and(|trade| {
+                let is_token = |transfer: &Transfer| transfer.mint != WSOL && transfer.mint != USDC;
+                match self.spec.side() {
+                    TradeSide::Buy => trade.to.iter().any(is_token),
+                    TradeSide::Sell => trade.from.iter().any(is_token),
+                }
+            })
+        {
+            return false;
+        }
+
+        if !self.spec.venues.is_empty()
+            && !trade.is_some_and(|trade| self.spec.venues.contains(&trade.venue))
+        {
+            return false;
+        }
+
+        if let Some(min_usd_value) = self.spec.min_usd_value
+            && !value.is_some_and(|value| value >= min_usd_value)
+        {
+            return false;
+        }
+
+        // an unpriced trade is not known to be dust
+        if self.spec.exclude_dust && !value.map_or(trade.is_some(), |value| value >= DUST_USD_VALUE)
+        {
+            return false;
+        }
+
+        true
+    }
+}
+
+// the priced legs of the larger side
+fn calc_trade_value(trade: &Trade) -> Option<f64> {
+    let from_value = calc_value(&trade.from.iter().collect::<Vec<_>>());
+    let to_value = calc_value(&trade.to.iter().collect::<Vec<_>>());
+    match (from_value, to_value) {
+        (Some(from_value), Some(to_value)) => Some(from_value.max(to_value)),
+        (value, None) | (None, value) => value,
+    }
+}
+
+fn calc_value(transfers: &[&Transfer]) -> Option<f64> {
+    transfers
+        .iter()
+        .filter_map(|transfer| transfer.usd_price.map(|price| price * transfer.amount))
+        .reduce(|a, b| a + b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        proto::{Activity, Venue},
+        server::utils::constants::test::{fixtures::transfer, solana_data::TOKEN1},
+    };
+
+    // buys 1000 TOKEN1 for 1 SOL on Raydium
+    fn buy() -> WalletEvent {
+        let mut trade = Trade {
+            from: vec![transfer(WSOL, 1.0, Some(150.0))],
+            to: vec![transfer(TOKEN1, 1000.0, None)],
+            ..Default::default()
+        };
+        trade.set_venue(Venue::RaydiumAmm);
+        WalletEvent::Trade(trade)
+    }
+
+    fn airdrop(usd_price: Option<f64>) -> WalletEvent {
+        WalletEvent::Activity(Activity {
+            incoming: vec![transfer(TOKEN1, 10.0, usd_price)],
+            ..Default::default()
+        })
+    }
+
+    fn event_filter_of(spec: StreamFilter) -> EventFilter {
+        EventFilter::new(spec, HashSet::from([WSOL.to_string()]))
+    }
+
+    #[test]
+    fn no_rules() {
+        let event_filter = event_filter_of(StreamFilter::default());
+
+        assert!(event_filter.accepts(&buy()));
+        assert!(event_filter.accepts(&airdrop(None)));
+    }
+
+    #[test]
+    fn tracked_tokens() {
+        let mut event_filter = event_filter_of(StreamFilter {
+            tracked_tokens_only: true,
+            ..Default::default()
+        });
+        assert!(!event_filter.accepts(&buy()));
+
+        event_filter.tokens.insert(TOKEN1.to_string());
+        assert!(event_filter.accepts(&buy()));
+    }
+
+    #[test]
+    fn side_and_venue() {
+        let mut spec = StreamFilter::default();
+        spec.set_side(TradeSide::Buy);
+        spec.venues.push(Venue::RaydiumAmm as i32);
+        let event_filter = event_filter_of(spec);
+        assert!(event_filter.accepts(&buy()));
+        assert!(!event_filter.accepts(&airdrop(Some(1.0))));
+
+        let mut spec = StreamFilter::default();
+        spec.set_side(TradeSide::Sell);
+        assert!(!event_filter_of(spec).accepts(&buy()));
+
+        let mut spec = StreamFilter::default();
+        spec.venues.push(Venue::Jupiter as i32);
+        assert!(!event_filter_of(spec).accepts(&buy()));
+    }
+
+    #[test]
+    fn usd_value() {
+        let event_filter = event_filter_of(StreamFilter {
+            min_usd_value: Some(100.0),
+            ..Default::default()
+        });
+        assert!(event_filter.accepts(&buy()));
+        assert!(!event_filter.accepts(&airdrop(Some(1.0))));
+        assert!(!event_filter.accepts(&airdrop(None)));
+
+        let event_filter = event_filter_of(StreamFilter {
+            exclude_dust: true,
+            ..Default::default()
+        });
+        assert!(event_filter.accepts(&buy()));
+        assert!(event_filter.accepts(&airdrop(Some(1.0))));
+        assert!(!event_filter.accepts(&airdrop(Some(0.01))));
+        assert!(!event_filter.accepts(&airdrop(None)));
+    }
+}
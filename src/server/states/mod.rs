@@ -1,7 +1,13 @@
+pub mod alert_book;
 pub mod app_state;
 pub mod client_state;
+pub mod event_filter;
 pub mod subscription_state;
+pub mod trade_ledger;
 
+pub use alert_book::AlertBook;
 pub use app_state::AppState;
-pub use client_state::ClientState;
+pub use client_state::{ClientState, WalletState};
+pub use event_filter::{EventFilter, EventFilterType};
 pub use subscription_state::SubscriptionState;
+pub use trade_ledger::{TradeLedger, TradeLedgerType};
@@ -1,4 +1,28 @@
+use std::collections::HashMap;
+
+use tokio::sync::mpsc;
+use tonic::Status;
+
+use crate::{
+    proto::SubscribeResponse,
+    server::states::{EventFilterType, client_state::WebSocketClientType},
+};
+
 #[derive(Clone)]
 pub struct SubscriptionState {
-    pub subscription_id: u64,
+    // wallet -> logs subscription id
+    pub subscription_ids: HashMap<String, u64>,
+    // kept to multiplex the wallets added later into the same stream
+    pub tx: mpsc::Sender<Result<SubscribeResponse, Status>>,
+    pub event_filter: EventFilterType,
+}
+
+impl SubscriptionState {
+    // the logs subscriptions of every wallet of the stream
+    pub async fn close(self, ws_client: &WebSocketClientType) {
+        let mut ws_client = ws_client.write().await;
+        for subscription_id in self.subscription_ids.into_values() {
+            let _ = ws_client.logs_unsubscribe(subscription_id).await;
+        }
+    }
 }
@@ -0,0 +1,45 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use tokio::sync::RwLock;
+
+use crate::proto::{Activity, Trade};
+
+pub type TradeLedgerType = Arc<RwLock<TradeLedger>>;
+
+// trades ordered by slot, so positions can be replayed chronologically
+// regardless of whether they arrived from the live stream or a backfill
+#[derive(Default)]
+pub struct TradeLedger {
+    // (slot, signature, wallet): a transaction can be a trade for several wallets of the client
+    trades: BTreeMap<(u64, String, String), Trade>,
+    // the non-swap activity, with the same key
+    activities: BTreeMap<(u64, String, String), Activity>,
+}
+
+impl TradeLedger {
+    pub fn add_trade(&mut self, trade: Trade) -> bool {
+        let key = (trade.slot, trade.signature.clone(), trade.wallet.clone());
+        if self.trades.contains_key(&key) {
+            return false;
+        }
+        self.trades.insert(key, trade);
+        true
+    }
+
+    pub fn add_activity(&mut self, activity: Activity) -> bool {
+        let key = (
+            activity.slot,
+            activity.signature.clone(),
+            activity.wallet.clone(),
+        );
+        if self.activities.contains_key(&key) {
+            return false;
+        }
+        self.activities.insert(key, activity);
+        true
+    }
+
+    pub fn trades(&self) -> impl Iterator<Item = &Trade> {
+        self.trades.values()
+    }
+}
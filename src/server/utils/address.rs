@@ -1,6 +1,8 @@
 use std::error::Error;
 
-use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::{
+    get_associated_token_address, get_associated_token_address_with_program_id,
+};
 use spl_token::solana_program::pubkey::Pubkey;
 use tracing::error;
 
@@ -8,30 +10,42 @@ use crate::{proto::InitRequest, server::domain::InputValidationError};
 
 #[tracing::instrument(name = "Validate init request data", skip_all)]
 pub fn validate_init_data(init_request: &InitRequest) -> Result<(), InputValidationError> {
-    if validate_address(&init_request.wallet).is_err() {
-        error!(
-            "{}: {}",
-            InputValidationError::InvalidWalletAddress,
-            init_request.wallet
-        );
-        return Err(InputValidationError::InvalidWalletAddress);
+    validate_wallets(&init_request.all_wallets())?;
+    validate_tokens(&init_request.tokens)
+}
+
+pub fn validate_wallets(wallets: &[String]) -> Result<(), InputValidationError> {
+    if wallets.is_empty() {
+        error!("{}", InputValidationError::MissingWallets);
+        return Err(InputValidationError::MissingWallets);
+    }
+
+    for wallet in wallets {
+        if validate_address(wallet).is_err() {
+            error!("{}: {}", InputValidationError::InvalidWalletAddress, wallet);
+            return Err(InputValidationError::InvalidWalletAddress);
+        }
     }
 
-    if init_request.tokens.len() == 0 {
+    Ok(())
+}
+
+pub fn validate_tokens(tokens: &[String]) -> Result<(), InputValidationError> {
+    if tokens.is_empty() {
         error!("{}", InputValidationError::MissingTokens);
-        return Err(InputValidationError::MissingTokens)?;
+        return Err(InputValidationError::MissingTokens);
     }
 
     let mut invalid_tokens: Vec<String> = vec![];
 
-    for token_mint in &init_request.tokens {
+    for token_mint in tokens {
         match validate_address(token_mint) {
             Ok(_) => {}
             Err(_) => invalid_tokens.push(token_mint.clone()),
         }
     }
 
-    if invalid_tokens.len() > 0 {
+    if !invalid_tokens.is_empty() {
         let err = InputValidationError::InvalidTokenAddress(format!(
             "Invalid token{} {}",
             if invalid_tokens.len() > 1 { "s:" } else { ":" },
@@ -51,14 +65,15 @@ fn validate_address(address: &str) -> Result<(), Box<dyn Error>> {
 }
 
 pub fn get_token_account(wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
-    get_associated_token_address(&wallet, &mint)
+    get_associated_token_address(wallet, mint)
 }
 
-pub fn gen_token_account(wallet: &String, mint: &String) -> String {
+pub fn gen_token_account(wallet: &str, mint: &str, token_program: &str) -> String {
     // unwrap is safe at this point
     let wallet: Pubkey = wallet.parse().unwrap();
     let mint: Pubkey = mint.parse().unwrap();
-    get_associated_token_address(&wallet, &mint).to_string()
+    let token_program: Pubkey = token_program.parse().unwrap();
+    get_associated_token_address_with_program_id(&wallet, &mint, &token_program).to_string()
 }
 
 #[cfg(test)]
@@ -67,6 +82,7 @@ mod tests {
     use crate::server::utils::constants::test::solana_data::{
         INVALID_TOKEN1, INVALID_WALLET, TOKEN1, WALLET,
     };
+    use crate::server::utils::constants::{TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID};
 
     use super::*;
 
@@ -75,6 +91,7 @@ mod tests {
         let init_request = InitRequest {
             wallet: WALLET.to_owned(),
             tokens: vec![TOKEN1.to_owned()],
+            wallets: vec![],
         };
 
         let result = validate_init_data(&init_request);
@@ -86,6 +103,7 @@ mod tests {
         let init_request = InitRequest {
             wallet: INVALID_WALLET.to_owned(),
             tokens: vec![],
+            wallets: vec![],
         };
 
         let result = validate_init_data(&init_request);
@@ -102,6 +120,7 @@ mod tests {
         let init_request = InitRequest {
             wallet: WALLET.to_owned(),
             tokens: vec![],
+            wallets: vec![],
         };
 
         let result = validate_init_data(&init_request);
@@ -115,6 +134,7 @@ mod tests {
         let init_request = InitRequest {
             wallet: WALLET.to_owned(),
             tokens: vec![INVALID_TOKEN1.to_string()],
+            wallets: vec![],
         };
 
         let result = validate_init_data(&init_request);
@@ -125,4 +145,45 @@ mod tests {
             result.unwrap_err()
         )
     }
+
+    #[test]
+    fn missing_wallet() {
+        let init_request = InitRequest {
+            wallet: "".to_owned(),
+            tokens: vec![TOKEN1.to_owned()],
+            wallets: vec![],
+        };
+
+        let result = validate_init_data(&init_request);
+
+        assert_eq!(InputValidationError::MissingWallets, result.unwrap_err())
+    }
+
+    #[test]
+    fn invalid_additional_wallet() {
+        let init_request = InitRequest {
+            wallet: WALLET.to_owned(),
+            tokens: vec![TOKEN1.to_owned()],
+            wallets: vec![INVALID_WALLET.to_owned()],
+        };
+
+        let result = validate_init_data(&init_request);
+
+        assert_eq!(
+            InputValidationError::InvalidWalletAddress,
+            result.unwrap_err()
+        )
+    }
+
+    #[test]
+    fn token_account_of_the_token_program() {
+        let classic = gen_token_account(WALLET, TOKEN1, TOKEN_PROGRAM_ID);
+        let token_2022 = gen_token_account(WALLET, TOKEN1, TOKEN_2022_PROGRAM_ID);
+
+        assert_eq!(
+            get_token_account(&WALLET.parse().unwrap(), &TOKEN1.parse().unwrap()).to_string(),
+            classic
+        );
+        assert_ne!(classic, token_2022);
+    }
 }
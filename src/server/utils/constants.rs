@@ -3,42 +3,178 @@ use lazy_static::lazy_static;
 use std::env as std_env;
 
 pub const WSOL: &str = "So11111111111111111111111111111111111111112";
+pub const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+pub const TOKEN_PROGRAM_ID: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+pub const TOKEN_2022_PROGRAM_ID: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+pub const STAKE_PROGRAM_ID: &str = "Stake11111111111111111111111111111111111111";
+// limit order and DCA programs filling orders from escrow on behalf of the wallet
+pub const KEEPER_PROGRAM_IDS: &[&str] = &[
+    "jupoNjAxXgZ4rjzxzPMP4oxduvQsQtZzyknqvzYNrNu", // Jupiter Limit Order
+    "j1o2qRpjcyUwEvwtcfhEQefh773ZgjxcVRry7LDqg5X", // Jupiter Limit Order v2
+    "DCA265Vj8a9CEuX1eb1LWRnDT7uK6q1xMipnNyatn23M", // Jupiter DCA
+];
+// Jito block engine tip accounts
+pub const JITO_TIP_ACCOUNTS: &[&str] = &[
+    "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5",
+    "HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe",
+    "Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY",
+    "ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49",
+    "DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh",
+    "ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt",
+    "DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL",
+    "3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT",
+];
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
 pub const SOL_DENOM: f64 = 1_000_000_000.0; // lamports in 1 SOL
+pub const SOL_DECIMALS: u8 = 9;
+pub const DEFAULT_HISTORY_LIMIT: usize = 100;
+pub const MAX_HISTORY_LIMIT: usize = 2_000; // every signature costs a getTransaction
+pub const MAX_SIGNATURES_PAGE: usize = 1000; // getSignaturesForAddress limit
+pub const MAX_GAP_FILL_SIGNATURES: usize = 5000;
+pub const MAX_MULTIPLE_ACCOUNTS: usize = 100; // getMultipleAccounts limit
+pub const MAX_CONCURRENT_BALANCE_REQUESTS: usize = 8;
+pub const WS_RECONNECT_BASE_DELAY_MS: u64 = 500;
+pub const WS_RECONNECT_MAX_DELAY_MS: u64 = 30_000;
+pub const ENDPOINT_COOLDOWN_BASE_SECS: u64 = 5;
+pub const ENDPOINT_COOLDOWN_MAX_SECS: u64 = 300;
+pub const ENDPOINT_HEALTH_CHECK_INTERVAL_SECS: u64 = 30;
+// JSON-RPC errors of an endpoint that another endpoint may not have
+pub const FAILOVER_RPC_ERROR_CODES: &[i64] = &[
+    -32005, // node is behind
+    -32016, // minimum context slot not reached
+    -32429, // rate limited
+    429,    // rate limited
+];
+
+// lite-api limits
+pub const JUPITER_MAX_TOKENS_PER_REQUEST: usize = 100;
+pub const JUPITER_MAX_PRICES_PER_REQUEST: usize = 50;
+pub const JUPITER_MAX_RETRIES: u32 = 3;
+pub const JUPITER_RETRY_BASE_DELAY_MS: u64 = 1_000;
+pub const JUPITER_RETRY_MAX_DELAY_MS: u64 = 30_000;
+pub const RECENT_PRICE_WINDOW_SECS: u64 = 120; // current price is accepted for trades this fresh
+pub const DUST_USD_VALUE: f64 = 1.0;
+pub const PRICE_WATCH_INTERVAL_SECS: u64 = 30;
+pub const SNAPSHOT_INTERVAL_SECS: u64 = 300;
+pub const DEFAULT_PORTFOLIO_RANGE_SECS: i64 = 86_400;
+pub const DEFAULT_PORTFOLIO_RESOLUTION_SECS: u64 = 3_600;
 
 lazy_static! {
-    pub static ref SOLANA_WS_URL: String = set_solana_ws_url();
-    pub static ref SOLANA_RPC_URL: String = set_solana_rpc_url();
+    pub static ref SOLANA_WS_URL: Option<String> = set_solana_ws_url();
+    pub static ref SOLANA_RPC_URL: Option<String> = set_solana_rpc_url();
+    pub static ref SERVER_CONFIG: Option<String> = set_server_config();
+    pub static ref BIRDEYE_API_KEY: Option<String> = set_birdeye_api_key();
+    pub static ref TOKEN_STORE_DB: Option<String> = set_token_store_db();
+    pub static ref SNAPSHOT_STORE_DB: Option<String> = set_snapshot_store_db();
 }
 
-fn set_solana_ws_url() -> String {
+// the single endpoints used when the server config has none
+fn set_solana_ws_url() -> Option<String> {
     dotenv().ok(); // Load environment variables
-    let ws_url = std_env::var(env::SOLANA_WS_URL_ENV_VAR).expect("SOLANA_WS_URL must be set.");
-    if ws_url.is_empty() {
-        panic!("SOLANA_WS_URL must not be empty.");
-    }
-    ws_url
+    std_env::var(env::SOLANA_WS_URL_ENV_VAR)
+        .ok()
+        .filter(|url| !url.is_empty())
 }
 
-fn set_solana_rpc_url() -> String {
+fn set_solana_rpc_url() -> Option<String> {
     dotenv().ok(); // Load environment variables
-    let rpc_url = std_env::var(env::SOLANA_RPC_URL_ENV_VAR).expect("SOLANA_RPC_URL must be set.");
-    if rpc_url.is_empty() {
-        panic!("SOLANA_RPC_URL must not be empty.");
-    }
-    rpc_url
+    std_env::var(env::SOLANA_RPC_URL_ENV_VAR)
+        .ok()
+        .filter(|url| !url.is_empty())
+}
+
+fn set_server_config() -> Option<String> {
+    dotenv().ok(); // Load environment variables
+    std_env::var(env::SERVER_CONFIG_ENV_VAR)
+        .ok()
+        .filter(|path| !path.is_empty())
+}
+
+fn set_birdeye_api_key() -> Option<String> {
+    dotenv().ok(); // Load environment variables
+    std_env::var(env::BIRDEYE_API_KEY_ENV_VAR)
+        .ok()
+        .filter(|key| !key.is_empty())
+}
+
+fn set_token_store_db() -> Option<String> {
+    dotenv().ok(); // Load environment variables
+    std_env::var(env::TOKEN_STORE_DB_ENV_VAR)
+        .ok()
+        .filter(|path| !path.is_empty())
+}
+
+fn set_snapshot_store_db() -> Option<String> {
+    dotenv().ok(); // Load environment variables
+    std_env::var(env::SNAPSHOT_STORE_DB_ENV_VAR)
+        .ok()
+        .filter(|path| !path.is_empty())
 }
 
 pub mod env {
     pub const SOLANA_WS_URL_ENV_VAR: &str = "SOLANA_WS_URL";
     pub const SOLANA_RPC_URL_ENV_VAR: &str = "SOLANA_RPC_URL";
+    pub const SERVER_CONFIG_ENV_VAR: &str = "SERVER_CONFIG";
+    pub const BIRDEYE_API_KEY_ENV_VAR: &str = "BIRDEYE_API_KEY";
+    pub const TOKEN_STORE_DB_ENV_VAR: &str = "TOKEN_STORE_DB";
+    pub const SNAPSHOT_STORE_DB_ENV_VAR: &str = "SNAPSHOT_STORE_DB";
 }
 
 pub mod test {
     pub mod solana_data {
         pub const WALLET: &str = "9AhKqLR67hwapvG8SA2JFXaCshXc9nALJjpKaHZrsbkw";
         pub const TOKEN1: &str = "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263";
+        pub const OTHER_WALLET: &str = "EKpQGSJtjMFqKZ9KQanSqYXRcF8fBopzLHYxdM65zcjm";
+        // PYUSD, a Token-2022 mint with 6 decimals
+        pub const TOKEN_2022_MINT: &str = "2b1kV6DkPAnxd5ixfnxCpjxmKwqjjaYmCZfHsFu24GXo";
+
+        // the WALLET's token accounts in the getMultipleAccounts base64 encoding:
+        // 1234.56789 TOKEN1 under the SPL Token program
+        pub const SPL_TOKEN_ACCOUNT_DATA: &str = "vAfFbmCtPT8Xc4LqxlSPuh/TLP2QygKz58+hhf3Oc5h5WVFn2kgMWuE0RQHSEbdzY0Dj+98A7N5jtk3IiswvHBXNWwcAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAQAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA";
+        // 2.5 TOKEN_2022_MINT, with the immutable owner and transfer fee amount extensions
+        pub const TOKEN_2022_ACCOUNT_DATA: &str = "F5JIO2yKKoe3Rx2BT5WR+TlchAqc49n01bp9OkuKdJ55WVFn2kgMWuE0RQHSEbdzY0Dj+98A7N5jtk3IiswvHKAlJgAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAQAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAgcAAAACAAgAAAAAAAAAAAA=";
 
         pub const INVALID_WALLET: &str = "9AhKqLR67hwapvG8SA2JFXaCshXc9nALJjpKaHZrsbk_";
         pub const INVALID_TOKEN1: &str = "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB26_";
     }
+
+    pub mod fixtures {
+        use super::solana_data::TOKEN1;
+        use crate::{
+            proto::{Holding, Transfer},
+            server::domain::{PortfolioSnapshot, TokenAmount},
+        };
+
+        // `amount` of a 6 decimals token
+        pub fn transfer(mint: &str, amount: f64, usd_price: Option<f64>) -> Transfer {
+            let raw = (amount * 1_000_000.0).round() as i128;
+            Transfer {
+                usd_price,
+                ..Transfer::new(mint.to_string(), TokenAmount::new(raw, 6))
+            }
+        }
+
+        // the wallet holds `raw_balance` Bonk
+        pub fn snapshot(
+            wallet: &str,
+            timestamp: i64,
+            raw_balance: u64,
+            usd_value: f64,
+        ) -> PortfolioSnapshot {
+            PortfolioSnapshot {
+                wallet: wallet.to_string(),
+                timestamp,
+                holdings: vec![Holding {
+                    name: "Bonk".to_string(),
+                    symbol: "Bonk".to_string(),
+                    address: TOKEN1.to_string(),
+                    balance: TokenAmount::new(raw_balance as i128, 5).to_string(),
+                    usd_price: Some(0.00002),
+                    usd_value: Some(usd_value),
+                    raw_balance,
+                    decimals: 5,
+                }],
+            }
+        }
+    }
 }
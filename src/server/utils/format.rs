@@ -1,9 +1,25 @@
 use num_format::{Locale, ToFormattedString};
 
+use crate::server::domain::TokenAmount;
+
 pub fn fmt_token(value: f64) -> String {
     fmt_ccy(value, "")
 }
 
+// exact, with at least two decimals
+pub fn fmt_token_amount(amount: &TokenAmount) -> String {
+    let exact = amount.abs().to_string();
+    let (int_str, frac_str) = exact.split_once('.').unwrap_or((&exact, ""));
+    let int_part: u128 = int_str.parse().unwrap_or(0);
+
+    format!(
+        "{}{}.{:0<2}",
+        if amount.is_negative() { "-" } else { "" },
+        int_part.to_formatted_string(&Locale::en),
+        frac_str
+    )
+}
+
 pub fn fmt_usd(value: f64) -> String {
     fmt_ccy(value, "$")
 }
@@ -32,4 +48,11 @@ mod tests {
         assert_eq!("123,456,789.12", fmt_token(value));
         assert_eq!("$123,456,789.12", fmt_usd(value));
     }
+
+    #[test]
+    fn fmt_exact() {
+        let amount = TokenAmount::new(1_234_567_000_000_001, 9);
+        assert_eq!("1,234,567.000000001", fmt_token_amount(&amount));
+        assert_eq!("1.50", fmt_token_amount(&TokenAmount::new(1_500_000, 6)));
+    }
 }
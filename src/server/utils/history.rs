@@ -0,0 +1,154 @@
+use std::sync::Arc;
+
+use tokio::sync::mpsc;
+use tonic::Status;
+
+use crate::{
+    proto::{GetTradeHistoryRequest, GetTradeHistoryResponse},
+    server::{
+        domain::{SignatureInfo, SubscriptionInput, WalletEvent},
+        states::{
+            TradeLedgerType,
+            app_state::{OffChainRpcClientType, OnChainRpcClientType, TokenStoreType},
+        },
+        utils::{
+            constants::{
+                DEFAULT_HISTORY_LIMIT, MAX_GAP_FILL_SIGNATURES, MAX_HISTORY_LIMIT,
+                MAX_SIGNATURES_PAGE,
+            },
+            handle_transaction,
+        },
+    },
+};
+
+// pages backwards from the newest signature until limit / since is reached or the receiver is dropped
+#[tracing::instrument(name = "Trade history", skip_all)]
+pub async fn stream_trade_history(
+    request: GetTradeHistoryRequest,
+    subscription_input: Arc<SubscriptionInput>,
+    off_chain_rpc_client: OffChainRpcClientType,
+    token_store: TokenStoreType,
+    on_chain_rpc_client: OnChainRpcClientType,
+    trade_ledger: TradeLedgerType,
+    tx: mpsc::Sender<Result<GetTradeHistoryResponse, Status>>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let limit = if request.limit == 0 {
+        DEFAULT_HISTORY_LIMIT
+    } else {
+        (request.limit as usize).min(MAX_HISTORY_LIMIT)
+    };
+
+    let mut before = request.before;
+    let mut scanned = 0;
+
+    while scanned < limit {
+        let page_size = (limit - scanned).min(MAX_SIGNATURES_PAGE);
+
+        let page = on_chain_rpc_client
+            .get_signatures_for_address(
+                subscription_input.wallet.clone(),
+                before.clone(),
+                None,
+                page_size,
+            )
+            .await?
+            .result;
+
+        let page_len = page.len();
+        scanned += page_len;
+        before = page.last().map(|info| info.signature.clone());
+
+        for signature_info in page {
+            if let (Some(since), Some(block_time)) = (request.since, signature_info.block_time)
+                && block_time < since
+            {
+                return Ok(());
+            }
+
+            if signature_info.err.is_some() {
+                continue;
+            }
+
+            match handle_transaction(
+                signature_info.signature.clone(),
+                subscription_input.clone(),
+                off_chain_rpc_client.clone(),
+                token_store.clone(),
+                on_chain_rpc_client.clone(),
+            )
+            .await
+            {
+                Ok(Some(event)) => {
+                    match &event {
+                        WalletEvent::Trade(trade) => {
+                            trade_ledger.write().await.add_trade(trade.clone())
+                        }
+                        WalletEvent::Activity(activity) => {
+                            trade_ledger.write().await.add_activity(activity.clone())
+                        }
+                    };
+                    let response = GetTradeHistoryResponse::from(event);
+                    if tx.send(Ok(response)).await.is_err() {
+                        return Ok(());
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to handle transaction {}: {}",
+                        signature_info.signature,
+                        e
+                    );
+                }
+            }
+        }
+
+        if page_len < page_size {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+// signatures newer than `until` (or not older than `since`), newest first
+#[tracing::instrument(name = "Collect signatures", skip_all)]
+pub async fn collect_signatures(
+    wallet: &str,
+    until: Option<String>,
+    since: Option<u64>,
+    on_chain_rpc_client: OnChainRpcClientType,
+) -> Result<Vec<SignatureInfo>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut signatures: Vec<SignatureInfo> = vec![];
+    let mut before: Option<String> = None;
+
+    while signatures.len() < MAX_GAP_FILL_SIGNATURES {
+        let page = on_chain_rpc_client
+            .get_signatures_for_address(
+                wallet.to_string(),
+                before.clone(),
+                until.clone(),
+                MAX_SIGNATURES_PAGE,
+            )
+            .await?
+            .result;
+
+        let page_len = page.len();
+        before = page.last().map(|info| info.signature.clone());
+
+        for signature_info in page {
+            if let (Some(since), Some(block_time)) = (since, signature_info.block_time)
+                && block_time < since
+            {
+                return Ok(signatures);
+            }
+            signatures.push(signature_info);
+        }
+
+        if page_len < MAX_SIGNATURES_PAGE {
+            break;
+        }
+    }
+
+    Ok(signatures)
+}
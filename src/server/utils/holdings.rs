@@ -1,82 +1,270 @@
-use std::{collections::HashMap, sync::Arc};
+use std::collections::HashMap;
+
+use futures_util::{StreamExt, TryStreamExt, stream};
 
 use crate::{
     proto::{Holding, HoldingsResponse},
     server::{
-        states::app_state::{OffChainRpcClientType, OnChainRpcClientType, TokenStoreType},
-        utils::constants::{SOL_DENOM, WSOL},
+        domain::TokenAmount,
+        states::{
+            WalletState,
+            app_state::{OffChainRpcClientType, OnChainRpcClientType, TokenStoreType},
+        },
+        utils::{
+            constants::{
+                MAX_CONCURRENT_BALANCE_REQUESTS, SOL_DECIMALS, TOKEN_2022_PROGRAM_ID,
+                TOKEN_PROGRAM_ID, WSOL,
+            },
+            store_tokens,
+        },
     },
 };
 
+type BalanceResult = Result<HashMap<String, TokenAmount>, Box<dyn std::error::Error + Send + Sync>>;
+
+// balances are summed across all the wallets of the client,
+// `all` reports every token account of the wallets instead of the tracked tokens' ATAs
 pub async fn query_holdings(
-    wallet: &String,
-    token_account_map: Arc<HashMap<String, String>>,
+    wallets: &[WalletState],
+    all: bool,
     tokens_store: TokenStoreType,
     on_chain_rpc_client: OnChainRpcClientType,
     off_chain_rpc_client: OffChainRpcClientType,
 ) -> Result<HoldingsResponse, Box<dyn std::error::Error + Send + Sync>> {
     let mut holdings: Vec<Holding> = Vec::new();
 
-    let mut token_balance_map: HashMap<String, f64> = HashMap::new();
-    for (token_mint, token_account) in token_account_map.iter() {
-        let token_balance = on_chain_rpc_client
-            .get_token_account_balance(token_account.clone())
-            .await?;
+    let mut token_balance_map = if all {
+        let token_balance_map = owned_token_balances(wallets, on_chain_rpc_client.clone()).await?;
 
-        if let Some(amount) = token_balance.result.as_ref().map(|res| res.value.to_f64()) {
-            if amount > 0.0 {
-                token_balance_map.insert(token_mint.clone(), amount);
-            }
+        // the metadata of the discovered tokens is best-effort
+        if let Err(e) = store_tokens(
+            &token_balance_map.keys().cloned().collect(),
+            off_chain_rpc_client.clone(),
+            on_chain_rpc_client.clone(),
+            tokens_store.clone(),
+        )
+        .await
+        {
+            tracing::warn!("Failed to store discovered tokens: {}", e);
         }
-    }
 
-    let sol_balance = on_chain_rpc_client.get_balance(wallet.to_string()).await?;
+        token_balance_map
+    } else {
+        tracked_token_balances(wallets, tokens_store.clone(), on_chain_rpc_client.clone()).await?
+    };
 
-    if let Some(amount) = sol_balance
-        .result
-        .as_ref()
-        .map(|res| res.value as f64 / SOL_DENOM)
-    {
-        if amount > 0.0 {
-            token_balance_map
-                .entry(WSOL.to_string())
-                .and_modify(|x| *x += amount)
-                .or_insert(amount);
-        }
+    let sol_balance = sol_balance(wallets, on_chain_rpc_client.clone()).await?;
+    if sol_balance.is_positive() {
+        *token_balance_map.entry(WSOL.to_string()).or_default() += sol_balance;
     }
 
     let token_prices_map = off_chain_rpc_client
         .get_prices(token_balance_map.keys().cloned().collect())
         .await?;
 
-    for token_address in token_balance_map.keys() {
-        if let Some(token_info) = tokens_store
+    for (token_address, balance) in token_balance_map.iter() {
+        let token_info = tokens_store
             .read()
             .await
             .get_token(token_address)
             .await
-            .ok()
+            .ok();
+
+        // the tracked tokens always have metadata, discovered ones may not
+        let (name, symbol) = match token_info {
+            Some(token_info) => (token_info.name.clone(), token_info.symbol.clone()),
+            None if all => ("Unknown".to_string(), "?".to_string()),
+            None => continue,
+        };
+
+        let (usd_price, usd_value) = match token_prices_map.get(token_address) {
+            Some(token_price) => (
+                Some(token_price.usd_price),
+                Some(balance.to_f64() * token_price.usd_price),
+            ),
+            None => (None, None),
+        };
+
+        holdings.push(Holding {
+            name,
+            symbol,
+            address: token_address.clone(),
+            balance: balance.to_string(),
+            usd_price,
+            usd_value,
+            raw_balance: balance.unsigned_raw(),
+            decimals: balance.decimals as u32,
+        });
+    }
+
+    Ok(HoldingsResponse { holdings })
+}
+
+// the tracked tokens' ATAs in one getMultipleAccounts round trip,
+// single requests with bounded concurrency if the batch fails
+async fn tracked_token_balances(
+    wallets: &[WalletState],
+    tokens_store: TokenStoreType,
+    on_chain_rpc_client: OnChainRpcClientType,
+) -> BalanceResult {
+    let token_accounts: Vec<(String, String)> = wallets
+        .iter()
+        .flat_map(|wallet_state| {
+            wallet_state
+                .token_account_map
+                .iter()
+                .map(|(token_mint, token_account)| (token_mint.clone(), token_account.clone()))
+        })
+        .collect();
+
+    match batch_token_balances(&token_accounts, tokens_store, on_chain_rpc_client.clone()).await {
+        Ok(token_balance_map) => Ok(token_balance_map),
+        Err(e) => {
+            tracing::warn!("Batch token balance request failed, falling back: {}", e);
+            single_token_balances(token_accounts, on_chain_rpc_client).await
+        }
+    }
+}
+
+// the token accounts are decoded locally, the decimals come from the token metadata
+async fn batch_token_balances(
+    token_accounts: &[(String, String)],
+    tokens_store: TokenStoreType,
+    on_chain_rpc_client: OnChainRpcClientType,
+) -> BalanceResult {
+    let mut token_balance_map: HashMap<String, TokenAmount> = HashMap::new();
+
+    let accounts = on_chain_rpc_client
+        .get_multiple_accounts(
+            token_accounts
+                .iter()
+                .map(|(_, token_account)| token_account.clone())
+                .collect(),
+        )
+        .await?;
+
+    for ((token_mint, _), account) in token_accounts.iter().zip(accounts) {
+        // the ATA does not exist until the wallet first receives the token
+        let Some(state) = account.and_then(|account| account.token_account_state()) else {
+            continue;
+        };
+        if state.mint != *token_mint || state.amount == 0 {
+            continue;
+        }
+
+        let decimals = tokens_store
+            .read()
+            .await
+            .get_token(token_mint)
+            .await?
+            .decimals;
+        *token_balance_map.entry(token_mint.clone()).or_default() +=
+            TokenAmount::new(state.amount as i128, decimals);
+    }
+
+    Ok(token_balance_map)
+}
+
+async fn single_token_balances(
+    token_accounts: Vec<(String, String)>,
+    on_chain_rpc_client: OnChainRpcClientType,
+) -> BalanceResult {
+    let mut token_balance_map: HashMap<String, TokenAmount> = HashMap::new();
+
+    let token_balances: Vec<_> = stream::iter(token_accounts)
+        .map(|(token_mint, token_account)| {
+            let on_chain_rpc_client = on_chain_rpc_client.clone();
+            async move {
+                on_chain_rpc_client
+                    .get_token_account_balance(token_account)
+                    .await
+                    .map(|token_balance| (token_mint, token_balance))
+            }
+        })
+        .buffer_unordered(MAX_CONCURRENT_BALANCE_REQUESTS)
+        .try_collect()
+        .await?;
+
+    for (token_mint, token_balance) in token_balances {
+        if let Some(amount) = token_balance
+            .result
+            .as_ref()
+            .and_then(|res| res.value.to_token_amount())
+            && amount.is_positive()
         {
-            let balance = token_balance_map.get(token_address).cloned().unwrap_or(0.0);
-
-            let (usd_price, usd_value) = match token_prices_map.get(token_address) {
-                Some(token_price) => (
-                    Some(token_price.usd_price),
-                    Some(balance * token_price.usd_price),
-                ),
-                None => (None, None),
-            };
-
-            holdings.push(Holding {
-                name: token_info.name.clone(),
-                symbol: token_info.symbol.clone(),
-                address: token_address.clone(),
-                balance: balance.to_string(),
-                usd_price,
-                usd_value,
-            });
+            *token_balance_map.entry(token_mint).or_default() += amount;
         }
     }
 
-    Ok(HoldingsResponse { holdings })
+    Ok(token_balance_map)
+}
+
+// the wallets' lamports summed, batched the same way as the token balances
+async fn sol_balance(
+    wallets: &[WalletState],
+    on_chain_rpc_client: OnChainRpcClientType,
+) -> Result<TokenAmount, Box<dyn std::error::Error + Send + Sync>> {
+    let wallets: Vec<String> = wallets
+        .iter()
+        .map(|wallet_state| wallet_state.subscription_input.wallet.clone())
+        .collect();
+
+    let lamports: u64 = match on_chain_rpc_client
+        .get_multiple_accounts(wallets.clone())
+        .await
+    {
+        Ok(accounts) => accounts
+            .iter()
+            .flatten()
+            .map(|account| account.lamports)
+            .sum(),
+        Err(e) => {
+            tracing::warn!("Batch SOL balance request failed, falling back: {}", e);
+            let balances: Vec<_> = stream::iter(wallets)
+                .map(|wallet| {
+                    let on_chain_rpc_client = on_chain_rpc_client.clone();
+                    async move { on_chain_rpc_client.get_balance(wallet).await }
+                })
+                .buffer_unordered(MAX_CONCURRENT_BALANCE_REQUESTS)
+                .try_collect()
+                .await?;
+
+            balances
+                .iter()
+                .filter_map(|balance| balance.result.as_ref())
+                .map(|res| res.value)
+                .sum()
+        }
+    };
+
+    Ok(TokenAmount::new(lamports as i128, SOL_DECIMALS))
+}
+
+async fn owned_token_balances(
+    wallets: &[WalletState],
+    on_chain_rpc_client: OnChainRpcClientType,
+) -> BalanceResult {
+    let mut token_balance_map: HashMap<String, TokenAmount> = HashMap::new();
+
+    for wallet_state in wallets {
+        for program_id in [TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID] {
+            let token_accounts = on_chain_rpc_client
+                .get_token_accounts_by_owner(
+                    wallet_state.subscription_input.wallet.clone(),
+                    program_id.to_string(),
+                )
+                .await?;
+
+            for token_account in token_accounts.result.into_iter().flat_map(|res| res.value) {
+                let info = token_account.account.data.parsed.info;
+                if let Some(amount) = info.token_amount.to_token_amount()
+                    && amount.is_positive()
+                {
+                    *token_balance_map.entry(info.mint).or_default() += amount;
+                }
+            }
+        }
+    }
+
+    Ok(token_balance_map)
 }
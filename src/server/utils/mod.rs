@@ -1,14 +1,28 @@
 pub mod address;
 pub mod constants;
 pub mod format;
+pub mod history;
 pub mod holdings;
+pub mod portfolio;
+pub mod positions;
+pub mod price_watcher;
+pub mod rate_limiter;
+pub mod sessions;
 pub mod tokens;
 pub mod tracing;
 pub mod transactions;
+pub mod venues;
 
 pub use address::*;
 pub use format::*;
+pub use history::*;
 pub use holdings::*;
+pub use portfolio::*;
+pub use positions::*;
+pub use price_watcher::*;
+pub use rate_limiter::*;
+pub use sessions::*;
 pub use tokens::*;
 pub use tracing::*;
 pub use transactions::*;
+pub use venues::*;
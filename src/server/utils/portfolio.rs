@@ -0,0 +1,230 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    proto::{Holding, PortfolioPoint},
+    server::{
+        domain::{InputValidationError, PortfolioSnapshot, TokenAmount},
+        states::{AppState, WalletState},
+        utils::{
+            constants::{DEFAULT_PORTFOLIO_RANGE_SECS, SNAPSHOT_INTERVAL_SECS},
+            query_holdings,
+        },
+    },
+};
+
+// snapshots the holdings of every registered wallet
+pub async fn watch_portfolios(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(SNAPSHOT_INTERVAL_SECS));
+
+    loop {
+        interval.tick().await;
+        take_snapshots(&state).await;
+    }
+}
+
+// a wallet registered by several clients is snapshotted once, with all its token accounts
+#[tracing::instrument(name = "Take snapshots", skip_all)]
+pub async fn take_snapshots(state: &AppState) {
+    let wallets: HashMap<String, WalletState> = state
+        .clients
+        .read()
+        .await
+        .values()
+        .flat_map(|client_state| client_state.wallets.clone())
+        .collect();
+
+    let timestamp = now();
+    for (wallet, wallet_state) in wallets {
+        let holdings = match query_holdings(
+            &[wallet_state],
+            true,
+            state.token_store.clone(),
+            state.on_chain_rpc_client.clone(),
+            state.off_chain_rpc_client.clone(),
+        )
+        .await
+        {
+            Ok(holdings_response) => holdings_response.holdings,
+            Err(e) => {
+                tracing::warn!("Failed to query holdings of wallet {}: {}", wallet, e);
+                continue;
+            }
+        };
+
+        let snapshot = PortfolioSnapshot {
+            wallet,
+            timestamp,
+            holdings,
+        };
+        if let Err(e) = state
+            .snapshot_store
+            .write()
+            .await
+            .add_snapshot(snapshot)
+            .await
+        {
+            tracing::error!("Failed to store snapshot: {}", e);
+        }
+    }
+}
+
+// the last day until now by default
+pub fn resolve_range(
+    from: Option<i64>,
+    to: Option<i64>,
+) -> Result<(i64, i64), InputValidationError> {
+    let to = to.unwrap_or_else(now);
+    let from = from.unwrap_or(to - DEFAULT_PORTFOLIO_RANGE_SECS);
+
+    if from > to {
+        return Err(InputValidationError::InvalidTimeRange);
+    }
+
+    Ok((from, to))
+}
+
+// the latest snapshot of each wallet in a bucket, summed across the wallets
+pub fn build_portfolio_history(
+    snapshots: Vec<PortfolioSnapshot>,
+    resolution: u64,
+) -> Vec<PortfolioPoint> {
+    let resolution = i64::try_from(resolution).unwrap_or(i64::MAX).max(1);
+
+    let mut buckets: BTreeMap<i64, HashMap<String, PortfolioSnapshot>> = BTreeMap::new();
+    for snapshot in snapshots {
+        let bucket = snapshot.timestamp.div_euclid(resolution) * resolution;
+        let wallet_snapshots = buckets.entry(bucket).or_default();
+        match wallet_snapshots.get(&snapshot.wallet) {
+            Some(latest) if latest.timestamp > snapshot.timestamp => {}
+            _ => {
+                wallet_snapshots.insert(snapshot.wallet.clone(), snapshot);
+            }
+        }
+    }
+
+    buckets
+        .into_iter()
+        .map(|(timestamp, wallet_snapshots)| {
+            let holdings = sum_holdings(wallet_snapshots.into_values());
+            PortfolioPoint {
+                timestamp,
+                usd_value: holdings
+                    .iter()
+                    .filter_map(|holding| holding.usd_value)
+                    .sum(),
+                holdings,
+            }
+        })
+        .collect()
+}
+
+// balances are summed exactly, a value is only known if it's known for every wallet
+fn sum_holdings(snapshots: impl Iterator<Item = PortfolioSnapshot>) -> Vec<Holding> {
+    let mut holdings: BTreeMap<String, (Holding, TokenAmount)> = BTreeMap::new();
+
+    for holding in snapshots.flat_map(|snapshot| snapshot.holdings) {
+        let amount = TokenAmount::new(holding.raw_balance as i128, holding.decimals as u8);
+        match holdings.get_mut(&holding.address) {
+            Some((sum, balance)) => {
+                *balance += amount;
+                sum.usd_value = sum.usd_value.zip(holding.usd_value).map(|(a, b)| a + b);
+            }
+            None => {
+                holdings.insert(holding.address.clone(), (holding, amount));
+            }
+        }
+    }
+
+    holdings
+        .into_values()
+        .map(|(holding, balance)| Holding {
+            balance: balance.to_string(),
+            raw_balance: balance.unsigned_raw(),
+            ..holding
+        })
+        .collect()
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::utils::constants::test::{
+        fixtures::snapshot,
+        solana_data::{OTHER_WALLET, WALLET},
+    };
+
+    #[test]
+    fn latest_snapshot_of_the_bucket() {
+        let points = build_portfolio_history(
+            vec![
+                snapshot(WALLET, 3_600, 100_000, 1.0),
+                snapshot(WALLET, 4_000, 200_000, 2.0),
+                snapshot(WALLET, 7_300, 300_000, 3.0),
+            ],
+            3_600,
+        );
+
+        assert_eq!(2, points.len());
+        assert_eq!(3_600, points[0].timestamp);
+        assert_eq!(2.0, points[0].usd_value);
+        assert_eq!("2", points[0].holdings[0].balance);
+        assert_eq!(7_200, points[1].timestamp);
+        assert_eq!(3.0, points[1].usd_value);
+    }
+
+    #[test]
+    fn summed_across_wallets() {
+        let points = build_portfolio_history(
+            vec![
+                snapshot(WALLET, 100, 100_001, 1.5),
+                snapshot(OTHER_WALLET, 200, 200_000, 2.5),
+            ],
+            3_600,
+        );
+
+        assert_eq!(1, points.len());
+        assert_eq!(4.0, points[0].usd_value);
+        assert_eq!(1, points[0].holdings.len());
+        assert_eq!(300_001, points[0].holdings[0].raw_balance);
+        assert_eq!("3.00001", points[0].holdings[0].balance);
+    }
+
+    #[test]
+    fn resolution_beyond_the_signed_timestamp() {
+        let points = build_portfolio_history(
+            vec![
+                snapshot(WALLET, 3_600, 100_000, 1.0),
+                snapshot(WALLET, 7_300, 300_000, 3.0),
+            ],
+            u64::MAX,
+        );
+
+        assert_eq!(1, points.len());
+        assert_eq!(0, points[0].timestamp);
+        assert_eq!(3.0, points[0].usd_value);
+    }
+
+    #[test]
+    fn time_range() {
+        assert_eq!(Ok((100, 200)), resolve_range(Some(100), Some(200)));
+        assert_eq!(
+            Ok((200 - DEFAULT_PORTFOLIO_RANGE_SECS, 200)),
+            resolve_range(None, Some(200))
+        );
+        assert_eq!(
+            Err(InputValidationError::InvalidTimeRange),
+            resolve_range(Some(300), Some(200))
+        );
+    }
+}
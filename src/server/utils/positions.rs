@@ -0,0 +1,258 @@
+use std::collections::HashMap;
+
+use crate::{
+    proto::{Position, Trade, Transfer},
+    server::{domain::TokenAmount, utils::constants::WSOL},
+};
+
+// the exact quantity is kept next to the position and reported with it
+struct PositionEntry {
+    position: Position,
+    quantity: TokenAmount,
+}
+
+impl PositionEntry {
+    fn new(transfer: &Transfer) -> Self {
+        Self {
+            position: Position {
+                mint: transfer.mint.clone(),
+                symbol: transfer.symbol.clone(),
+                name: transfer.name.clone(),
+                ..Default::default()
+            },
+            quantity: TokenAmount::new(0, transfer.decimals as u8),
+        }
+    }
+
+    fn into_position(self) -> Position {
+        Position {
+            quantity: self.quantity.to_string(),
+            raw_quantity: self.quantity.unsigned_raw(),
+            decimals: self.quantity.decimals as u32,
+            ..self.position
+        }
+    }
+}
+
+// replays the trades (in chronological order) with average cost basis
+pub fn build_positions<'a>(trades: impl Iterator<Item = &'a Trade>) -> Vec<Position> {
+    let mut entries: HashMap<String, PositionEntry> = HashMap::new();
+
+    for trade in trades {
+        let trade_value = side_value(&trade.from).or_else(|| side_value(&trade.to));
+
+        for sell in trade.from.iter() {
+            let price = leg_price(sell, &trade.from, trade_value);
+            let entry = entries
+                .entry(sell.mint.clone())
+                .or_insert_with(|| PositionEntry::new(sell));
+
+            // amounts held before tracking started have no known cost basis
+            let sold = sell.token_amount();
+            let closed = if sold.raw < entry.quantity.raw {
+                sold
+            } else {
+                entry.quantity
+            };
+            if let Some(price) = price {
+                entry.position.realized_pnl +=
+                    closed.to_f64() * (price - entry.position.avg_entry_price);
+            }
+
+            entry.quantity -= closed;
+            if entry.quantity.is_zero() {
+                entry.position.avg_entry_price = 0.0;
+            }
+            entry.position.trade_count += 1;
+        }
+
+        for buy in trade.to.iter() {
+            // unpriced buys are treated as zero cost
+            let price = leg_price(buy, &trade.to, trade_value).unwrap_or(0.0);
+            let entry = entries
+                .entry(buy.mint.clone())
+                .or_insert_with(|| PositionEntry::new(buy));
+
+            let bought = buy.token_amount();
+            let cost =
+                entry.quantity.to_f64() * entry.position.avg_entry_price + bought.to_f64() * price;
+            entry.quantity += bought;
+            if entry.quantity.is_positive() {
+                entry.position.avg_entry_price = cost / entry.quantity.to_f64();
+            }
+            entry.position.trade_count += 1;
+        }
+
+        add_fee(&mut entries, trade);
+    }
+
+    let mut positions: Vec<Position> = entries
+        .into_values()
+        .map(PositionEntry::into_position)
+        .collect();
+    positions.sort_by(|a, b| a.mint.cmp(&b.mint));
+    positions
+}
+
+fn side_value(transfers: &[Transfer]) -> Option<f64> {
+    transfers
+        .iter()
+        .map(|transfer| {
+            transfer
+                .usd_price
+                .map(|price| price * transfer.token_amount().to_f64())
+        })
+        .sum()
+}
+
+// a single unpriced leg gets the price implied by the other side of the trade
+fn leg_price(transfer: &Transfer, side: &[Transfer], trade_value: Option<f64>) -> Option<f64> {
+    transfer.usd_price.or_else(|| {
+        let amount = transfer.token_amount();
+        if side.len() == 1 && amount.is_positive() {
+            trade_value.map(|value| value / amount.to_f64())
+        } else {
+            None
+        }
+    })
+}
+
+// the fee and the tip are split between the non-SOL legs of the trade,
+// rent is refundable so it is not a cost
+fn add_fee(entries: &mut HashMap<String, PositionEntry>, trade: &Trade) {
+    let legs: Vec<&Transfer> = trade.from.iter().chain(trade.to.iter()).collect();
+    let mut fee_mints: Vec<&String> = legs
+        .iter()
+        .filter(|transfer| transfer.mint != WSOL)
+        .map(|transfer| &transfer.mint)
+        .collect();
+    if fee_mints.is_empty() {
+        fee_mints = legs.iter().map(|transfer| &transfer.mint).collect();
+    }
+    if fee_mints.is_empty() {
+        return;
+    }
+
+    let tip = trade
+        .sol_costs
+        .as_ref()
+        .map_or(0.0, |sol_costs| sol_costs.tip);
+    let fee_share = (trade.fee + tip) / fee_mints.len() as f64;
+    for mint in fee_mints {
+        if let Some(entry) = entries.get_mut(mint) {
+            entry.position.fees_paid += fee_share;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::utils::constants::test::{fixtures::transfer, solana_data::TOKEN1};
+
+    fn trade(from: Transfer, to: Transfer, slot: u64) -> Trade {
+        Trade {
+            from: vec![from],
+            to: vec![to],
+            signature: format!("signature{}", slot),
+            slot,
+            block_time: slot,
+            fee: 0.00001,
+            ..Default::default()
+        }
+    }
+
+    fn position<'a>(positions: &'a [Position], mint: &str) -> &'a Position {
+        positions.iter().find(|p| p.mint == mint).unwrap()
+    }
+
+    #[test]
+    fn average_entry_price() {
+        let trades = [
+            trade(
+                transfer(WSOL, 1.0, Some(100.0)),
+                transfer(TOKEN1, 100.0, Some(1.0)),
+                1,
+            ),
+            trade(
+                transfer(WSOL, 3.0, Some(100.0)),
+                transfer(TOKEN1, 100.0, Some(3.0)),
+                2,
+            ),
+        ];
+
+        let positions = build_positions(trades.iter());
+        let token = position(&positions, TOKEN1);
+
+        assert_eq!("200", token.quantity);
+        assert_eq!(200_000_000, token.raw_quantity);
+        assert_eq!(6, token.decimals);
+        assert_eq!(2.0, token.avg_entry_price);
+        assert_eq!(0.0, token.realized_pnl);
+        assert_eq!(2, token.trade_count);
+        assert!((token.fees_paid - 0.00002).abs() < 1e-12);
+    }
+
+    #[test]
+    fn realized_pnl_on_partial_sell() {
+        let trades = [
+            trade(
+                transfer(WSOL, 1.0, Some(100.0)),
+                transfer(TOKEN1, 100.0, Some(1.0)),
+                1,
+            ),
+            trade(
+                transfer(TOKEN1, 40.0, Some(1.5)),
+                transfer(WSOL, 0.6, Some(100.0)),
+                2,
+            ),
+        ];
+
+        let positions = build_positions(trades.iter());
+        let token = position(&positions, TOKEN1);
+
+        assert_eq!("60", token.quantity);
+        assert_eq!(1.0, token.avg_entry_price);
+        assert_eq!(20.0, token.realized_pnl);
+    }
+
+    #[test]
+    fn implied_price_for_unpriced_leg() {
+        let trades = [trade(
+            transfer(WSOL, 1.0, Some(100.0)),
+            transfer(TOKEN1, 50.0, None),
+            1,
+        )];
+
+        let positions = build_positions(trades.iter());
+
+        assert_eq!(2.0, position(&positions, TOKEN1).avg_entry_price);
+    }
+
+    #[test]
+    fn no_dust_after_selling_everything() {
+        // 0.1 + 0.1 + 0.1 is not 0.3 in f64
+        let mut trades: Vec<Trade> = (1..=3)
+            .map(|slot| {
+                trade(
+                    transfer(WSOL, 0.001, Some(100.0)),
+                    transfer(TOKEN1, 0.1, Some(1.0)),
+                    slot,
+                )
+            })
+            .collect();
+        trades.push(trade(
+            transfer(TOKEN1, 0.3, Some(2.0)),
+            transfer(WSOL, 0.006, Some(100.0)),
+            4,
+        ));
+
+        let positions = build_positions(trades.iter());
+        let token = position(&positions, TOKEN1);
+
+        assert_eq!("0", token.quantity);
+        assert_eq!(0, token.raw_quantity);
+        assert_eq!(0.0, token.avg_entry_price);
+        assert!((token.realized_pnl - 0.3).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,65 @@
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+use crate::{
+    proto::SubscribeResponse,
+    server::{states::AppState, utils::constants::PRICE_WATCH_INTERVAL_SECS},
+};
+
+// polls the prices for the subscribed clients with alerts
+pub async fn watch_prices(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(PRICE_WATCH_INTERVAL_SECS));
+
+    loop {
+        interval.tick().await;
+        check_alerts(&state).await;
+    }
+}
+
+// the triggered alerts are pushed on the client's subscription stream
+#[tracing::instrument(name = "Check alerts", skip_all)]
+pub async fn check_alerts(state: &AppState) {
+    let mints: HashSet<String> = state
+        .clients
+        .read()
+        .await
+        .values()
+        .filter(|client_state| {
+            client_state.logs_subscription.is_some() && !client_state.alerts.is_empty()
+        })
+        .flat_map(|client_state| client_state.alerts.mints())
+        .collect();
+
+    if mints.is_empty() {
+        return;
+    }
+
+    let prices = match state
+        .off_chain_rpc_client
+        .get_prices(mints.into_iter().collect())
+        .await
+    {
+        Ok(prices) => prices,
+        Err(e) => {
+            tracing::warn!("Failed to fetch prices for the alerts: {}", e);
+            return;
+        }
+    };
+
+    // sent after the lock is released, a full stream must not block the other clients
+    let mut notifications = vec![];
+    for client_state in state.clients.write().await.values_mut() {
+        let Some(subscription) = &client_state.logs_subscription else {
+            continue;
+        };
+        for alert_event in client_state.alerts.check(&prices) {
+            notifications.push((
+                subscription.tx.clone(),
+                SubscribeResponse::alert(alert_event),
+            ));
+        }
+    }
+
+    for (tx, message) in notifications {
+        let _ = tx.send(Ok(message)).await;
+    }
+}
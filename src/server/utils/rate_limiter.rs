@@ -0,0 +1,85 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+// token bucket, a request takes a token and waits for the refill if there is none
+pub struct RateLimiter {
+    capacity: f64,
+    refill_per_sec: f64,
+    bucket: Mutex<Bucket>,
+}
+
+impl RateLimiter {
+    pub fn new(capacity: u32, refill_per_sec: f64) -> Self {
+        Self {
+            capacity: capacity as f64,
+            refill_per_sec,
+            bucket: Mutex::new(Bucket {
+                tokens: capacity as f64,
+                updated_at: Instant::now(),
+            }),
+        }
+    }
+
+    pub async fn acquire(&self) {
+        while let Some(wait) = self.try_acquire(Instant::now()) {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    // None if a token was taken, otherwise the time until the next one
+    fn try_acquire(&self, now: Instant) -> Option<Duration> {
+        let mut bucket = self
+            .bucket
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let elapsed = now.saturating_duration_since(bucket.updated_at);
+        bucket.tokens =
+            (bucket.tokens + elapsed.as_secs_f64() * self.refill_per_sec).min(self.capacity);
+        bucket.updated_at = bucket.updated_at.max(now);
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64(
+                (1.0 - bucket.tokens) / self.refill_per_sec,
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn burst_then_refill() {
+        let rate_limiter = RateLimiter::new(2, 4.0);
+        let now = Instant::now();
+
+        assert_eq!(None, rate_limiter.try_acquire(now));
+        assert_eq!(None, rate_limiter.try_acquire(now));
+        assert_eq!(
+            Some(Duration::from_millis(250)),
+            rate_limiter.try_acquire(now)
+        );
+
+        assert_eq!(
+            None,
+            rate_limiter.try_acquire(now + Duration::from_millis(250))
+        );
+        // the bucket does not fill beyond its capacity
+        let later = now + Duration::from_secs(10);
+        assert_eq!(None, rate_limiter.try_acquire(later));
+        assert_eq!(None, rate_limiter.try_acquire(later));
+        assert!(rate_limiter.try_acquire(later).is_some());
+    }
+}
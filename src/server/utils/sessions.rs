@@ -0,0 +1,57 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::server::{
+    config::SessionsConfig,
+    states::{AppState, SubscriptionState, client_state::WebSocketClientType},
+};
+
+// frees the sessions of the clients that are gone without unsubscribing
+pub async fn reap_sessions(state: Arc<AppState>, sessions: SessionsConfig) {
+    let mut interval = tokio::time::interval(Duration::from_secs(sessions.reaper_interval_secs));
+    let ttl = Duration::from_secs(sessions.ttl_secs);
+
+    loop {
+        interval.tick().await;
+        free_abandoned_sessions(&state, ttl).await;
+    }
+}
+
+// a dropped stream closes its logs subscriptions, a session without a stream
+// expires after the ttl without a request
+#[tracing::instrument(name = "Free abandoned sessions", skip_all)]
+pub async fn free_abandoned_sessions(state: &AppState, ttl: Duration) {
+    // closed after the lock is released, the unsubscribe requests must not block the other clients
+    let mut closing: Vec<(WebSocketClientType, SubscriptionState)> = vec![];
+
+    {
+        let mut clients = state.clients.write().await;
+
+        for (client_id, client_state) in clients.iter_mut() {
+            if client_state
+                .logs_subscription
+                .as_ref()
+                .is_some_and(|subscription| subscription.tx.is_closed())
+                && let Some(subscription) = client_state.logs_subscription.take()
+            {
+                tracing::info!("Subscription stream of client {} dropped", client_id);
+                closing.push((client_state.ws_client.clone(), subscription));
+            }
+        }
+
+        clients.retain(|client_id, client_state| {
+            if client_state.logs_subscription.is_some() || client_state.idle_for() < ttl {
+                return true;
+            }
+            tracing::info!(
+                "Session of client {} expired after {:?}",
+                client_id,
+                client_state.idle_for()
+            );
+            false
+        });
+    }
+
+    for (ws_client, subscription) in closing {
+        subscription.close(&ws_client).await;
+    }
+}
@@ -1,25 +1,44 @@
+use std::collections::HashMap;
+
 use crate::server::{
-    domain::TokenStoreError,
-    states::app_state::{OffChainRpcClientType, TokenStoreType},
+    domain::{TokenInfo, TokenStoreError, TransferFee},
+    states::app_state::{OffChainRpcClientType, OnChainRpcClientType, TokenStoreType},
+    utils::constants::TOKEN_PROGRAM_ID,
 };
 
 #[tracing::instrument(name = "Store tokens", skip_all)]
 pub async fn store_tokens(
     token_mints: &Vec<String>,
     off_chain_rpc_client: OffChainRpcClientType,
+    on_chain_rpc_client: OnChainRpcClientType,
     token_store: TokenStoreType,
 ) -> Result<(), TokenStoreError> {
     let mut token_store = token_store.write().await;
 
     let mut tokens_to_query: Vec<String> = vec![];
+    // stored without the mint info, e.g. the mint account was not available
+    let mut tokens_to_complete: Vec<TokenInfo> = vec![];
 
     for token_mint in token_mints {
         if !token_store.has_token(token_mint).await {
             tokens_to_query.push(token_mint.clone());
+        } else if let Ok(token) = token_store.get_token(token_mint).await
+            && token.token_program.is_none()
+        {
+            tokens_to_complete.push(token);
         }
     }
 
-    if tokens_to_query.len() > 0 {
+    for mut token in tokens_to_complete {
+        if add_mint_info(&mut token, on_chain_rpc_client.clone()).await
+            && token_store.update_token(token.clone()).await.is_err()
+        {
+            tracing::error!("Failed to update token {}", token.id);
+            return Err(TokenStoreError::UnexpectedError);
+        }
+    }
+
+    if !tokens_to_query.is_empty() {
         match off_chain_rpc_client.get_tokens(tokens_to_query).await {
             Err(e) => {
                 let error = TokenStoreError::TokenIsNotAvailable(e.to_string());
@@ -27,7 +46,9 @@ pub async fn store_tokens(
                 return Err(error);
             }
             Ok(tokens) => {
-                for token in tokens.into_iter() {
+                for mut token in tokens.into_iter() {
+                    add_mint_info(&mut token, on_chain_rpc_client.clone()).await;
+
                     if token_store.add_token(token.clone()).await.is_err() {
                         tracing::error!("Failed to store token {}", token.id);
                         return Err(TokenStoreError::UnexpectedError);
@@ -39,3 +60,50 @@ pub async fn store_tokens(
 
     Ok(())
 }
+
+// the token program of the mint and its transfer fee, false if the mint account is
+// not available; the token is then stored without them and completed on its next use
+async fn add_mint_info(token: &mut TokenInfo, on_chain_rpc_client: OnChainRpcClientType) -> bool {
+    match on_chain_rpc_client.get_account_info(token.id.clone()).await {
+        Ok(response) => match response.result.and_then(|result| result.value) {
+            Some(account_info) => {
+                token.transfer_fee = account_info
+                    .transfer_fee_config()
+                    .map(|config| TransferFee {
+                        basis_points: config.newer_transfer_fee.transfer_fee_basis_points,
+                        maximum_fee: config.newer_transfer_fee.maximum_fee,
+                    });
+                token.token_program = Some(account_info.owner);
+                true
+            }
+            None => {
+                tracing::warn!("Mint account {} not found", token.id);
+                false
+            }
+        },
+        Err(e) => {
+            tracing::warn!("Failed to fetch mint account {}: {}", token.id, e);
+            false
+        }
+    }
+}
+
+pub async fn get_token_programs(
+    token_mints: &[String],
+    token_store: TokenStoreType,
+) -> HashMap<String, String> {
+    let token_store = token_store.read().await;
+
+    let mut token_programs = HashMap::new();
+    for token_mint in token_mints {
+        let token_program = token_store
+            .get_token(token_mint)
+            .await
+            .ok()
+            .and_then(|token| token.token_program)
+            .unwrap_or_else(|| TOKEN_PROGRAM_ID.to_string());
+        token_programs.insert(token_mint.clone(), token_program);
+    }
+
+    token_programs
+}
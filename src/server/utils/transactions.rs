@@ -1,12 +1,16 @@
 use std::{collections::HashMap, sync::Arc};
 
 use crate::{
-    proto::{Trade, Transfer},
+    proto::{Activity, ActivityKind, ExecutionPath, SolCosts, Trade, Transfer},
     server::{
-        domain::{EncodedTransaction, SubscriptionInput, TransactionMeta},
+        domain::{SubscriptionInput, TokenAmount, TokenBalance, TransactionMeta, WalletEvent},
         states::app_state::{OffChainRpcClientType, OnChainRpcClientType, TokenStoreType},
         utils::{
-            constants::{SOL_DENOM, WSOL},
+            calc_venue,
+            constants::{
+                JITO_TIP_ACCOUNTS, KEEPER_PROGRAM_IDS, LAMPORTS_PER_SIGNATURE, SOL_DECIMALS,
+                SOL_DENOM, STAKE_PROGRAM_ID, USDC, WSOL,
+            },
             store_tokens,
         },
     },
@@ -19,49 +23,156 @@ pub async fn handle_transaction(
     off_chain_rpc_client: OffChainRpcClientType,
     token_store: TokenStoreType,
     on_chain_rpc_client: OnChainRpcClientType,
-) -> Result<Option<Trade>, Box<dyn std::error::Error + Send + Sync>> {
-    let transaction = on_chain_rpc_client.get_transaction(signature).await?;
+) -> Result<Option<WalletEvent>, Box<dyn std::error::Error + Send + Sync>> {
+    let transaction = on_chain_rpc_client
+        .get_transaction(signature.clone())
+        .await?;
 
-    if let Some(encoded_transaction) = transaction.result.as_ref().map(|res| &res.transaction) {
-        if !wallet_is_the_fee_payer(&subscription_input.wallet, encoded_transaction) {
-            return Ok(None);
-        }
-    } else {
+    let Some(transaction_result) = transaction.result.as_ref() else {
+        return Ok(None);
+    };
+
+    let Some(transaction_meta) = transaction_result.meta.as_ref() else {
+        return Ok(None);
+    };
+
+    // the event is attributed by the balance changes of the wallet, whoever paid the fee
+    let wallet = subscription_input.wallet.as_str();
+    let account_keys = transaction_result.account_keys();
+    let wallet_index = account_keys.iter().position(|key| *key == wallet);
+    let num_signers = transaction_result
+        .transaction
+        .message
+        .header
+        .num_required_signatures;
+    let execution_path = calc_execution_path(wallet_index, num_signers);
+
+    // the wallet's SOL pays for the costs only if it paid the fee
+    let sol_costs = (execution_path == ExecutionPath::Direct)
+        .then(|| calc_sol_costs(transaction_meta, &account_keys, wallet, num_signers));
+
+    let balance_changes =
+        calc_token_changes_for_wallet(transaction_meta, wallet_index, &subscription_input, 0);
+    if balance_changes.is_empty() {
         return Ok(None);
     }
 
-    let transaction_meta = transaction
-        .result
-        .as_ref()
-        .and_then(|res| res.meta.as_ref());
+    // the SOL leg of a trade is the traded amount, without rent and tips
+    let trade_changes = calc_token_changes_for_wallet(
+        transaction_meta,
+        wallet_index,
+        &subscription_input,
+        sol_costs.as_ref().map_or(0, LamportCosts::excluded),
+    );
 
-    if let Some(transaction_meta) = transaction_meta {
-        let trades = build_trades(
-            transaction_meta,
-            &subscription_input,
-            off_chain_rpc_client,
-            token_store,
+    // fills of escrowed orders only show the received leg in the wallet's balances
+    let escrow_fill = execution_path == ExecutionPath::KeeperFilled
+        && account_keys
+            .iter()
+            .any(|key| KEEPER_PROGRAM_IDS.contains(&key.as_str()));
+
+    let has_buys = trade_changes.values().any(TokenAmount::is_positive);
+    let has_sells = trade_changes.values().any(TokenAmount::is_negative);
+    let is_trade = has_buys && (has_sells || escrow_fill);
+    let token_changes = if is_trade {
+        trade_changes
+    } else {
+        balance_changes
+    };
+
+    let activity = (!is_trade).then(|| {
+        (
+            calc_activity_kind(
+                transaction_meta,
+                &account_keys,
+                wallet,
+                execution_path != ExecutionPath::KeeperFilled,
+                &token_changes,
+            ),
+            find_counterparty(transaction_meta, &account_keys, wallet, &token_changes),
         )
-        .await;
-        return Ok(trades);
-    }
+    });
 
-    Ok(None)
-}
+    let (sells, buys) = build_transfers(
+        token_changes,
+        transaction_result.block_time,
+        off_chain_rpc_client,
+        on_chain_rpc_client.clone(),
+        token_store,
+    )
+    .await;
 
-fn wallet_is_the_fee_payer(wallet: &String, enc_transaction: &EncodedTransaction) -> bool {
-    if let Some(fee_payer) = enc_transaction.message.account_keys.first() {
-        wallet == fee_payer
+    // the fee is counted only if the wallet paid it
+    let fee = if execution_path == ExecutionPath::Direct {
+        calc_fee(transaction_meta)
     } else {
-        false
+        0.0
+    };
+
+    let event = match activity {
+        None => {
+            let mut trade = Trade {
+                from: sells,
+                to: buys,
+                signature,
+                slot: transaction_result.slot,
+                block_time: transaction_result.block_time,
+                fee,
+                wallet: subscription_input.wallet.clone(),
+                ..Default::default()
+            };
+            trade.set_execution_path(execution_path);
+            trade.set_venue(calc_venue(&transaction_result.invoked_programs()));
+            trade.sol_costs = sol_costs.map(SolCosts::from);
+            WalletEvent::Trade(trade)
+        }
+        Some((kind, counterparty)) => {
+            let mut activity = Activity {
+                incoming: buys,
+                outgoing: sells,
+                counterparty,
+                signature,
+                slot: transaction_result.slot,
+                block_time: transaction_result.block_time,
+                fee,
+                wallet: subscription_input.wallet.clone(),
+                ..Default::default()
+            };
+            activity.set_kind(kind);
+            WalletEvent::Activity(activity)
+        }
+    };
+
+    Ok(Some(event))
+}
+
+fn calc_execution_path(wallet_index: Option<usize>, num_signers: usize) -> ExecutionPath {
+    match wallet_index {
+        Some(0) => ExecutionPath::Direct,
+        Some(index) if index < num_signers => ExecutionPath::Relayed,
+        _ => ExecutionPath::KeeperFilled,
     }
 }
 
-fn calc_sol_change(transaction_meta: &TransactionMeta) -> f64 {
-    // it is safe because here pre_balances and post_balances have element at index 0
-    let pre_balance = transaction_meta.pre_balances.get(0).cloned().unwrap_or(0);
-    let post_balance = transaction_meta.post_balances.get(0).cloned().unwrap_or(0);
-    (post_balance as f64 - pre_balance as f64) / SOL_DENOM
+// the fee payer's balance change is without the fee, it is reported separately
+fn calc_lamport_change(transaction_meta: &TransactionMeta, wallet_index: Option<usize>) -> i64 {
+    let Some(index) = wallet_index else {
+        return 0;
+    };
+
+    let pre_balance = transaction_meta
+        .pre_balances
+        .get(index)
+        .cloned()
+        .unwrap_or(0);
+    let post_balance = transaction_meta
+        .post_balances
+        .get(index)
+        .cloned()
+        .unwrap_or(0);
+    let fee = if index == 0 { transaction_meta.fee } else { 0 };
+
+    post_balance as i64 + fee as i64 - pre_balance as i64
 }
 
 fn calc_fee(transaction_meta: &TransactionMeta) -> f64 {
@@ -69,93 +180,684 @@ fn calc_fee(transaction_meta: &TransactionMeta) -> f64 {
     fee as f64 / SOL_DENOM
 }
 
+// the changes are the balance changes of the wallet, so Token-2022 amounts are net of
+// the transfer fee: a buy is reported as received, the fee is kept on the transfer
+// `excluded_lamports` of the SOL change are not counted, wrapped SOL is added to it
 fn calc_token_changes_for_wallet(
     transaction_meta: &TransactionMeta,
+    wallet_index: Option<usize>,
     subscription_input: &SubscriptionInput,
-) -> HashMap<String, f64> {
-    let mut token_changes: HashMap<String, f64> = HashMap::new();
+    excluded_lamports: i64,
+) -> HashMap<String, TokenAmount> {
+    let mut token_changes: HashMap<String, TokenAmount> = HashMap::new();
 
     let wallet_str = subscription_input.wallet.as_str();
 
-    let sol_changes = calc_sol_change(transaction_meta) - calc_fee(transaction_meta);
+    let sol_changes = TokenAmount::new(
+        (calc_lamport_change(transaction_meta, wallet_index) - excluded_lamports) as i128,
+        SOL_DECIMALS,
+    );
 
     token_changes.insert(WSOL.to_string(), sol_changes);
 
     for token_balance in &transaction_meta.pre_token_balances {
-        if token_balance.owner.as_deref() == Some(wallet_str) {
-            token_changes.insert(
-                token_balance.mint.clone(),
-                -token_balance.ui_token_amount.to_f64(),
-            );
+        if token_balance.owner.as_deref() == Some(wallet_str)
+            && let Some(amount) = token_balance.ui_token_amount.to_token_amount()
+        {
+            let entry = token_changes.entry(token_balance.mint.clone()).or_default();
+            *entry -= amount;
         }
     }
 
     for token_balance in &transaction_meta.post_token_balances {
-        if token_balance.owner.as_deref() == Some(wallet_str) {
-            let entry = token_changes
-                .entry(token_balance.mint.clone())
-                .or_insert(0.0);
-            *entry += token_balance.ui_token_amount.to_f64();
+        if token_balance.owner.as_deref() == Some(wallet_str)
+            && let Some(amount) = token_balance.ui_token_amount.to_token_amount()
+        {
+            let entry = token_changes.entry(token_balance.mint.clone()).or_default();
+            *entry += amount;
         }
     }
 
-    token_changes.retain(|_, v| *v != 0.0);
+    token_changes.retain(|_, v| !v.is_zero());
     token_changes
 }
 
-#[tracing::instrument(name = "Build trades", skip_all)]
-async fn build_trades(
+// lamports paid or refunded to the fee payer wallet next to the traded amount
+#[derive(Debug, Default, PartialEq)]
+struct LamportCosts {
+    base_fee: u64,
+    priority_fee: u64,
+    tip: u64,
+    rent_created: u64,
+    rent_reclaimed: u64,
+}
+
+impl LamportCosts {
+    // the part of the wallet's SOL change, the fee already added back, which is not traded
+    fn excluded(&self) -> i64 {
+        self.rent_reclaimed as i64 - self.rent_created as i64 - self.tip as i64
+    }
+}
+
+impl From<LamportCosts> for SolCosts {
+    fn from(costs: LamportCosts) -> Self {
+        Self {
+            base_fee: costs.base_fee as f64 / SOL_DENOM,
+            priority_fee: costs.priority_fee as f64 / SOL_DENOM,
+            tip: costs.tip as f64 / SOL_DENOM,
+            rent_created: costs.rent_created as f64 / SOL_DENOM,
+            rent_reclaimed: costs.rent_reclaimed as f64 / SOL_DENOM,
+        }
+    }
+}
+
+// the fee holds the compute unit price set by the compute budget instructions on top of
+// the signature fees, the rent is the lamports of the wallet's opened and closed token
+// accounts, without the wrapped SOL they hold
+fn calc_sol_costs(
     transaction_meta: &TransactionMeta,
-    subscription_input: &SubscriptionInput,
+    account_keys: &[&String],
+    wallet: &str,
+    num_signers: usize,
+) -> LamportCosts {
+    let pre_balance = |index: usize| {
+        transaction_meta
+            .pre_balances
+            .get(index)
+            .cloned()
+            .unwrap_or(0)
+    };
+    let post_balance = |index: usize| {
+        transaction_meta
+            .post_balances
+            .get(index)
+            .cloned()
+            .unwrap_or(0)
+    };
+    let wrapped_sol = |token_balance: &TokenBalance| {
+        if token_balance.mint == WSOL {
+            token_balance.ui_token_amount.amount.parse().unwrap_or(0)
+        } else {
+            0
+        }
+    };
+
+    let base_fee = LAMPORTS_PER_SIGNATURE * num_signers as u64;
+    let priority_fee = transaction_meta.fee.saturating_sub(base_fee);
+
+    let tips: u64 = account_keys
+        .iter()
+        .enumerate()
+        .filter(|(_, key)| JITO_TIP_ACCOUNTS.contains(&key.as_str()))
+        .map(|(index, _)| post_balance(index).saturating_sub(pre_balance(index)))
+        .sum();
+    // a tip is a transfer signed by its funder: the sole signer, or a co-signing wallet
+    // that spent at least the tip
+    let wallet_index = account_keys
+        .iter()
+        .position(|key| *key == wallet)
+        .filter(|index| *index < num_signers);
+    let tip = match wallet_index {
+        Some(_) if num_signers == 1 => tips,
+        Some(index) if -calc_lamport_change(transaction_meta, Some(index)) >= tips as i64 => tips,
+        _ => 0,
+    };
+
+    let rent_created = transaction_meta
+        .post_token_balances
+        .iter()
+        .filter(|token_balance| token_balance.owner.as_deref() == Some(wallet))
+        .filter(|token_balance| pre_balance(token_balance.account_index) == 0)
+        .map(|token_balance| {
+            post_balance(token_balance.account_index).saturating_sub(wrapped_sol(token_balance))
+        })
+        .sum();
+
+    let rent_reclaimed = transaction_meta
+        .pre_token_balances
+        .iter()
+        .filter(|token_balance| token_balance.owner.as_deref() == Some(wallet))
+        .filter(|token_balance| post_balance(token_balance.account_index) == 0)
+        .map(|token_balance| {
+            pre_balance(token_balance.account_index).saturating_sub(wrapped_sol(token_balance))
+        })
+        .sum();
+
+    LamportCosts {
+        base_fee: base_fee.min(transaction_meta.fee),
+        priority_fee,
+        tip,
+        rent_created,
+        rent_reclaimed,
+    }
+}
+
+// prices the changes at block time: the execution price comes from the USDC / SOL leg
+// when possible, the rest is queried from the historical price source
+async fn calc_prices_at(
+    token_changes: &HashMap<String, TokenAmount>,
+    block_time: u64,
     off_chain_rpc_client: OffChainRpcClientType,
+) -> HashMap<String, f64> {
+    let sol_price = if token_changes.contains_key(WSOL) && !token_changes.contains_key(USDC) {
+        off_chain_rpc_client
+            .get_price_at(WSOL.to_string(), block_time)
+            .await
+            .ok()
+            .flatten()
+    } else {
+        None
+    };
+
+    let ui_changes: HashMap<String, f64> = token_changes
+        .iter()
+        .map(|(mint, change)| (mint.clone(), change.to_f64()))
+        .collect();
+    let mut prices = calc_execution_prices(&ui_changes, sol_price);
+
+    for mint in token_changes.keys() {
+        if prices.contains_key(mint) {
+            continue;
+        }
+        match off_chain_rpc_client
+            .get_price_at(mint.clone(), block_time)
+            .await
+        {
+            Ok(Some(price)) => {
+                prices.insert(mint.clone(), price);
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to fetch price of {} at {}: {}", mint, block_time, e),
+        }
+    }
+
+    prices
+}
+
+fn calc_execution_prices(
+    token_changes: &HashMap<String, f64>,
+    sol_price: Option<f64>,
+) -> HashMap<String, f64> {
+    let mut prices: HashMap<String, f64> = HashMap::new();
+
+    let quote = if let Some(amount) = token_changes.get(USDC) {
+        prices.insert(USDC.to_string(), 1.0);
+        Some((USDC, *amount, amount.abs()))
+    } else if let (Some(amount), Some(sol_price)) = (token_changes.get(WSOL), sol_price) {
+        prices.insert(WSOL.to_string(), sol_price);
+        Some((WSOL, *amount, amount.abs() * sol_price))
+    } else {
+        None
+    };
+
+    if let Some((quote_mint, quote_amount, quote_value)) = quote {
+        let others: Vec<(&String, &f64)> = token_changes
+            .iter()
+            .filter(|(mint, _)| mint.as_str() != quote_mint)
+            .collect();
+
+        // only a single token swapped against the quote has an unambiguous price
+        if let [(mint, amount)] = others.as_slice()
+            && amount.signum() != quote_amount.signum()
+        {
+            prices.insert(mint.to_string(), quote_value / amount.abs());
+        }
+    }
+
+    prices
+}
+
+#[tracing::instrument(name = "Build transfers", skip_all)]
+async fn build_transfers(
+    token_changes: HashMap<String, TokenAmount>,
+    block_time: u64,
+    off_chain_rpc_client: OffChainRpcClientType,
+    on_chain_rpc_client: OnChainRpcClientType,
     token_store: TokenStoreType,
-) -> Option<Trade> {
+) -> (Vec<Transfer>, Vec<Transfer>) {
     let mut sells: Vec<Transfer> = vec![];
     let mut buys: Vec<Transfer> = vec![];
 
-    let token_changes = calc_token_changes_for_wallet(transaction_meta, subscription_input);
-    if token_changes.is_empty() {
-        return None;
-    }
-
-    let token_prices_map = off_chain_rpc_client
-        .get_prices(token_changes.keys().cloned().collect())
-        .await
-        .ok();
+    let token_prices_map =
+        calc_prices_at(&token_changes, block_time, off_chain_rpc_client.clone()).await;
 
     store_tokens(
         &token_changes.keys().cloned().collect(),
         off_chain_rpc_client,
+        on_chain_rpc_client,
         token_store.clone(),
     )
     .await
     .ok();
 
     for (mint, amount) in token_changes.into_iter() {
-        let mut transfer = Transfer::new(mint.clone(), amount.abs());
-        if let Some(token_prices_map) = token_prices_map.as_ref() {
-            if let Some(token_price) = token_prices_map.get(&mint) {
-                transfer.usd_price = Some(token_price.usd_price);
-            }
-            if let Ok(token_info) = token_store.clone().read().await.get_token(&mint).await {
-                transfer.symbol = Some(token_info.symbol.clone());
-                transfer.name = Some(token_info.name.clone());
-            }
+        let mut transfer = Transfer::new(mint.clone(), amount);
+        transfer.usd_price = token_prices_map.get(&mint).cloned();
+        if let Ok(token_info) = token_store.clone().read().await.get_token(&mint).await {
+            transfer.symbol = Some(token_info.symbol.clone());
+            transfer.name = Some(token_info.name.clone());
+            transfer.transfer_fee = token_info
+                .transfer_fee
+                .as_ref()
+                .map(|transfer_fee| transfer_fee.calc_fee_for_change(&amount).to_f64());
         }
 
-        if amount < 0.0 {
+        if amount.is_negative() {
             sells.push(transfer);
-        } else if amount > 0.0 {
+        } else if amount.is_positive() {
             buys.push(transfer);
         }
     }
 
-    if sells.len() == 0 || buys.len() == 0 {
-        return None;
+    (sells, buys)
+}
+
+// one sided changes: the direction, the programs involved and the other parties tell the kind
+fn calc_activity_kind(
+    transaction_meta: &TransactionMeta,
+    account_keys: &[&String],
+    wallet: &str,
+    signed: bool,
+    token_changes: &HashMap<String, TokenAmount>,
+) -> ActivityKind {
+    let incoming = token_changes.values().any(TokenAmount::is_positive);
+    let sol_only = token_changes.keys().all(|mint| mint == WSOL);
+    let staking = sol_only && account_keys.iter().any(|key| *key == STAKE_PROGRAM_ID);
+
+    // an account emptied into the wallet, a close refunding another account is a transfer
+    let closes_account = || {
+        let wallet_change = calc_lamport_change(
+            transaction_meta,
+            account_keys.iter().position(|key| *key == wallet),
+        );
+        account_keys.iter().enumerate().any(|(index, key)| {
+            let pre_balance = transaction_meta
+                .pre_balances
+                .get(index)
+                .cloned()
+                .unwrap_or(0);
+            let post_balance = transaction_meta
+                .post_balances
+                .get(index)
+                .cloned()
+                .unwrap_or(0);
+            *key != wallet
+                && pre_balance > 0
+                && post_balance == 0
+                && wallet_change >= pre_balance as i64
+        })
+    };
+
+    let has_other_recipients = || {
+        token_changes
+            .iter()
+            .filter(|(_, change)| change.is_positive())
+            .any(|(mint, _)| {
+                calc_counterparty_changes(transaction_meta, account_keys, mint)
+                    .iter()
+                    .any(|(account, change)| account != wallet && change.is_positive())
+            })
+    };
+
+    match (incoming, staking) {
+        (false, true) => ActivityKind::Stake,
+        (false, false) => ActivityKind::TransferOut,
+        (true, true) => ActivityKind::Unstake,
+        (true, false) if sol_only && signed && closes_account() => ActivityKind::AccountClose,
+        (true, false) if !signed && has_other_recipients() => ActivityKind::Airdrop,
+        (true, false) => ActivityKind::TransferIn,
+    }
+}
+
+// the account with the largest opposite change of the main leg (a token leg if any)
+fn find_counterparty(
+    transaction_meta: &TransactionMeta,
+    account_keys: &[&String],
+    wallet: &str,
+    token_changes: &HashMap<String, TokenAmount>,
+) -> Option<String> {
+    let (mint, wallet_change) = token_changes
+        .iter()
+        .find(|(mint, _)| mint.as_str() != WSOL)
+        .or_else(|| token_changes.get_key_value(WSOL))?;
+
+    calc_counterparty_changes(transaction_meta, account_keys, mint)
+        .into_iter()
+        .filter(|(account, change)| {
+            account != wallet
+                && !change.is_zero()
+                && change.raw.signum() != wallet_change.raw.signum()
+        })
+        .max_by_key(|(_, change)| change.raw.abs())
+        .map(|(account, _)| account)
+}
+
+// balance changes of the mint by owner, SOL by account without the fee
+fn calc_counterparty_changes(
+    transaction_meta: &TransactionMeta,
+    account_keys: &[&String],
+    mint: &str,
+) -> HashMap<String, TokenAmount> {
+    let mut changes: HashMap<String, TokenAmount> = HashMap::new();
+
+    if mint == WSOL {
+        for (index, key) in account_keys.iter().enumerate() {
+            changes.insert(
+                key.to_string(),
+                TokenAmount::new(
+                    calc_lamport_change(transaction_meta, Some(index)) as i128,
+                    SOL_DECIMALS,
+                ),
+            );
+        }
+        return changes;
+    }
+
+    for token_balance in &transaction_meta.pre_token_balances {
+        if let Some(owner) = &token_balance.owner
+            && token_balance.mint == mint
+            && let Some(amount) = token_balance.ui_token_amount.to_token_amount()
+        {
+            *changes.entry(owner.clone()).or_default() -= amount;
+        }
+    }
+
+    for token_balance in &transaction_meta.post_token_balances {
+        if let Some(owner) = &token_balance.owner
+            && token_balance.mint == mint
+            && let Some(amount) = token_balance.ui_token_amount.to_token_amount()
+        {
+            *changes.entry(owner.clone()).or_default() += amount;
+        }
     }
 
-    Some(Trade {
-        from: sells,
-        to: buys,
-    })
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::{
+        domain::UiTokenAmount,
+        utils::constants::test::solana_data::{TOKEN1, WALLET},
+    };
+
+    const OTHER: &str = "5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1";
+    const THIRD: &str = "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU";
+
+    fn transaction_meta(pre_balances: Vec<u64>, post_balances: Vec<u64>) -> TransactionMeta {
+        TransactionMeta {
+            err: None,
+            fee: 5_000,
+            pre_balances,
+            post_balances,
+            pre_token_balances: vec![],
+            post_token_balances: vec![],
+            loaded_addresses: Default::default(),
+            inner_instructions: None,
+        }
+    }
+
+    fn token_balance(account_index: usize, mint: &str, owner: &str, amount: u64) -> TokenBalance {
+        TokenBalance {
+            account_index,
+            mint: mint.to_string(),
+            owner: Some(owner.to_string()),
+            ui_token_amount: UiTokenAmount {
+                decimals: if mint == WSOL { 9 } else { 6 },
+                amount: amount.to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn execution_path_from_signers() {
+        assert_eq!(ExecutionPath::Direct, calc_execution_path(Some(0), 2));
+        assert_eq!(ExecutionPath::Relayed, calc_execution_path(Some(1), 2));
+        assert_eq!(ExecutionPath::KeeperFilled, calc_execution_path(Some(2), 2));
+        assert_eq!(ExecutionPath::KeeperFilled, calc_execution_path(None, 2));
+    }
+
+    #[test]
+    fn sol_change_of_the_wallet() {
+        // the fee payer spent 1 SOL + fee, the other wallet received 1 SOL
+        let transaction_meta = transaction_meta(
+            vec![3_000_000_000, 1_000_000_000],
+            vec![1_999_995_000, 2_000_000_000],
+        );
+
+        assert_eq!(
+            -1_000_000_000,
+            calc_lamport_change(&transaction_meta, Some(0))
+        );
+        assert_eq!(
+            1_000_000_000,
+            calc_lamport_change(&transaction_meta, Some(1))
+        );
+        assert_eq!(0, calc_lamport_change(&transaction_meta, None));
+    }
+
+    #[test]
+    fn execution_price_from_usdc_leg() {
+        let token_changes =
+            HashMap::from([(USDC.to_string(), -150.0), (TOKEN1.to_string(), 300.0)]);
+
+        let prices = calc_execution_prices(&token_changes, None);
+
+        assert_eq!(Some(&1.0), prices.get(USDC));
+        assert_eq!(Some(&0.5), prices.get(TOKEN1));
+    }
+
+    #[test]
+    fn execution_price_from_sol_leg() {
+        let token_changes = HashMap::from([(WSOL.to_string(), 2.0), (TOKEN1.to_string(), -1000.0)]);
+
+        let prices = calc_execution_prices(&token_changes, Some(150.0));
+
+        assert_eq!(Some(&150.0), prices.get(WSOL));
+        assert_eq!(Some(&0.3), prices.get(TOKEN1));
+
+        let prices = calc_execution_prices(&token_changes, None);
+        assert!(prices.is_empty());
+    }
+
+    #[test]
+    fn activity_kind_of_token_transfers() {
+        let wallet = WALLET.to_string();
+        let other = OTHER.to_string();
+        let account_keys = vec![&wallet, &other];
+
+        let mut transaction_meta = transaction_meta(vec![0, 0], vec![0, 0]);
+        transaction_meta.pre_token_balances = vec![token_balance(2, TOKEN1, WALLET, 5_000_000)];
+        transaction_meta.post_token_balances = vec![
+            token_balance(2, TOKEN1, WALLET, 2_000_000),
+            token_balance(3, TOKEN1, OTHER, 3_000_000),
+        ];
+
+        let token_changes = HashMap::from([(TOKEN1.to_string(), TokenAmount::new(-3_000_000, 6))]);
+        assert_eq!(
+            ActivityKind::TransferOut,
+            calc_activity_kind(
+                &transaction_meta,
+                &account_keys,
+                WALLET,
+                true,
+                &token_changes
+            )
+        );
+        assert_eq!(
+            Some(OTHER.to_string()),
+            find_counterparty(&transaction_meta, &account_keys, WALLET, &token_changes)
+        );
+
+        // the same tokens landing in several wallets from a foreign signer
+        let token_changes = HashMap::from([(TOKEN1.to_string(), TokenAmount::new(3_000_000, 6))]);
+        transaction_meta.pre_token_balances = vec![];
+        assert_eq!(
+            ActivityKind::Airdrop,
+            calc_activity_kind(
+                &transaction_meta,
+                &account_keys,
+                OTHER,
+                false,
+                &token_changes
+            )
+        );
+    }
+
+    #[test]
+    fn activity_kind_of_sol_movements() {
+        let wallet = WALLET.to_string();
+        let other = OTHER.to_string();
+        let stake_program = STAKE_PROGRAM_ID.to_string();
+
+        // closing a token account refunds its rent to the wallet
+        let account_keys = vec![&wallet, &other];
+        let close_meta = transaction_meta(vec![1_000_000_000, 2_039_280], vec![1_002_034_280, 0]);
+        let token_changes = HashMap::from([(WSOL.to_string(), TokenAmount::new(2_039_280, 9))]);
+        assert_eq!(
+            ActivityKind::AccountClose,
+            calc_activity_kind(&close_meta, &account_keys, WALLET, true, &token_changes)
+        );
+
+        // the closed account refunds a third account, the wallet only receives a transfer
+        let third = THIRD.to_string();
+        let account_keys = vec![&wallet, &other, &third];
+        let close_meta = transaction_meta(
+            vec![1_000_000_000, 2_039_280, 0],
+            vec![1_000_995_000, 0, 1_039_280],
+        );
+        let token_changes = HashMap::from([(WSOL.to_string(), TokenAmount::new(1_000_000, 9))]);
+        assert_eq!(
+            ActivityKind::TransferIn,
+            calc_activity_kind(&close_meta, &account_keys, WALLET, true, &token_changes)
+        );
+
+        // delegating to a stake account
+        let account_keys = vec![&wallet, &other, &stake_program];
+        let stake_meta = transaction_meta(
+            vec![3_000_000_000, 0, 1],
+            vec![1_999_995_000, 1_000_000_000, 1],
+        );
+        let token_changes =
+            HashMap::from([(WSOL.to_string(), TokenAmount::new(-1_000_000_000, 9))]);
+        assert_eq!(
+            ActivityKind::Stake,
+            calc_activity_kind(&stake_meta, &account_keys, WALLET, true, &token_changes)
+        );
+
+        let token_changes = HashMap::from([(WSOL.to_string(), TokenAmount::new(1_000_000_000, 9))]);
+        assert_eq!(
+            ActivityKind::Unstake,
+            calc_activity_kind(&stake_meta, &account_keys, WALLET, true, &token_changes)
+        );
+    }
+
+    #[test]
+    fn sol_costs_of_a_buy() {
+        // 1 SOL swapped to a new token account, with a priority fee and a tip
+        let wallet = WALLET.to_string();
+        let tip_account = JITO_TIP_ACCOUNTS[0].to_string();
+        let token_account = OTHER.to_string();
+        let account_keys = vec![&wallet, &tip_account, &token_account];
+
+        let mut transaction_meta = transaction_meta(
+            vec![10_000_000_000, 1, 0],
+            vec![8_996_945_720, 1_000_001, 2_039_280],
+        );
+        transaction_meta.fee = 15_000;
+        transaction_meta.post_token_balances = vec![token_balance(2, TOKEN1, WALLET, 300_000_000)];
+
+        let sol_costs = calc_sol_costs(&transaction_meta, &account_keys, WALLET, 1);
+        assert_eq!(
+            LamportCosts {
+                base_fee: 5_000,
+                priority_fee: 10_000,
+                tip: 1_000_000,
+                rent_created: 2_039_280,
+                rent_reclaimed: 0,
+            },
+            sol_costs
+        );
+
+        let subscription_input = SubscriptionInput::new(WALLET.to_string(), Default::default());
+        let token_changes = calc_token_changes_for_wallet(
+            &transaction_meta,
+            Some(0),
+            &subscription_input,
+            sol_costs.excluded(),
+        );
+        assert_eq!(
+            Some(&TokenAmount::new(-1_000_000_000, 9)),
+            token_changes.get(WSOL)
+        );
+        assert_eq!(
+            Some(&TokenAmount::new(300_000_000, 6)),
+            token_changes.get(TOKEN1)
+        );
+    }
+
+    #[test]
+    fn sol_costs_of_an_unwrap() {
+        // closing a wrapped SOL account returns the wrapped amount and the rent
+        let wallet = WALLET.to_string();
+        let token_account = OTHER.to_string();
+        let account_keys = vec![&wallet, &token_account];
+
+        let mut transaction_meta =
+            transaction_meta(vec![1_000_000_000, 502_039_280], vec![1_502_034_280, 0]);
+        transaction_meta.pre_token_balances = vec![token_balance(1, WSOL, WALLET, 500_000_000)];
+
+        let sol_costs = calc_sol_costs(&transaction_meta, &account_keys, WALLET, 1);
+        assert_eq!(
+            LamportCosts {
+                base_fee: 5_000,
+                priority_fee: 0,
+                tip: 0,
+                rent_created: 0,
+                rent_reclaimed: 2_039_280,
+            },
+            sol_costs
+        );
+
+        let subscription_input = SubscriptionInput::new(WALLET.to_string(), Default::default());
+        let balance_changes =
+            calc_token_changes_for_wallet(&transaction_meta, Some(0), &subscription_input, 0);
+        assert_eq!(TokenAmount::new(2_039_280, 9), balance_changes[WSOL]);
+
+        let trade_changes = calc_token_changes_for_wallet(
+            &transaction_meta,
+            Some(0),
+            &subscription_input,
+            sol_costs.excluded(),
+        );
+        assert!(trade_changes.is_empty());
+    }
+
+    #[test]
+    fn sol_costs_of_a_tip_from_a_co_signer() {
+        // the co-signer tips, the wallet pays the fee and receives 1 SOL
+        let wallet = WALLET.to_string();
+        let co_signer = OTHER.to_string();
+        let tip_account = JITO_TIP_ACCOUNTS[0].to_string();
+        let account_keys = vec![&wallet, &co_signer, &tip_account];
+
+        let mut transaction_meta = transaction_meta(
+            vec![1_000_000_000, 2_000_000_000, 1],
+            vec![1_999_990_000, 999_000_000, 1_000_001],
+        );
+        transaction_meta.fee = 10_000;
+
+        let sol_costs = calc_sol_costs(&transaction_meta, &account_keys, WALLET, 2);
+        assert_eq!(
+            LamportCosts {
+                base_fee: 10_000,
+                priority_fee: 0,
+                tip: 0,
+                rent_created: 0,
+                rent_reclaimed: 0,
+            },
+            sol_costs
+        );
+    }
 }
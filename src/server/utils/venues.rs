@@ -0,0 +1,171 @@
+use crate::proto::Venue;
+
+// programs of the known swap venues
+const VENUE_PROGRAM_IDS: &[(&str, Venue)] = &[
+    // Jupiter v6
+    (
+        "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4",
+        Venue::Jupiter,
+    ),
+    // Jupiter v4
+    (
+        "JUP4Fb2cqiRUcaTHdrPC8h2gNsA2ETXiPDD33WcGuJB",
+        Venue::Jupiter,
+    ),
+    (
+        "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8",
+        Venue::RaydiumAmm,
+    ),
+    (
+        "CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK",
+        Venue::RaydiumClmm,
+    ),
+    (
+        "CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C",
+        Venue::RaydiumCpmm,
+    ),
+    (
+        "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc",
+        Venue::OrcaWhirlpool,
+    ),
+    (
+        "LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo",
+        Venue::MeteoraDlmm,
+    ),
+    (
+        "Eo7WjKq67rjJQSZxS6z3YkapzY3eMj6Xy8X5EQVn5UaB",
+        Venue::MeteoraPools,
+    ),
+    (
+        "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P",
+        Venue::PumpFun,
+    ),
+    (
+        "pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA",
+        Venue::PumpSwap,
+    ),
+    (
+        "PhoeNiXZ8ByJGLkxNfZRnkUfjvmuYqLR89jjFHGqdXY",
+        Venue::Phoenix,
+    ),
+    (
+        "opnb2LAfJYbRMAHHvqjCwQxanZn7ReEHp1k81EohpZb",
+        Venue::Openbook,
+    ),
+];
+
+fn is_aggregator(venue: Venue) -> bool {
+    venue == Venue::Jupiter
+}
+
+// an aggregator anywhere in the transaction wins, otherwise the first venue executed
+pub fn calc_venue(invoked_programs: &[&String]) -> Venue {
+    let venues: Vec<Venue> = invoked_programs
+        .iter()
+        .filter_map(|program| {
+            VENUE_PROGRAM_IDS
+                .iter()
+                .find(|(program_id, _)| program == program_id)
+                .map(|(_, venue)| *venue)
+        })
+        .collect();
+
+    venues
+        .iter()
+        .find(|venue| is_aggregator(**venue))
+        .or(venues.first())
+        .cloned()
+        .unwrap_or(Venue::UnknownVenue)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::domain::TransactionResult;
+
+    const COMPUTE_BUDGET: &str = "ComputeBudget111111111111111111111111111111";
+    const RAYDIUM_AMM: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
+    const ORCA_WHIRLPOOL: &str = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
+    const JUPITER: &str = "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4";
+
+    fn transaction_result(
+        account_keys: &[&str],
+        instructions: &[usize],
+        inner_instructions: serde_json::Value,
+    ) -> TransactionResult {
+        let instructions: Vec<serde_json::Value> = instructions
+            .iter()
+            .map(|index| serde_json::json!({ "programIdIndex": index }))
+            .collect();
+
+        serde_json::from_value(serde_json::json!({
+            "blockTime": 0,
+            "slot": 0,
+            "meta": {
+                "err": null,
+                "fee": 5000,
+                "preBalances": [],
+                "postBalances": [],
+                "preTokenBalances": [],
+                "postTokenBalances": [],
+                "innerInstructions": inner_instructions,
+            },
+            "transaction": {
+                "signatures": [],
+                "message": {
+                    "accountKeys": account_keys,
+                    "instructions": instructions,
+                },
+            },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn venue_of_a_direct_swap() {
+        let transaction_result = transaction_result(
+            &[COMPUTE_BUDGET, ORCA_WHIRLPOOL, RAYDIUM_AMM],
+            &[0, 2],
+            serde_json::Value::Null,
+        );
+
+        assert_eq!(
+            Venue::RaydiumAmm,
+            calc_venue(&transaction_result.invoked_programs())
+        );
+    }
+
+    #[test]
+    fn venue_of_a_routed_swap() {
+        // the AMMs are invoked by the aggregator's instruction
+        let transaction_result = transaction_result(
+            &[COMPUTE_BUDGET, ORCA_WHIRLPOOL, RAYDIUM_AMM, JUPITER],
+            &[0, 3],
+            serde_json::json!([{
+                "index": 1,
+                "instructions": [{ "programIdIndex": 1 }, { "programIdIndex": 2 }],
+            }]),
+        );
+
+        let invoked_programs = transaction_result.invoked_programs();
+        assert_eq!(
+            vec![COMPUTE_BUDGET, JUPITER, ORCA_WHIRLPOOL, RAYDIUM_AMM],
+            invoked_programs
+                .iter()
+                .map(|program| program.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(Venue::Jupiter, calc_venue(&invoked_programs));
+    }
+
+    #[test]
+    fn unknown_venue() {
+        let transaction_result =
+            transaction_result(&[COMPUTE_BUDGET], &[0], serde_json::Value::Null);
+
+        assert_eq!(
+            Venue::UnknownVenue,
+            calc_venue(&transaction_result.invoked_programs())
+        );
+    }
+}
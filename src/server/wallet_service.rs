@@ -1,39 +1,133 @@
 //use futures_util::TryFutureExt;
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::sync::mpsc;
+use tokio::sync::{RwLock, mpsc};
 use tokio_stream::wrappers::ReceiverStream;
 use tonic::async_trait;
-use tonic::{Request, Response, Status, transport::Server};
+use tonic::{
+    Request, Response, Status,
+    transport::{Server, ServerTlsConfig},
+};
 use uuid::Uuid;
 
+use crate::proto::{
+    AddTokensRequest, AddWalletsRequest, AlertsResponse, DeleteAlertRequest,
+    GetPortfolioHistoryRequest, GetPortfolioHistoryResponse, GetPositionsRequest,
+    GetPositionsResponse, GetTradeHistoryRequest, GetTradeHistoryResponse, GetTradeRequest,
+    GetTradeResponse, HeartbeatRequest, HeartbeatResponse, HoldingsRequest, HoldingsResponse,
+    ListAlertsRequest, RemoveTokensRequest, RemoveWalletsRequest, SetAlertRequest,
+    SetAlertResponse, TokensResponse, WalletsResponse,
+};
 use crate::proto::{
     CallRequest, CallResponse, InitRequest, InitResponse, SubscribeRequest, SubscribeResponse,
     UnsubscribeRequest, UnsubscribeResponse,
     cli_service_server::{CliService, CliServiceServer},
 };
-use crate::proto::{GetTradeRequest, GetTradeResponse, HoldingsRequest, HoldingsResponse};
-use crate::server::states::{AppState, ClientState, SubscriptionState};
-use crate::server::utils::constants::WSOL;
-use crate::server::utils::{handle_transaction, query_holdings, store_tokens, validate_init_data};
+use crate::server::auth::{ApiKeyInterceptor, Principal};
+use crate::server::config::{ChannelsConfig, SessionsConfig};
+use crate::server::domain::InputValidationError;
+use crate::server::states::{
+    AppState, ClientState, EventFilter, EventFilterType, SubscriptionState, WalletState,
+};
+use crate::server::utils::constants::{DEFAULT_PORTFOLIO_RESOLUTION_SECS, WSOL};
+use crate::server::utils::{
+    build_portfolio_history, build_positions, get_token_programs, handle_transaction,
+    query_holdings, reap_sessions, resolve_range, store_tokens, stream_trade_history,
+    validate_init_data, validate_tokens, validate_wallets, watch_portfolios, watch_prices,
+};
 
 pub struct WalletService {
     state: Arc<AppState>,
+    channels: ChannelsConfig,
+    sessions: SessionsConfig,
 }
 
 impl WalletService {
-    pub fn new(state: Arc<AppState>) -> Self {
-        Self { state }
+    pub fn new(state: Arc<AppState>, channels: ChannelsConfig, sessions: SessionsConfig) -> Self {
+        Self {
+            state,
+            channels,
+            sessions,
+        }
+    }
+
+    // the session must have been created by the principal of the request,
+    // a missing session is left to the handler, every request is a heartbeat
+    async fn authorize_client<T>(&self, request: &Request<T>) -> Result<Uuid, Status> {
+        let client_id = extract_client_id(request)?;
+        let principal = extract_principal(request);
+
+        if let Some(client_state) = self.state.clients.read().await.get(&client_id) {
+            if client_state.principal != principal {
+                tracing::warn!(
+                    "Client {} of {} accessed by {}",
+                    client_id,
+                    client_state.principal,
+                    principal
+                );
+                return Err(Status::permission_denied(
+                    "Client belongs to another principal",
+                ));
+            }
+            client_state.touch();
+        }
+
+        Ok(client_id)
+    }
+
+    async fn logs_subscribe(
+        &self,
+        client_state: &ClientState,
+        wallet_state: &WalletState,
+        event_filter: EventFilterType,
+        tx: mpsc::Sender<Result<SubscribeResponse, Status>>,
+    ) -> Option<u64> {
+        match client_state
+            .ws_client
+            .write()
+            .await
+            .logs_subscribe(
+                wallet_state.subscription_input.clone(),
+                self.state.off_chain_rpc_client.clone(),
+                self.state.token_store.clone(),
+                self.state.on_chain_rpc_client.clone(),
+                client_state.trade_ledger.clone(),
+                event_filter,
+                tx,
+            )
+            .await
+        {
+            Ok(subscription_id) => {
+                tracing::info!(
+                    "Subscription was successful for wallet {} with id: {}",
+                    wallet_state.subscription_input.wallet,
+                    subscription_id
+                );
+                Some(subscription_id)
+            }
+            Err(e) => {
+                tracing::error!(
+                    "Failed to subscribe wallet {}: {}",
+                    wallet_state.subscription_input.wallet,
+                    e
+                );
+                None
+            }
+        }
     }
 }
 
 #[async_trait]
 impl CliService for WalletService {
     type SubscribeStream = ReceiverStream<Result<SubscribeResponse, Status>>;
+    type GetTradeHistoryStream = ReceiverStream<Result<GetTradeHistoryResponse, Status>>;
 
     #[tracing::instrument(name = "Init", skip_all)]
     async fn init(&self, request: Request<InitRequest>) -> Result<Response<InitResponse>, Status> {
         tracing::info!("New client request received");
         let new_id = Uuid::new_v4();
+        let principal = extract_principal(&request);
 
         let mut init_request = request.into_inner();
 
@@ -46,16 +140,29 @@ impl CliService for WalletService {
         store_tokens(
             &init_request.tokens,
             self.state.off_chain_rpc_client.clone(),
+            self.state.on_chain_rpc_client.clone(),
             self.state.token_store.clone(),
         )
         .await?;
 
+        let token_programs =
+            get_token_programs(&init_request.tokens, self.state.token_store.clone()).await;
+
         self.state.clients.write().await.insert(
-            new_id.clone(),
-            ClientState::build(init_request, self.state.ws_client_factory.clone()),
+            new_id,
+            ClientState::build(
+                init_request,
+                principal.clone(),
+                token_programs,
+                self.state.ws_client_factory.clone(),
+            ),
         );
 
-        tracing::info!("Registered new client with ID: {}", new_id);
+        tracing::info!(
+            "Registered new client with ID: {} for {}",
+            new_id,
+            principal
+        );
 
         Ok(Response::new(InitResponse {
             client_id: new_id.to_string(),
@@ -67,9 +174,10 @@ impl CliService for WalletService {
         &self,
         request: Request<SubscribeRequest>,
     ) -> Result<Response<<WalletService as CliService>::SubscribeStream>, Status> {
-        let client_id = extract_client_id(&request)?;
+        let client_id = self.authorize_client(&request).await?;
+        let filter = request.into_inner().filter;
 
-        let (tx, rx) = mpsc::channel(10);
+        let (tx, rx) = mpsc::channel(self.channels.subscription);
 
         let mut clients = self.state.clients.write().await;
 
@@ -82,22 +190,48 @@ impl CliService for WalletService {
 
                 tracing::info!("call logs subscribe for: {}", client_id);
 
-                if let Ok(subscription_id) = client_state
-                    .ws_client
-                    .write()
-                    .await
-                    .logs_subscribe(
-                        client_state.subscription_input.clone(),
-                        self.state.off_chain_rpc_client.clone(),
-                        self.state.token_store.clone(),
-                        self.state.on_chain_rpc_client.clone(),
-                        tx.clone(),
-                    )
-                    .await
-                {
-                    client_state.logs_subscription = Some(SubscriptionState { subscription_id });
-                    tracing::info!("Subscription was successful with id: {}", subscription_id);
+                let event_filter = Arc::new(RwLock::new(EventFilter::new(
+                    filter.unwrap_or_default(),
+                    client_state.tokens.keys().cloned().collect(),
+                )));
+
+                // all the wallets of the client are multiplexed into the same stream
+                let mut subscription_ids = HashMap::new();
+                let mut failed_wallets = vec![];
+                for (wallet, wallet_state) in client_state.wallets.iter() {
+                    match self
+                        .logs_subscribe(
+                            client_state,
+                            wallet_state,
+                            event_filter.clone(),
+                            tx.clone(),
+                        )
+                        .await
+                    {
+                        Some(subscription_id) => {
+                            subscription_ids.insert(wallet.clone(), subscription_id);
+                        }
+                        None => failed_wallets.push(wallet.clone()),
+                    }
+                }
+
+                // no stream is opened without a live subscription
+                if subscription_ids.is_empty() {
+                    return Err(Status::unavailable("Failed to subscribe the wallets"));
+                }
+                if !failed_wallets.is_empty() {
+                    // the stream is not read before the response, so the status must not wait
+                    let _ = tx.try_send(Ok(SubscribeResponse::status(format!(
+                        "Failed to subscribe wallets: {}",
+                        failed_wallets.join(", ")
+                    ))));
                 }
+
+                client_state.logs_subscription = Some(SubscriptionState {
+                    subscription_ids,
+                    tx: tx.clone(),
+                    event_filter,
+                });
             }
             None => {
                 tracing::warn!("Client {} not found", client_id);
@@ -113,22 +247,16 @@ impl CliService for WalletService {
         &self,
         request: Request<UnsubscribeRequest>,
     ) -> Result<Response<UnsubscribeResponse>, Status> {
-        let client_id = extract_client_id(&request)?;
+        let client_id = self.authorize_client(&request).await?;
         //let state_clone = self.state.clone();
         let mut clients = self.state.clients.write().await;
 
         match clients.get_mut(&client_id) {
             Some(client_state) => {
-                if let Some(subscription) = &client_state.logs_subscription {
-                    let _ = client_state
-                        .ws_client
-                        .write()
-                        .await
-                        .logs_unsubscribe(subscription.subscription_id)
-                        .await;
+                if let Some(subscription) = client_state.logs_subscription.take() {
+                    subscription.close(&client_state.ws_client).await;
                 }
 
-                client_state.logs_subscription = None;
                 tracing::info!("Unsubscription was successful for client: {}", client_id);
             }
             None => {
@@ -147,15 +275,17 @@ impl CliService for WalletService {
         &self,
         request: Request<HoldingsRequest>,
     ) -> Result<Response<HoldingsResponse>, Status> {
-        let client_id = extract_client_id(&request)?;
+        let client_id = self.authorize_client(&request).await?;
 
+        let all = request.into_inner().all;
         let clients = self.state.clients.read().await;
 
         match clients.get(&client_id) {
             Some(client_state) => {
+                let wallets: Vec<WalletState> = client_state.wallets.values().cloned().collect();
                 let holdings_response = query_holdings(
-                    &client_state.subscription_input.wallet,
-                    client_state.token_account_map.clone(),
+                    &wallets,
+                    all,
                     self.state.token_store.clone(),
                     self.state.on_chain_rpc_client.clone(),
                     self.state.off_chain_rpc_client.clone(),
@@ -180,25 +310,198 @@ impl CliService for WalletService {
         &self,
         request: Request<GetTradeRequest>,
     ) -> Result<Response<GetTradeResponse>, Status> {
-        let client_id = extract_client_id(&request)?;
+        let client_id = self.authorize_client(&request).await?;
 
         let get_tx_request = request.into_inner();
         let clients = self.state.clients.read().await;
 
         match clients.get(&client_id) {
             Some(client_state) => {
-                let trade = handle_transaction(
-                    get_tx_request.signature,
-                    client_state.subscription_input.clone(),
-                    self.state.off_chain_rpc_client.clone(),
-                    self.state.token_store.clone(),
-                    self.state.on_chain_rpc_client.clone(),
+                let wallets = select_wallets(client_state, get_tx_request.wallet)?;
+
+                // the first wallet the transaction means something for
+                let mut event = None;
+                for wallet_state in wallets {
+                    event = handle_transaction(
+                        get_tx_request.signature.clone(),
+                        wallet_state.subscription_input.clone(),
+                        self.state.off_chain_rpc_client.clone(),
+                        self.state.token_store.clone(),
+                        self.state.on_chain_rpc_client.clone(),
+                    )
+                    .await
+                    .ok()
+                    .flatten();
+
+                    if event.is_some() {
+                        break;
+                    }
+                }
+
+                Ok(Response::new(
+                    event.map(GetTradeResponse::from).unwrap_or_default(),
+                ))
+            }
+            None => {
+                tracing::warn!("Client {} not found", client_id);
+                Err(Status::not_found("Client not found"))
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "Get Trade History", skip_all)]
+    async fn get_trade_history(
+        &self,
+        request: Request<GetTradeHistoryRequest>,
+    ) -> Result<Response<<WalletService as CliService>::GetTradeHistoryStream>, Status> {
+        let client_id = self.authorize_client(&request).await?;
+
+        let history_request = request.into_inner();
+
+        let (wallets, trade_ledger) = match self.state.clients.read().await.get(&client_id) {
+            Some(client_state) => (
+                select_wallets(client_state, history_request.wallet.clone())?,
+                client_state.trade_ledger.clone(),
+            ),
+            None => {
+                tracing::warn!("Client {} not found", client_id);
+                return Err(Status::not_found("Client not found"));
+            }
+        };
+
+        let (tx, rx) = mpsc::channel(self.channels.history);
+
+        let state = self.state.clone();
+        tokio::spawn(async move {
+            // the wallets are streamed one after the other, the limit applies to each
+            for wallet_state in wallets {
+                if tx.is_closed() {
+                    break;
+                }
+
+                if let Err(e) = stream_trade_history(
+                    history_request.clone(),
+                    wallet_state.subscription_input.clone(),
+                    state.off_chain_rpc_client.clone(),
+                    state.token_store.clone(),
+                    state.on_chain_rpc_client.clone(),
+                    trade_ledger.clone(),
+                    tx.clone(),
                 )
                 .await
-                .ok()
-                .flatten();
+                {
+                    tracing::error!("Failed to fetch trade history: {}", e);
+                    let _ = tx
+                        .send(Err(Status::internal("Failed to fetch trade history")))
+                        .await;
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    #[tracing::instrument(name = "Get Positions", skip_all)]
+    async fn get_positions(
+        &self,
+        request: Request<GetPositionsRequest>,
+    ) -> Result<Response<GetPositionsResponse>, Status> {
+        let client_id = self.authorize_client(&request).await?;
+
+        let clients = self.state.clients.read().await;
+
+        match clients.get(&client_id) {
+            Some(client_state) => {
+                let positions = build_positions(client_state.trade_ledger.read().await.trades());
+
+                Ok(Response::new(GetPositionsResponse { positions }))
+            }
+            None => {
+                tracing::warn!("Client {} not found", client_id);
+                Err(Status::not_found("Client not found"))
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "Get Portfolio History", skip_all)]
+    async fn get_portfolio_history(
+        &self,
+        request: Request<GetPortfolioHistoryRequest>,
+    ) -> Result<Response<GetPortfolioHistoryResponse>, Status> {
+        let client_id = self.authorize_client(&request).await?;
+
+        let history_request = request.into_inner();
+        let (from, to) = resolve_range(history_request.from, history_request.to)?;
+        let resolution = match history_request.resolution {
+            0 => DEFAULT_PORTFOLIO_RESOLUTION_SECS,
+            resolution => resolution,
+        };
+
+        let wallets: Vec<String> = match self.state.clients.read().await.get(&client_id) {
+            Some(client_state) => select_wallets(client_state, history_request.wallet)?
+                .into_iter()
+                .map(|wallet_state| wallet_state.subscription_input.wallet.clone())
+                .collect(),
+            None => {
+                tracing::warn!("Client {} not found", client_id);
+                return Err(Status::not_found("Client not found"));
+            }
+        };
+
+        let snapshots = self
+            .state
+            .snapshot_store
+            .read()
+            .await
+            .get_snapshots(&wallets, from, to)
+            .await?;
+
+        Ok(Response::new(GetPortfolioHistoryResponse {
+            points: build_portfolio_history(snapshots, resolution),
+        }))
+    }
+
+    #[tracing::instrument(name = "Add Wallets", skip_all)]
+    async fn add_wallets(
+        &self,
+        request: Request<AddWalletsRequest>,
+    ) -> Result<Response<WalletsResponse>, Status> {
+        let client_id = self.authorize_client(&request).await?;
+
+        let wallets = request.into_inner().wallets;
+        validate_wallets(&wallets)?;
+
+        let mut clients = self.state.clients.write().await;
 
-                Ok(Response::new(GetTradeResponse { trade }))
+        match clients.get_mut(&client_id) {
+            Some(client_state) => {
+                for wallet in wallets {
+                    let Some(wallet_state) = client_state.add_wallet(wallet.clone()).cloned()
+                    else {
+                        continue;
+                    };
+                    tracing::info!("Added wallet {} for client: {}", wallet, client_id);
+
+                    // an active subscription is extended with the new wallet
+                    if let Some((event_filter, tx)) =
+                        client_state.logs_subscription.as_ref().map(|subscription| {
+                            (subscription.event_filter.clone(), subscription.tx.clone())
+                        })
+                        && let Some(subscription_id) = self
+                            .logs_subscribe(client_state, &wallet_state, event_filter, tx)
+                            .await
+                        && let Some(subscription) = client_state.logs_subscription.as_mut()
+                    {
+                        subscription
+                            .subscription_ids
+                            .insert(wallet, subscription_id);
+                    }
+                }
+
+                Ok(Response::new(WalletsResponse {
+                    wallets: client_state.wallet_list(),
+                }))
             }
             None => {
                 tracing::warn!("Client {} not found", client_id);
@@ -207,6 +510,247 @@ impl CliService for WalletService {
         }
     }
 
+    #[tracing::instrument(name = "Remove Wallets", skip_all)]
+    async fn remove_wallets(
+        &self,
+        request: Request<RemoveWalletsRequest>,
+    ) -> Result<Response<WalletsResponse>, Status> {
+        let client_id = self.authorize_client(&request).await?;
+
+        let wallets = request.into_inner().wallets;
+
+        let mut clients = self.state.clients.write().await;
+
+        match clients.get_mut(&client_id) {
+            Some(client_state) => {
+                if client_state
+                    .wallets
+                    .keys()
+                    .all(|wallet| wallets.contains(wallet))
+                {
+                    return Err(InputValidationError::MissingWallets.into());
+                }
+
+                for wallet in wallets {
+                    if client_state.wallets.remove(&wallet).is_none() {
+                        continue;
+                    }
+
+                    if let Some(subscription_id) = client_state
+                        .logs_subscription
+                        .as_mut()
+                        .and_then(|subscription| subscription.subscription_ids.remove(&wallet))
+                    {
+                        let _ = client_state
+                            .ws_client
+                            .write()
+                            .await
+                            .logs_unsubscribe(subscription_id)
+                            .await;
+                    }
+                    tracing::info!("Removed wallet {} for client: {}", wallet, client_id);
+                }
+
+                Ok(Response::new(WalletsResponse {
+                    wallets: client_state.wallet_list(),
+                }))
+            }
+            None => {
+                tracing::warn!("Client {} not found", client_id);
+                Err(Status::not_found("Client not found"))
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "Add Tokens", skip_all)]
+    async fn add_tokens(
+        &self,
+        request: Request<AddTokensRequest>,
+    ) -> Result<Response<TokensResponse>, Status> {
+        let client_id = self.authorize_client(&request).await?;
+
+        let tokens = request.into_inner().tokens;
+        validate_tokens(&tokens)?;
+
+        if !self.state.clients.read().await.contains_key(&client_id) {
+            tracing::warn!("Client {} not found", client_id);
+            return Err(Status::not_found("Client not found"));
+        }
+
+        // the metadata is fetched before locking the clients for writing
+        store_tokens(
+            &tokens,
+            self.state.off_chain_rpc_client.clone(),
+            self.state.on_chain_rpc_client.clone(),
+            self.state.token_store.clone(),
+        )
+        .await?;
+
+        let token_programs = get_token_programs(&tokens, self.state.token_store.clone()).await;
+
+        let mut clients = self.state.clients.write().await;
+
+        match clients.get_mut(&client_id) {
+            Some(client_state) => {
+                let mut new_tokens = client_state.tokens.clone();
+                new_tokens.extend(token_programs);
+                if new_tokens.len() != client_state.tokens.len() {
+                    client_state.set_tokens(new_tokens).await;
+                    tracing::info!("Updated tokens for client: {}", client_id);
+                }
+
+                Ok(Response::new(TokensResponse {
+                    tokens: client_state.token_list(),
+                }))
+            }
+            None => {
+                tracing::warn!("Client {} not found", client_id);
+                Err(Status::not_found("Client not found"))
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "Remove Tokens", skip_all)]
+    async fn remove_tokens(
+        &self,
+        request: Request<RemoveTokensRequest>,
+    ) -> Result<Response<TokensResponse>, Status> {
+        let client_id = self.authorize_client(&request).await?;
+
+        let tokens: HashSet<String> = request
+            .into_inner()
+            .tokens
+            .into_iter()
+            .filter(|token| token != WSOL)
+            .collect();
+
+        let mut clients = self.state.clients.write().await;
+
+        match clients.get_mut(&client_id) {
+            Some(client_state) => {
+                let new_tokens: HashMap<String, String> = client_state
+                    .tokens
+                    .iter()
+                    .filter(|(token, _)| !tokens.contains(*token))
+                    .map(|(token, token_program)| (token.clone(), token_program.clone()))
+                    .collect();
+                if new_tokens.len() != client_state.tokens.len() {
+                    client_state.set_tokens(new_tokens).await;
+                    tracing::info!("Updated tokens for client: {}", client_id);
+                }
+
+                Ok(Response::new(TokensResponse {
+                    tokens: client_state.token_list(),
+                }))
+            }
+            None => {
+                tracing::warn!("Client {} not found", client_id);
+                Err(Status::not_found("Client not found"))
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "Set Alert", skip_all)]
+    async fn set_alert(
+        &self,
+        request: Request<SetAlertRequest>,
+    ) -> Result<Response<SetAlertResponse>, Status> {
+        let client_id = self.authorize_client(&request).await?;
+        let set_alert_request = request.into_inner();
+
+        validate_tokens(std::slice::from_ref(&set_alert_request.mint))?;
+        if !set_alert_request.value.is_finite() || set_alert_request.value <= 0.0 {
+            return Err(InputValidationError::InvalidAlertValue.into());
+        }
+
+        let mut clients = self.state.clients.write().await;
+
+        match clients.get_mut(&client_id) {
+            Some(client_state) => {
+                let condition = set_alert_request.condition();
+                let alert = client_state.alerts.add(
+                    set_alert_request.mint,
+                    condition,
+                    set_alert_request.value,
+                    set_alert_request.repeat,
+                );
+                tracing::info!("Added alert {} for client: {}", alert.id, client_id);
+
+                Ok(Response::new(SetAlertResponse { alert: Some(alert) }))
+            }
+            None => {
+                tracing::warn!("Client {} not found", client_id);
+                Err(Status::not_found("Client not found"))
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "List Alerts", skip_all)]
+    async fn list_alerts(
+        &self,
+        request: Request<ListAlertsRequest>,
+    ) -> Result<Response<AlertsResponse>, Status> {
+        let client_id = self.authorize_client(&request).await?;
+
+        let clients = self.state.clients.read().await;
+
+        match clients.get(&client_id) {
+            Some(client_state) => Ok(Response::new(AlertsResponse {
+                alerts: client_state.alerts.list(),
+            })),
+            None => {
+                tracing::warn!("Client {} not found", client_id);
+                Err(Status::not_found("Client not found"))
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "Delete Alert", skip_all)]
+    async fn delete_alert(
+        &self,
+        request: Request<DeleteAlertRequest>,
+    ) -> Result<Response<AlertsResponse>, Status> {
+        let client_id = self.authorize_client(&request).await?;
+        let id = request.into_inner().id;
+
+        let mut clients = self.state.clients.write().await;
+
+        match clients.get_mut(&client_id) {
+            Some(client_state) => {
+                if !client_state.alerts.remove(id) {
+                    return Err(Status::not_found("Alert not found"));
+                }
+                tracing::info!("Deleted alert {} for client: {}", id, client_id);
+
+                Ok(Response::new(AlertsResponse {
+                    alerts: client_state.alerts.list(),
+                }))
+            }
+            None => {
+                tracing::warn!("Client {} not found", client_id);
+                Err(Status::not_found("Client not found"))
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "Heartbeat", skip_all)]
+    async fn heartbeat(
+        &self,
+        request: Request<HeartbeatRequest>,
+    ) -> Result<Response<HeartbeatResponse>, Status> {
+        let client_id = self.authorize_client(&request).await?;
+
+        match self.state.clients.read().await.get(&client_id) {
+            Some(_) => Ok(Response::new(HeartbeatResponse {
+                ttl_secs: self.sessions.ttl_secs,
+            })),
+            None => {
+                tracing::warn!("Client {} not found", client_id);
+                Err(Status::not_found("Client not found"))
+            }
+        }
+    }
+
     async fn call(&self, request: Request<CallRequest>) -> Result<Response<CallResponse>, Status> {
         let payload = request.into_inner().payload;
         let reply = format!("Processed: {}", payload);
@@ -214,6 +758,23 @@ impl CliService for WalletService {
     }
 }
 
+#[allow(clippy::result_large_err)]
+fn select_wallets(
+    client_state: &ClientState,
+    wallet: Option<String>,
+) -> Result<Vec<WalletState>, Status> {
+    match wallet {
+        Some(wallet) => client_state
+            .wallets
+            .get(&wallet)
+            .cloned()
+            .map(|wallet_state| vec![wallet_state])
+            .ok_or_else(|| Status::not_found("Wallet not found")),
+        None => Ok(client_state.wallets.values().cloned().collect()),
+    }
+}
+
+#[allow(clippy::result_large_err)]
 fn extract_client_id<T>(req: &Request<T>) -> Result<Uuid, Status> {
     let client_id = req
         .metadata()
@@ -225,15 +786,49 @@ fn extract_client_id<T>(req: &Request<T>) -> Result<Uuid, Status> {
     Uuid::parse_str(client_id).map_err(|_| Status::invalid_argument("malformed uuid"))
 }
 
-pub async fn run_server(addr: &str, state: AppState) -> Result<(), Box<dyn std::error::Error>> {
-    let svc = WalletService::new(Arc::new(state));
+// set by the ApiKeyInterceptor, a service without it is open to anyone
+fn extract_principal<T>(req: &Request<T>) -> Principal {
+    req.extensions()
+        .get::<Principal>()
+        .cloned()
+        .unwrap_or_else(Principal::anonymous)
+}
+
+pub async fn run_server(
+    addr: SocketAddr,
+    state: AppState,
+    channels: ChannelsConfig,
+    sessions: SessionsConfig,
+    auth: ApiKeyInterceptor,
+    tls: Option<ServerTlsConfig>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let state = Arc::new(state);
+    tokio::spawn(watch_prices(state.clone()));
+    tokio::spawn(watch_portfolios(state.clone()));
+    tokio::spawn(reap_sessions(state.clone(), sessions));
+
+    let svc = WalletService::new(state, channels, sessions);
+
+    tracing::info!(
+        "Server listening on {} ({})",
+        addr,
+        if tls.is_some() { "TLS" } else { "plaintext" }
+    );
+    if !auth.is_enabled() {
+        tracing::warn!("No API keys configured, the server is open to any client");
+    } else if tls.is_none() {
+        tracing::warn!("API keys are sent in plaintext without TLS");
+    }
 
-    tracing::info!("Server listening on {}", addr);
+    let mut builder = Server::builder();
+    if let Some(tls) = tls {
+        builder = builder.tls_config(tls)?;
+    }
 
     // INFO: adding TraceLayer gave trait bound error for the grpc stream sercvices
-    Server::builder()
-        .add_service(CliServiceServer::new(svc))
-        .serve(addr.parse()?)
+    builder
+        .add_service(CliServiceServer::with_interceptor(svc, auth))
+        .serve(addr)
         .await?;
 
     Ok(())
@@ -1,21 +1,45 @@
 use std::{net::SocketAddr, sync::Arc};
 
 use sol_trace::{
+    client::{ApiKeyAuth, AuthenticatedClient},
     proto::{
-        InitRequest, SubscribeRequest, SubscribeResponse, UnsubscribeRequest,
+        AddTokensRequest, AddWalletsRequest, AlertCondition, AlertsResponse, DeleteAlertRequest,
+        GetPortfolioHistoryRequest, GetPortfolioHistoryResponse, GetPositionsRequest,
+        GetPositionsResponse, GetTradeHistoryRequest, GetTradeHistoryResponse, HeartbeatRequest,
+        HeartbeatResponse, HoldingsRequest, HoldingsResponse, InitRequest, ListAlertsRequest,
+        RemoveTokensRequest, RemoveWalletsRequest, SetAlertRequest, SetAlertResponse,
+        SubscribeRequest, SubscribeResponse, TokensResponse, UnsubscribeRequest, WalletsResponse,
         cli_service_client::CliServiceClient, cli_service_server::CliServiceServer,
     },
     server::{
+        auth::ApiKeyInterceptor,
+        config::{ApiKeyConfig, AuthConfig, ChannelsConfig, SessionsConfig},
         domain::{
-            BalanceResponse, OffChainRpcClient, OnChainRpcClient, SubscriptionInput,
-            TokenAccountBalanceResponse, TokenInfo, TokenPrice, TransactionResponse, WSCResult,
-            WebSocketClient,
+            AccountInfoResponse, BalanceResponse, Context, OffChainRpcClient, OnChainRpcClient,
+            ParsedTokenAccount, ParsedTokenAccountData, RawAccount, SignatureInfo,
+            SignaturesForAddressResponse, SnapshotStore, SubscriptionInput, TokenAccount,
+            TokenAccountBalanceResponse, TokenAccountBalanceResult, TokenAccountData,
+            TokenAccountInfo, TokenAccountsByOwnerResponse, TokenAccountsByOwnerResult, TokenInfo,
+            TokenPrice, TransactionResponse, UiTokenAmount, WSCResult, WebSocketClient,
         },
-        services::HashmapTokenStore,
+        services::{HashmapSnapshotStore, HashmapTokenStore},
         states::{
-            AppState,
+            AppState, EventFilterType, TradeLedgerType,
             app_state::{OffChainRpcClientType, OnChainRpcClientType, TokenStoreType},
         },
+        utils::{
+            check_alerts,
+            constants::{
+                TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID,
+                test::{
+                    fixtures::snapshot,
+                    solana_data::{
+                        SPL_TOKEN_ACCOUNT_DATA, TOKEN_2022_ACCOUNT_DATA, TOKEN_2022_MINT, TOKEN1,
+                    },
+                },
+            },
+            gen_token_account, reap_sessions,
+        },
         wallet_service::WalletService,
     },
 };
@@ -27,27 +51,51 @@ use tokio::{
     time::{Duration, sleep},
 };
 use tokio_stream::wrappers::TcpListenerStream;
-use tonic::{Request, Status, metadata::MetadataValue, transport::Server};
+use tonic::{
+    Request, Status,
+    metadata::MetadataValue,
+    transport::{Certificate, Channel, ClientTlsConfig, Identity, Server, ServerTlsConfig},
+};
 use uuid::Uuid;
 
-pub struct MockWebSocketClient {}
+pub const UNTRACKED_TOKEN: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+// every token is priced the same
+pub const TOKEN_USD_PRICE: f64 = 3.0;
+pub const WALLET: &str = "9AhKqLR67hwapvG8SA2JFXaCshXc9nALJjpKaHZrsbkw";
+// the client's wallet is snapshotted at these unix seconds
+pub const SNAPSHOT_TIMESTAMPS: &[i64] = &[3_600, 4_000, 7_300];
+// the test server has two principals
+pub const API_KEY: &str = "desk-a-key";
+pub const OTHER_API_KEY: &str = "desk-b-key";
+
+#[derive(Default)]
+pub struct MockWebSocketClient {
+    // the logs subscriptions of these wallets fail
+    pub failing_wallets: Vec<String>,
+}
 
 #[async_trait]
 impl WebSocketClient for MockWebSocketClient {
     async fn logs_subscribe(
         &mut self,
-        _subscription_input: Arc<SubscriptionInput>,
+        subscription_input: Arc<SubscriptionInput>,
         _off_chain_rpc_client: OffChainRpcClientType,
         _token_store: TokenStoreType,
         _on_chain_rpc_client: OnChainRpcClientType,
+        _trade_ledger: TradeLedgerType,
+        _event_filter: EventFilterType,
         tx: mpsc::Sender<Result<SubscribeResponse, Status>>,
     ) -> WSCResult<u64> {
+        if self.failing_wallets.contains(&subscription_input.wallet) {
+            return Err("logsSubscribe failed".into());
+        }
+
         let sub_id: u64 = 11111;
 
         sleep(Duration::from_millis(500)).await;
-        tx.send(Ok(SubscribeResponse {
-            message: "Subscription stream data".to_string(),
-        }))
+        tx.send(Ok(SubscribeResponse::status(
+            "Subscription stream data".to_string(),
+        )))
         .await?;
 
         Ok(sub_id)
@@ -73,23 +121,126 @@ impl OffChainRpcClient for MockOffChainRpcClient {
 
     async fn get_prices(
         &self,
-        _tokens: Vec<String>,
+        tokens: Vec<String>,
     ) -> Result<
         std::collections::HashMap<String, TokenPrice>,
         Box<dyn std::error::Error + Send + Sync>,
     > {
-        Ok(std::collections::HashMap::new())
+        Ok(tokens
+            .into_iter()
+            .map(|token| {
+                let price = TokenPrice {
+                    usd_price: TOKEN_USD_PRICE,
+                    block_id: 1,
+                    decimals: 6,
+                    price_change_24h: None,
+                };
+                (token, price)
+            })
+            .collect())
     }
+
+    async fn get_price_at(
+        &self,
+        _token: String,
+        _timestamp: u64,
+    ) -> Result<Option<f64>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(None)
+    }
+}
+
+// the wallet's history, newest first
+pub const SIGNATURES: &[&str] = &["signature3", "signature2", "signature1"];
+
+#[derive(Default)]
+pub struct MockOnChainRpcClient {
+    // the signatures of the transactions fetched, in the order of the requests
+    pub fetched: std::sync::Mutex<Vec<String>>,
+    // getMultipleAccounts fails, the balances are requested one by one
+    pub batch_fails: bool,
+    // replaces SIGNATURES, paged by `before` and `limit`, every transaction is a trade
+    pub history: Vec<SignatureInfo>,
+    // the `before` and `limit` of every getSignaturesForAddress request
+    pub pages: std::sync::Mutex<Vec<(Option<String>, usize)>>,
 }
 
-pub struct MockOnChainRpcClient {}
+// `len` signatures of the WALLET, newest first, one per second before the block time 1_000_000
+pub fn history(len: usize) -> Vec<SignatureInfo> {
+    (0..len)
+        .map(|index| SignatureInfo {
+            signature: format!("history{}", index),
+            slot: 1,
+            err: None,
+            block_time: Some(1_000_000 - index as u64),
+        })
+        .collect()
+}
+
+// the WALLET buys 1 TOKEN1 for 1 SOL
+fn trade_transaction(signature: &str) -> Result<TransactionResponse, serde_json::Error> {
+    serde_json::from_value(serde_json::json!({
+        "result": {
+            "blockTime": 1_000_000,
+            "slot": 1,
+            "transaction": {
+                "signatures": [signature],
+                "message": {
+                    "accountKeys": [WALLET, "pool"],
+                    "header": { "numRequiredSignatures": 1 }
+                }
+            },
+            "meta": {
+                "err": null,
+                "fee": 5_000,
+                "preBalances": [3_000_000_000u64, 5_000_000_000u64],
+                "postBalances": [1_999_995_000u64, 6_000_000_000u64],
+                "preTokenBalances": [],
+                "postTokenBalances": [{
+                    "accountIndex": 1,
+                    "mint": TOKEN1,
+                    "owner": WALLET,
+                    "uiTokenAmount": { "decimals": 5, "amount": "100000" }
+                }]
+            }
+        },
+        "id": 1
+    }))
+}
+
+// the WALLET's token accounts of TOKEN1 and TOKEN_2022_MINT with their balances
+fn token_account_fixture(pub_key: &str) -> Option<(RawAccount, UiTokenAmount)> {
+    let (token_program, data, decimals, amount) =
+        if pub_key == gen_token_account(WALLET, TOKEN1, TOKEN_PROGRAM_ID) {
+            (TOKEN_PROGRAM_ID, SPL_TOKEN_ACCOUNT_DATA, 5, "123456789")
+        } else if pub_key == gen_token_account(WALLET, TOKEN_2022_MINT, TOKEN_2022_PROGRAM_ID) {
+            (TOKEN_2022_PROGRAM_ID, TOKEN_2022_ACCOUNT_DATA, 6, "2500000")
+        } else {
+            return None;
+        };
+
+    let account = RawAccount {
+        lamports: 2_039_280,
+        owner: token_program.to_string(),
+        data: (data.to_string(), "base64".to_string()),
+    };
+    let balance = UiTokenAmount {
+        decimals,
+        amount: amount.to_string(),
+    };
+    Some((account, balance))
+}
 
 #[async_trait]
 impl OnChainRpcClient for MockOnChainRpcClient {
     async fn get_transaction(
         &self,
-        _signature: String,
+        signature: String,
     ) -> Result<TransactionResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let is_trade = self.history.iter().any(|info| info.signature == signature);
+        self.fetched.lock().unwrap().push(signature.clone());
+        if is_trade {
+            return Ok(trade_transaction(&signature)?);
+        }
         Ok(TransactionResponse {
             result: None,
             id: 1,
@@ -98,10 +249,13 @@ impl OnChainRpcClient for MockOnChainRpcClient {
 
     async fn get_token_account_balance(
         &self,
-        _signature: String,
+        pub_key: String,
     ) -> Result<TokenAccountBalanceResponse, Box<dyn std::error::Error + Send + Sync>> {
         Ok(TokenAccountBalanceResponse {
-            result: None,
+            result: token_account_fixture(&pub_key).map(|(_, balance)| TokenAccountBalanceResult {
+                context: Context { slot: 1 },
+                value: balance,
+            }),
             id: 1,
         })
     }
@@ -115,73 +269,321 @@ impl OnChainRpcClient for MockOnChainRpcClient {
             id: 1,
         })
     }
+
+    async fn get_signatures_for_address(
+        &self,
+        _pub_key: String,
+        before: Option<String>,
+        until: Option<String>,
+        limit: usize,
+    ) -> Result<SignaturesForAddressResponse, Box<dyn std::error::Error + Send + Sync>> {
+        self.pages.lock().unwrap().push((before.clone(), limit));
+
+        if !self.history.is_empty() {
+            let start = before.map_or(0, |before| {
+                self.history
+                    .iter()
+                    .position(|info| info.signature == before)
+                    .map_or(self.history.len(), |index| index + 1)
+            });
+            let result = self
+                .history
+                .iter()
+                .skip(start)
+                .take(limit)
+                .cloned()
+                .collect();
+            return Ok(SignaturesForAddressResponse { result, id: 1 });
+        }
+
+        // one page of the signatures newer than `until`, then the history is exhausted
+        let result = match before {
+            None => SIGNATURES
+                .iter()
+                .take_while(|signature| until.as_deref() != Some(**signature))
+                .map(|signature| SignatureInfo {
+                    signature: signature.to_string(),
+                    slot: 1,
+                    err: None,
+                    block_time: Some(1),
+                })
+                .collect(),
+            Some(_) => vec![],
+        };
+
+        Ok(SignaturesForAddressResponse { result, id: 1 })
+    }
+
+    async fn get_token_accounts_by_owner(
+        &self,
+        owner: String,
+        program_id: String,
+    ) -> Result<TokenAccountsByOwnerResponse, Box<dyn std::error::Error + Send + Sync>> {
+        // a single untracked token account under the SPL Token program
+        let value = if program_id == TOKEN_PROGRAM_ID {
+            vec![TokenAccount {
+                pubkey: "token_account1".to_string(),
+                account: TokenAccountData {
+                    data: ParsedTokenAccountData {
+                        parsed: ParsedTokenAccount {
+                            info: TokenAccountInfo {
+                                mint: UNTRACKED_TOKEN.to_string(),
+                                owner,
+                                token_amount: UiTokenAmount {
+                                    decimals: 6,
+                                    amount: "1000000".to_string(),
+                                },
+                            },
+                        },
+                    },
+                },
+            }]
+        } else {
+            vec![]
+        };
+
+        Ok(TokenAccountsByOwnerResponse {
+            result: Some(TokenAccountsByOwnerResult { value }),
+            id: 1,
+        })
+    }
+
+    async fn get_account_info(
+        &self,
+        pub_key: String,
+    ) -> Result<AccountInfoResponse, Box<dyn std::error::Error + Send + Sync>> {
+        // only the Token-2022 mint has an account
+        if pub_key != TOKEN_2022_MINT {
+            return Ok(AccountInfoResponse {
+                result: None,
+                id: 1,
+            });
+        }
+
+        Ok(serde_json::from_value(serde_json::json!({
+            "result": {
+                "value": {
+                    "owner": TOKEN_2022_PROGRAM_ID,
+                    "data": ["", "base64"]
+                }
+            },
+            "id": 1
+        }))?)
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        pub_keys: Vec<String>,
+    ) -> Result<Vec<Option<RawAccount>>, Box<dyn std::error::Error + Send + Sync>> {
+        if self.batch_fails {
+            return Err("getMultipleAccounts failed".into());
+        }
+
+        Ok(pub_keys
+            .iter()
+            .map(|pub_key| token_account_fixture(pub_key).map(|(account, _)| account))
+            .collect())
+    }
 }
 
-async fn run_test_server(incoming: TcpListenerStream) -> Result<(), Box<dyn std::error::Error>> {
+#[derive(Default)]
+pub struct TestServerOptions {
+    pub tls: Option<ServerTlsConfig>,
+    // runs the session reaper
+    pub sessions: Option<SessionsConfig>,
+    // checks the price alerts at this interval
+    pub alerts_interval: Option<Duration>,
+    // the WebSocket client fails to subscribe these wallets
+    pub failing_wallets: Vec<String>,
+}
+
+async fn run_test_server(
+    incoming: TcpListenerStream,
+    options: TestServerOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
     let token_store = Arc::new(RwLock::new(HashmapTokenStore::default()));
+    let mut snapshot_store = HashmapSnapshotStore::default();
+    for (index, timestamp) in SNAPSHOT_TIMESTAMPS.iter().enumerate() {
+        snapshot_store
+            .add_snapshot(snapshot(
+                WALLET,
+                *timestamp,
+                (index as u64 + 1) * 100_000,
+                (index + 1) as f64,
+            ))
+            .await?;
+    }
+    let snapshot_store = Arc::new(RwLock::new(snapshot_store));
     let off_chain_rpc_client = Arc::new(MockOffChainRpcClient { tokens: vec![] });
-    let on_chain_rpc_client = Arc::new(MockOnChainRpcClient {});
+    let on_chain_rpc_client = Arc::new(MockOnChainRpcClient::default());
+    let failing_wallets = options.failing_wallets.clone();
     let ws_client_factory: Arc<dyn Fn() -> Box<dyn WebSocketClient + Send + Sync> + Send + Sync> =
-        Arc::new(move || Box::new(MockWebSocketClient {}));
+        Arc::new(move || {
+            Box::new(MockWebSocketClient {
+                failing_wallets: failing_wallets.clone(),
+            })
+        });
     let state = AppState::new(
         token_store,
+        snapshot_store,
         off_chain_rpc_client,
         on_chain_rpc_client,
         ws_client_factory,
     );
-    let svc = WalletService::new(Arc::new(state));
+    let state = Arc::new(state);
+    if let Some(sessions) = options.sessions {
+        tokio::spawn(reap_sessions(state.clone(), sessions));
+    }
+    if let Some(alerts_interval) = options.alerts_interval {
+        let state = state.clone();
+        tokio::spawn(async move {
+            loop {
+                sleep(alerts_interval).await;
+                check_alerts(&state).await;
+            }
+        });
+    }
+    let svc = WalletService::new(
+        state,
+        ChannelsConfig::default(),
+        options.sessions.unwrap_or_default(),
+    );
+
+    let auth = ApiKeyInterceptor::new(&AuthConfig {
+        api_keys: vec![
+            ApiKeyConfig {
+                principal: "desk-a".to_string(),
+                key: API_KEY.to_string(),
+            },
+            ApiKeyConfig {
+                principal: "desk-b".to_string(),
+                key: OTHER_API_KEY.to_string(),
+            },
+        ],
+    });
 
-    Server::builder()
-        .add_service(CliServiceServer::new(svc))
+    let mut builder = Server::builder();
+    if let Some(tls) = options.tls {
+        builder = builder.tls_config(tls)?;
+    }
+
+    builder
+        .add_service(CliServiceServer::with_interceptor(svc, auth))
         .serve_with_incoming(incoming)
         .await?;
 
     Ok(())
 }
 
-pub async fn init_server_client() -> TestClientApp {
+pub async fn spawn_test_server(options: TestServerOptions) -> SocketAddr {
     let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
     let addr = listener.local_addr().unwrap();
     let incoming = TcpListenerStream::new(listener);
 
     tokio::spawn(async move {
-        run_test_server(incoming).await.expect("Server failed");
+        run_test_server(incoming, options)
+            .await
+            .expect("Server failed");
     });
 
     sleep(Duration::from_millis(100)).await;
 
-    let client = TestClientApp::build(addr).await;
-    client
+    addr
+}
+
+pub async fn init_server_client() -> TestClientApp {
+    let addr = spawn_test_server(TestServerOptions::default()).await;
+
+    TestClientApp::build(addr).await
+}
+
+// the certificates are generated by tests/certs/generate.sh
+pub fn read_cert(name: &str) -> Vec<u8> {
+    std::fs::read(format!(
+        "{}/tests/certs/{}",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    ))
+    .unwrap()
+}
+
+// a server requiring client certificates of the test CA
+pub fn mtls_server_config() -> ServerTlsConfig {
+    ServerTlsConfig::new()
+        .identity(Identity::from_pem(
+            read_cert("server.pem"),
+            read_cert("server.key"),
+        ))
+        .client_ca_root(Certificate::from_pem(read_cert("ca.pem")))
+}
+
+pub fn client_tls_config(with_identity: bool) -> ClientTlsConfig {
+    let tls = ClientTlsConfig::new()
+        .ca_certificate(Certificate::from_pem(read_cert("ca.pem")))
+        .domain_name("localhost");
+
+    match with_identity {
+        true => tls.identity(Identity::from_pem(
+            read_cert("client.pem"),
+            read_cert("client.key"),
+        )),
+        false => tls,
+    }
 }
 
 pub struct TestClientApp {
-    pub client: CliServiceClient<tonic::transport::Channel>,
+    pub addr: SocketAddr,
+    pub client: AuthenticatedClient,
     pub client_id: Uuid,
 }
 
 impl TestClientApp {
     pub async fn build(addr: SocketAddr) -> Self {
-        let mut client: CliServiceClient<tonic::transport::Channel> =
-            CliServiceClient::connect(format!("http://{}", addr))
-                .await
-                .unwrap();
+        Self::build_with_api_key(addr, Some(API_KEY)).await.unwrap()
+    }
+
+    pub async fn build_with_api_key(
+        addr: SocketAddr,
+        api_key: Option<&str>,
+    ) -> Result<Self, Status> {
+        let channel = Channel::from_shared(format!("http://{}", addr))
+            .unwrap()
+            .connect()
+            .await
+            .unwrap();
+
+        Self::build_with_channel(addr, channel, api_key).await
+    }
+
+    pub async fn build_with_channel(
+        addr: SocketAddr,
+        channel: Channel,
+        api_key: Option<&str>,
+    ) -> Result<Self, Status> {
+        let mut client =
+            CliServiceClient::with_interceptor(channel, ApiKeyAuth::new(api_key).unwrap());
 
         //TODO: the Pubkey validation is not mocked (yet?)
         let init_request = InitRequest {
-            wallet: "9AhKqLR67hwapvG8SA2JFXaCshXc9nALJjpKaHZrsbkw".to_owned(),
+            wallet: WALLET.to_owned(),
             tokens: vec!["DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263".to_owned()],
+            wallets: vec![],
         };
-        let init_response = client.init(init_request).await.unwrap().into_inner();
+        let init_response = client.init(init_request).await?.into_inner();
 
         let client_id = Uuid::parse_str(init_response.client_id.as_str()).unwrap();
 
-        Self { client, client_id }
+        Ok(Self {
+            addr,
+            client,
+            client_id,
+        })
     }
 
     pub async fn sub(
         &mut self,
     ) -> Result<tonic::Streaming<SubscribeResponse>, Box<dyn std::error::Error>> {
-        let mut subscribe_request = Request::new(SubscribeRequest {});
+        let mut subscribe_request = Request::new(SubscribeRequest::default());
         subscribe_request.metadata_mut().insert(
             "client-id",
             MetadataValue::try_from(self.client_id.clone().to_string())?,
@@ -204,4 +606,223 @@ impl TestClientApp {
 
         Ok(())
     }
+
+    pub async fn positions(&mut self) -> Result<GetPositionsResponse, Box<dyn std::error::Error>> {
+        let mut positions_request = Request::new(GetPositionsRequest {});
+        positions_request.metadata_mut().insert(
+            "client-id",
+            MetadataValue::try_from(self.client_id.to_string())?,
+        );
+
+        let response = self
+            .client
+            .get_positions(positions_request)
+            .await?
+            .into_inner();
+
+        Ok(response)
+    }
+
+    pub async fn heartbeat(&mut self) -> Result<HeartbeatResponse, Box<dyn std::error::Error>> {
+        let mut heartbeat_request = Request::new(HeartbeatRequest {});
+        heartbeat_request.metadata_mut().insert(
+            "client-id",
+            MetadataValue::try_from(self.client_id.to_string())?,
+        );
+
+        let response = self.client.heartbeat(heartbeat_request).await?.into_inner();
+
+        Ok(response)
+    }
+
+    pub async fn trade_history(
+        &mut self,
+    ) -> Result<tonic::Streaming<GetTradeHistoryResponse>, Box<dyn std::error::Error>> {
+        let mut history_request = Request::new(GetTradeHistoryRequest {
+            limit: 10,
+            before: None,
+            since: None,
+            wallet: None,
+        });
+        history_request.metadata_mut().insert(
+            "client-id",
+            MetadataValue::try_from(self.client_id.to_string())?,
+        );
+
+        let stream = self
+            .client
+            .get_trade_history(history_request)
+            .await?
+            .into_inner();
+
+        Ok(stream)
+    }
+
+    pub async fn add_wallets(
+        &mut self,
+        wallets: Vec<String>,
+    ) -> Result<WalletsResponse, Box<dyn std::error::Error>> {
+        let mut add_request = Request::new(AddWalletsRequest { wallets });
+        add_request.metadata_mut().insert(
+            "client-id",
+            MetadataValue::try_from(self.client_id.to_string())?,
+        );
+
+        let response = self.client.add_wallets(add_request).await?.into_inner();
+
+        Ok(response)
+    }
+
+    pub async fn remove_wallets(
+        &mut self,
+        wallets: Vec<String>,
+    ) -> Result<WalletsResponse, Box<dyn std::error::Error>> {
+        let mut remove_request = Request::new(RemoveWalletsRequest { wallets });
+        remove_request.metadata_mut().insert(
+            "client-id",
+            MetadataValue::try_from(self.client_id.to_string())?,
+        );
+
+        let response = self
+            .client
+            .remove_wallets(remove_request)
+            .await?
+            .into_inner();
+
+        Ok(response)
+    }
+
+    pub async fn add_tokens(
+        &mut self,
+        tokens: Vec<String>,
+    ) -> Result<TokensResponse, Box<dyn std::error::Error>> {
+        let mut add_request = Request::new(AddTokensRequest { tokens });
+        add_request.metadata_mut().insert(
+            "client-id",
+            MetadataValue::try_from(self.client_id.to_string())?,
+        );
+
+        let response = self.client.add_tokens(add_request).await?.into_inner();
+
+        Ok(response)
+    }
+
+    pub async fn portfolio_history(
+        &mut self,
+        from: Option<i64>,
+        to: Option<i64>,
+        resolution: u64,
+    ) -> Result<GetPortfolioHistoryResponse, Box<dyn std::error::Error>> {
+        let mut history_request = Request::new(GetPortfolioHistoryRequest {
+            from,
+            to,
+            resolution,
+            wallet: None,
+        });
+        history_request.metadata_mut().insert(
+            "client-id",
+            MetadataValue::try_from(self.client_id.to_string())?,
+        );
+
+        let response = self
+            .client
+            .get_portfolio_history(history_request)
+            .await?
+            .into_inner();
+
+        Ok(response)
+    }
+
+    pub async fn set_alert(
+        &mut self,
+        mint: String,
+        condition: AlertCondition,
+        value: f64,
+    ) -> Result<SetAlertResponse, Box<dyn std::error::Error>> {
+        let mut set_alert_request = SetAlertRequest {
+            mint,
+            value,
+            ..Default::default()
+        };
+        set_alert_request.set_condition(condition);
+
+        let mut set_alert_request = Request::new(set_alert_request);
+        set_alert_request.metadata_mut().insert(
+            "client-id",
+            MetadataValue::try_from(self.client_id.to_string())?,
+        );
+
+        let response = self.client.set_alert(set_alert_request).await?.into_inner();
+
+        Ok(response)
+    }
+
+    pub async fn list_alerts(&mut self) -> Result<AlertsResponse, Box<dyn std::error::Error>> {
+        let mut list_alerts_request = Request::new(ListAlertsRequest {});
+        list_alerts_request.metadata_mut().insert(
+            "client-id",
+            MetadataValue::try_from(self.client_id.to_string())?,
+        );
+
+        let response = self
+            .client
+            .list_alerts(list_alerts_request)
+            .await?
+            .into_inner();
+
+        Ok(response)
+    }
+
+    pub async fn delete_alert(
+        &mut self,
+        id: u64,
+    ) -> Result<AlertsResponse, Box<dyn std::error::Error>> {
+        let mut delete_alert_request = Request::new(DeleteAlertRequest { id });
+        delete_alert_request.metadata_mut().insert(
+            "client-id",
+            MetadataValue::try_from(self.client_id.to_string())?,
+        );
+
+        let response = self
+            .client
+            .delete_alert(delete_alert_request)
+            .await?
+            .into_inner();
+
+        Ok(response)
+    }
+
+    pub async fn remove_tokens(
+        &mut self,
+        tokens: Vec<String>,
+    ) -> Result<TokensResponse, Box<dyn std::error::Error>> {
+        let mut remove_request = Request::new(RemoveTokensRequest { tokens });
+        remove_request.metadata_mut().insert(
+            "client-id",
+            MetadataValue::try_from(self.client_id.to_string())?,
+        );
+
+        let response = self
+            .client
+            .remove_tokens(remove_request)
+            .await?
+            .into_inner();
+
+        Ok(response)
+    }
+
+    pub async fn holdings(
+        &mut self,
+        all: bool,
+    ) -> Result<HoldingsResponse, Box<dyn std::error::Error>> {
+        let mut holdings_request = Request::new(HoldingsRequest { all });
+        holdings_request.metadata_mut().insert(
+            "client-id",
+            MetadataValue::try_from(self.client_id.to_string())?,
+        );
+
+        let response = self.client.holdings(holdings_request).await?.into_inner();
+
+        Ok(response)
+    }
 }
@@ -0,0 +1,127 @@
+use std::{collections::HashSet, sync::Arc};
+
+use sol_trace::{
+    proto::GetTradeHistoryRequest,
+    server::{
+        domain::{SignatureInfo, SubscriptionInput},
+        services::HashmapTokenStore,
+        states::TradeLedger,
+        utils::{constants::MAX_HISTORY_LIMIT, stream_trade_history},
+    },
+};
+use tokio::sync::{RwLock, mpsc};
+
+use crate::helpers::{MockOffChainRpcClient, MockOnChainRpcClient, WALLET, history};
+
+// the signatures of the streamed trades and the mock with the requests made
+async fn trade_history(
+    history: Vec<SignatureInfo>,
+    request: GetTradeHistoryRequest,
+) -> (Vec<String>, Arc<MockOnChainRpcClient>) {
+    let on_chain_rpc_client = Arc::new(MockOnChainRpcClient {
+        history,
+        ..Default::default()
+    });
+    let (tx, mut rx) = mpsc::channel(MAX_HISTORY_LIMIT);
+
+    stream_trade_history(
+        request,
+        Arc::new(SubscriptionInput::new(WALLET.to_string(), HashSet::new())),
+        Arc::new(MockOffChainRpcClient { tokens: vec![] }),
+        Arc::new(RwLock::new(HashmapTokenStore::default())),
+        on_chain_rpc_client.clone(),
+        Arc::new(RwLock::new(TradeLedger::default())),
+        tx,
+    )
+    .await
+    .unwrap();
+
+    let mut trades = vec![];
+    while let Some(response) = rx.recv().await {
+        trades.push(response.unwrap().trade.unwrap().signature);
+    }
+
+    (trades, on_chain_rpc_client)
+}
+
+fn signatures(range: std::ops::Range<usize>) -> Vec<String> {
+    range.map(|index| format!("history{}", index)).collect()
+}
+
+fn pages(on_chain_rpc_client: &MockOnChainRpcClient) -> Vec<(Option<String>, usize)> {
+    on_chain_rpc_client.pages.lock().unwrap().clone()
+}
+
+#[tokio::test]
+async fn should_page_trade_history_up_to_the_limit() {
+    let (trades, on_chain_rpc_client) = trade_history(
+        history(2_500),
+        GetTradeHistoryRequest {
+            limit: 1_500,
+            ..Default::default()
+        },
+    )
+    .await;
+
+    assert_eq!(
+        vec![(None, 1_000), (Some("history999".to_string()), 500)],
+        pages(&on_chain_rpc_client)
+    );
+    assert_eq!(signatures(0..1_500), trades);
+    assert_eq!(trades, *on_chain_rpc_client.fetched.lock().unwrap());
+}
+
+#[tokio::test]
+async fn should_cap_trade_history_limit() {
+    let (trades, on_chain_rpc_client) = trade_history(
+        history(2_500),
+        GetTradeHistoryRequest {
+            limit: u32::MAX,
+            ..Default::default()
+        },
+    )
+    .await;
+
+    assert_eq!(
+        vec![(None, 1_000), (Some("history999".to_string()), 1_000)],
+        pages(&on_chain_rpc_client)
+    );
+    assert_eq!(signatures(0..MAX_HISTORY_LIMIT), trades);
+}
+
+#[tokio::test]
+async fn should_page_from_before_until_since() {
+    let (trades, on_chain_rpc_client) = trade_history(
+        history(2_500),
+        GetTradeHistoryRequest {
+            before: Some("history10".to_string()),
+            // the block time of history20
+            since: Some(1_000_000 - 20),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    assert_eq!(
+        vec![(Some("history10".to_string()), 100)],
+        pages(&on_chain_rpc_client)
+    );
+    // history21 is older than `since`, it is not fetched
+    assert_eq!(signatures(11..21), trades);
+    assert_eq!(trades, *on_chain_rpc_client.fetched.lock().unwrap());
+}
+
+#[tokio::test]
+async fn should_skip_failed_transactions() {
+    let mut history = history(5);
+    history[1].err = Some(serde_json::json!({ "InstructionError": [0, "Custom"] }));
+
+    let (trades, on_chain_rpc_client) =
+        trade_history(history, GetTradeHistoryRequest::default()).await;
+
+    // the history is exhausted by the first page
+    assert_eq!(vec![(None, 100)], pages(&on_chain_rpc_client));
+    let expected = vec!["history0", "history2", "history3", "history4"];
+    assert_eq!(expected, trades);
+    assert_eq!(expected, *on_chain_rpc_client.fetched.lock().unwrap());
+}
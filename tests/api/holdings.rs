@@ -0,0 +1,95 @@
+use std::{collections::HashMap, sync::Arc};
+
+use sol_trace::{
+    proto::Holding,
+    server::{
+        domain::{TokenInfo, TokenStore},
+        services::HashmapTokenStore,
+        states::WalletState,
+        utils::{
+            constants::{
+                TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID,
+                test::solana_data::{TOKEN_2022_MINT, TOKEN1},
+            },
+            query_holdings,
+        },
+    },
+};
+use tokio::sync::RwLock;
+
+use crate::helpers::{MockOffChainRpcClient, MockOnChainRpcClient, WALLET};
+
+// the tracked TOKEN1 and TOKEN_2022_MINT balances of the WALLET, sorted by mint
+async fn tracked_holdings(batch_fails: bool) -> Vec<Holding> {
+    let mut token_store = HashmapTokenStore::default();
+    for (id, symbol, decimals, token_program) in [
+        (TOKEN1, "Bonk", 5, TOKEN_PROGRAM_ID),
+        (TOKEN_2022_MINT, "PYUSD", 6, TOKEN_2022_PROGRAM_ID),
+    ] {
+        token_store
+            .add_token(TokenInfo {
+                id: id.to_string(),
+                name: symbol.to_string(),
+                symbol: symbol.to_string(),
+                decimals,
+                token_program: Some(token_program.to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+    }
+    let wallet_state = WalletState::build(
+        WALLET.to_string(),
+        &HashMap::from([
+            (TOKEN1.to_string(), TOKEN_PROGRAM_ID.to_string()),
+            (
+                TOKEN_2022_MINT.to_string(),
+                TOKEN_2022_PROGRAM_ID.to_string(),
+            ),
+        ]),
+    );
+
+    let mut holdings = query_holdings(
+        &[wallet_state],
+        false,
+        Arc::new(RwLock::new(token_store)),
+        Arc::new(MockOnChainRpcClient {
+            batch_fails,
+            ..Default::default()
+        }),
+        Arc::new(MockOffChainRpcClient { tokens: vec![] }),
+    )
+    .await
+    .unwrap()
+    .holdings;
+    holdings.sort_by(|a, b| a.address.cmp(&b.address));
+
+    holdings
+}
+
+fn balances(holdings: &[Holding]) -> Vec<(&str, &str)> {
+    holdings
+        .iter()
+        .map(|holding| (holding.address.as_str(), holding.balance.as_str()))
+        .collect()
+}
+
+#[tokio::test]
+async fn should_decode_batched_token_accounts() {
+    let holdings = tracked_holdings(false).await;
+
+    assert_eq!(
+        vec![(TOKEN_2022_MINT, "2.5"), (TOKEN1, "1234.56789")],
+        balances(&holdings)
+    );
+}
+
+#[tokio::test]
+async fn should_fall_back_to_single_token_balances() {
+    let holdings = tracked_holdings(true).await;
+
+    assert_eq!(
+        vec![(TOKEN_2022_MINT, "2.5"), (TOKEN1, "1234.56789")],
+        balances(&holdings)
+    );
+}
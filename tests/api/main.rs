@@ -1,3 +1,7 @@
 mod helpers;
 
+mod history;
+mod holdings;
+mod solana_ws_client;
+mod tokens;
 mod wallet_service;
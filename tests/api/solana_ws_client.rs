@@ -0,0 +1,184 @@
+use std::{collections::HashSet, sync::Arc};
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::json;
+use sol_trace::{
+    proto::SubscribeResponse,
+    server::{
+        config::Commitment,
+        domain::{SubscriptionInput, WebSocketClient},
+        services::{EndpointPool, HashmapTokenStore, SolanaWebSocketClient, WebSocketSettings},
+        states::{EventFilter, TradeLedger},
+    },
+};
+use tokio::{
+    net::TcpListener,
+    sync::{RwLock, mpsc},
+    time::{Duration, sleep, timeout},
+};
+use tonic::Status;
+use tungstenite::Message;
+
+use crate::helpers::{MockOffChainRpcClient, MockOnChainRpcClient, SIGNATURES, WALLET};
+
+// a Solana node that notifies the oldest signature, then drops the first connection
+async fn spawn_ws_node() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+
+    tokio::spawn(async move {
+        let mut connections = vec![];
+        for remote_id in 1.. {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws_stream = tokio_tungstenite::accept_async(stream).await.unwrap();
+
+            let Some(Ok(Message::Text(req))) = ws_stream.next().await else {
+                continue;
+            };
+            let req: serde_json::Value = serde_json::from_str(&req).unwrap();
+            let resp = json!({ "jsonrpc": "2.0", "result": remote_id, "id": req["id"] });
+            ws_stream
+                .send(Message::Text(resp.to_string()))
+                .await
+                .unwrap();
+
+            if remote_id == 1 {
+                let notification = json!({
+                    "jsonrpc": "2.0",
+                    "method": "logsNotification",
+                    "params": {
+                        "result": {
+                            "context": { "slot": 1 },
+                            "value": { "signature": SIGNATURES[2], "err": null }
+                        },
+                        "subscription": remote_id
+                    }
+                });
+                ws_stream
+                    .send(Message::Text(notification.to_string()))
+                    .await
+                    .unwrap();
+            } else {
+                // the reconnected subscription stays up
+                connections.push(ws_stream);
+            }
+        }
+    });
+
+    url
+}
+
+// accepts every connection, completes the handshake if `handshake`, and never answers
+async fn spawn_hung_ws_node(handshake: bool) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+
+    tokio::spawn(async move {
+        let mut connections = vec![];
+        let mut ws_connections = vec![];
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            if handshake {
+                ws_connections.push(tokio_tungstenite::accept_async(stream).await.unwrap());
+            } else {
+                connections.push(stream);
+            }
+        }
+    });
+
+    url
+}
+
+fn settings() -> WebSocketSettings {
+    WebSocketSettings {
+        commitment: Commitment::Confirmed,
+        ping_interval: Duration::from_secs(60),
+        writer_channel_size: 16,
+        connect_timeout: Duration::from_millis(500),
+        request_timeout: Duration::from_millis(500),
+    }
+}
+
+async fn next_message(
+    rx: &mut mpsc::Receiver<Result<SubscribeResponse, Status>>,
+) -> SubscribeResponse {
+    timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .expect("No subscription message received")
+        .unwrap()
+        .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn should_fill_gap_after_reconnect() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let url = spawn_ws_node().await;
+    let on_chain_rpc_client = Arc::new(MockOnChainRpcClient::default());
+    let (tx, mut rx) = mpsc::channel(16);
+
+    let mut ws_client =
+        SolanaWebSocketClient::new(Arc::new(EndpointPool::new(vec![url])), settings());
+    ws_client
+        .logs_subscribe(
+            Arc::new(SubscriptionInput::new(WALLET.to_string(), HashSet::new())),
+            Arc::new(MockOffChainRpcClient { tokens: vec![] }),
+            Arc::new(RwLock::new(HashmapTokenStore::default())),
+            on_chain_rpc_client.clone(),
+            Arc::new(RwLock::new(TradeLedger::default())),
+            Arc::new(RwLock::new(EventFilter::default())),
+            tx,
+        )
+        .await?;
+
+    assert_eq!(
+        "Connection lost, reconnecting...",
+        next_message(&mut rx).await.message
+    );
+    assert_eq!(
+        "Reconnected, subscription resumed",
+        next_message(&mut rx).await.message
+    );
+
+    // the signatures missed while disconnected are replayed oldest first
+    let expected: Vec<String> = SIGNATURES.iter().rev().map(|s| s.to_string()).collect();
+    for _ in 0..50 {
+        if on_chain_rpc_client.fetched.lock().unwrap().len() >= expected.len() {
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(expected, *on_chain_rpc_client.fetched.lock().unwrap());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn should_fail_over_hung_endpoints() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let no_handshake = spawn_hung_ws_node(false).await;
+    let no_subscription = spawn_hung_ws_node(true).await;
+    let url = spawn_ws_node().await;
+    let pool = Arc::new(EndpointPool::new(vec![
+        no_handshake.clone(),
+        no_subscription.clone(),
+        url.clone(),
+    ]));
+    let (tx, _rx) = mpsc::channel(16);
+
+    let mut ws_client = SolanaWebSocketClient::new(pool.clone(), settings());
+    timeout(
+        Duration::from_secs(5),
+        ws_client.logs_subscribe(
+            Arc::new(SubscriptionInput::new(WALLET.to_string(), HashSet::new())),
+            Arc::new(MockOffChainRpcClient { tokens: vec![] }),
+            Arc::new(RwLock::new(HashmapTokenStore::default())),
+            Arc::new(MockOnChainRpcClient::default()),
+            Arc::new(RwLock::new(TradeLedger::default())),
+            Arc::new(RwLock::new(EventFilter::default())),
+            tx,
+        ),
+    )
+    .await??;
+
+    assert_eq!(url, pool.ranked()[0]);
+
+    Ok(())
+}
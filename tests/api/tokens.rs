@@ -0,0 +1,49 @@
+use std::sync::Arc;
+
+use sol_trace::server::{
+    domain::{TokenInfo, TokenStore},
+    services::SqliteTokenStore,
+    utils::{
+        constants::{TOKEN_2022_PROGRAM_ID, test::solana_data::TOKEN_2022_MINT},
+        get_token_programs, store_tokens,
+    },
+};
+use tokio::sync::RwLock;
+
+use crate::helpers::{MockOffChainRpcClient, MockOnChainRpcClient};
+
+#[tokio::test]
+async fn should_complete_token_stored_without_program() -> Result<(), Box<dyn std::error::Error>> {
+    let mut token_store = SqliteTokenStore::open_in_memory()?;
+    // a row from before the token program column
+    token_store
+        .add_token(TokenInfo {
+            id: TOKEN_2022_MINT.to_string(),
+            name: "PayPal USD".to_string(),
+            symbol: "PYUSD".to_string(),
+            decimals: 6,
+            ..Default::default()
+        })
+        .await?;
+    let token_store = Arc::new(RwLock::new(token_store));
+    let token_mints = vec![TOKEN_2022_MINT.to_string()];
+
+    store_tokens(
+        &token_mints,
+        Arc::new(MockOffChainRpcClient { tokens: vec![] }),
+        Arc::new(MockOnChainRpcClient::default()),
+        token_store.clone(),
+    )
+    .await?;
+
+    let token = token_store.read().await.get_token(TOKEN_2022_MINT).await?;
+    assert_eq!(Some(TOKEN_2022_PROGRAM_ID.to_string()), token.token_program);
+    assert_eq!(
+        Some(&TOKEN_2022_PROGRAM_ID.to_string()),
+        get_token_programs(&token_mints, token_store)
+            .await
+            .get(TOKEN_2022_MINT)
+    );
+
+    Ok(())
+}
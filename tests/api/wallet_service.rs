@@ -1,8 +1,16 @@
-use tokio::time::{Duration, sleep};
-use tonic::Status;
+use sol_trace::{
+    proto::{AlertCondition, StatusEvent, subscribe_response::Event},
+    server::config::SessionsConfig,
+};
+use tokio::time::{Duration, sleep, timeout};
+use tonic::{Status, transport::Channel};
 use uuid::Uuid;
 
-use crate::helpers::init_server_client;
+use crate::helpers::{
+    API_KEY, OTHER_API_KEY, SNAPSHOT_TIMESTAMPS, TOKEN_USD_PRICE, TestClientApp, TestServerOptions,
+    UNTRACKED_TOKEN, WALLET, client_tls_config, init_server_client, mtls_server_config,
+    spawn_test_server,
+};
 
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn should_run_subscription_unsubscription() -> Result<(), Box<dyn std::error::Error>> {
@@ -14,6 +22,10 @@ async fn should_run_subscription_unsubscription() -> Result<(), Box<dyn std::err
 
     if let Some(msg) = stream.message().await? {
         assert_eq!("Subscription stream data", msg.message);
+        assert!(matches!(
+            msg.event,
+            Some(Event::Status(StatusEvent { message })) if message == "Subscription stream data"
+        ));
     } else {
         panic!("No subscription message received");
     }
@@ -23,6 +35,44 @@ async fn should_run_subscription_unsubscription() -> Result<(), Box<dyn std::err
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn should_not_open_stream_without_subscribed_wallet() -> Result<(), Box<dyn std::error::Error>>
+{
+    let addr = spawn_test_server(TestServerOptions {
+        failing_wallets: vec![WALLET.to_string()],
+        ..Default::default()
+    })
+    .await;
+    let mut client = TestClientApp::build(addr).await;
+
+    // no subscription is kept, so the next attempt fails the same way
+    for _ in 0..2 {
+        let binding = client.sub().await.unwrap_err();
+        let status = binding.downcast_ref::<Status>().unwrap();
+        assert_eq!(tonic::Code::Unavailable, status.code());
+    }
+
+    // a wallet that subscribes opens the stream, the failed one is reported on it
+    let new_wallet = "5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1".to_string();
+    client.add_wallets(vec![new_wallet]).await?;
+    let mut stream = client.sub().await?;
+
+    let mut messages = vec![
+        stream.message().await?.unwrap().message,
+        stream.message().await?.unwrap().message,
+    ];
+    messages.sort();
+    assert_eq!(
+        vec![
+            format!("Failed to subscribe wallets: {}", WALLET),
+            "Subscription stream data".to_string(),
+        ],
+        messages
+    );
+
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn should_client_not_found_for_sub_with_wrong_client_id()
 -> Result<(), Box<dyn std::error::Error>> {
@@ -61,6 +111,387 @@ async fn should_client_not_found_for_unsub_with_wrong_client_id()
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn should_reject_init_without_api_key() -> Result<(), Box<dyn std::error::Error>> {
+    let client = init_server_client().await;
+
+    for api_key in [None, Some("unknown-key")] {
+        let result = TestClientApp::build_with_api_key(client.addr, api_key).await;
+
+        let Err(status) = result else {
+            panic!("init without a valid API key succeeded");
+        };
+        assert_eq!(tonic::Code::Unauthenticated, status.code());
+    }
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn should_reject_client_of_other_principal() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = init_server_client().await;
+    let mut other_client = TestClientApp::build_with_api_key(client.addr, Some(OTHER_API_KEY))
+        .await
+        .unwrap();
+
+    // the other desk uses the session of the first one
+    other_client.client_id = client.client_id;
+
+    for result in [
+        other_client.positions().await.map(|_| ()),
+        other_client.unsub().await,
+    ] {
+        let binding = result.unwrap_err();
+        let status = binding.downcast_ref::<Status>().unwrap();
+        assert_eq!(tonic::Code::PermissionDenied, status.code());
+    }
+
+    // the owner is not affected
+    client.positions().await.unwrap();
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn should_init_over_mutual_tls() -> Result<(), Box<dyn std::error::Error>> {
+    let addr = spawn_test_server(TestServerOptions {
+        tls: Some(mtls_server_config()),
+        ..Default::default()
+    })
+    .await;
+
+    let channel = Channel::from_shared(format!("https://{}", addr))?
+        .tls_config(client_tls_config(true))?
+        .connect()
+        .await?;
+    let mut client = TestClientApp::build_with_channel(addr, channel, Some(API_KEY)).await?;
+
+    client.positions().await.unwrap();
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn should_reject_tls_client_without_certificate() -> Result<(), Box<dyn std::error::Error>> {
+    let addr = spawn_test_server(TestServerOptions {
+        tls: Some(mtls_server_config()),
+        ..Default::default()
+    })
+    .await;
+
+    // the handshake fails either on connect or on the first request
+    let result = match Channel::from_shared(format!("https://{}", addr))?
+        .tls_config(client_tls_config(false))?
+        .connect()
+        .await
+    {
+        Ok(channel) => TestClientApp::build_with_channel(addr, channel, Some(API_KEY))
+            .await
+            .map(|_| ())
+            .map_err(|status| status.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    assert!(result.is_err());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn should_expire_idle_session() -> Result<(), Box<dyn std::error::Error>> {
+    let addr = spawn_test_server(TestServerOptions {
+        sessions: Some(SessionsConfig {
+            ttl_secs: 1,
+            reaper_interval_secs: 1,
+        }),
+        ..Default::default()
+    })
+    .await;
+    let mut client = TestClientApp::build(addr).await;
+
+    sleep(Duration::from_millis(2500)).await;
+
+    let binding = client.heartbeat().await.unwrap_err();
+    let status = binding.downcast_ref::<Status>().unwrap();
+    assert_eq!("Client not found", status.message());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn should_keep_session_alive_with_heartbeats() -> Result<(), Box<dyn std::error::Error>> {
+    let addr = spawn_test_server(TestServerOptions {
+        sessions: Some(SessionsConfig {
+            ttl_secs: 2,
+            reaper_interval_secs: 1,
+        }),
+        ..Default::default()
+    })
+    .await;
+    let mut client = TestClientApp::build(addr).await;
+
+    for _ in 0..6 {
+        sleep(Duration::from_millis(500)).await;
+        assert_eq!(2, client.heartbeat().await?.ttl_secs);
+    }
+
+    client.positions().await?;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn should_close_dropped_subscription_stream() -> Result<(), Box<dyn std::error::Error>> {
+    let addr = spawn_test_server(TestServerOptions {
+        sessions: Some(SessionsConfig {
+            reaper_interval_secs: 1,
+            ..Default::default()
+        }),
+        ..Default::default()
+    })
+    .await;
+    let mut client = TestClientApp::build(addr).await;
+
+    // the client goes away without unsubscribing
+    drop(client.sub().await?);
+
+    sleep(Duration::from_millis(1500)).await;
+
+    // the reaper has unsubscribed, the session is kept
+    client.sub().await?;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn should_finish_trade_history_stream() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = init_server_client().await;
+
+    let mut stream = client.trade_history().await?;
+
+    // the mocked transaction is not a trade, so the stream closes empty
+    assert!(stream.message().await?.is_none());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn should_client_not_found_for_trade_history_with_wrong_client_id()
+-> Result<(), Box<dyn std::error::Error>> {
+    let mut client = init_server_client().await;
+
+    client.client_id = Uuid::new_v4();
+
+    let result = client.trade_history().await;
+
+    assert!(result.is_err());
+    let binding = result.unwrap_err();
+    let your_error = binding.downcast_ref::<Status>();
+    assert!(your_error.is_some());
+    assert_eq!("Client not found", your_error.unwrap().message());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn should_return_no_positions_without_trades() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = init_server_client().await;
+
+    let response = client.positions().await?;
+
+    assert!(response.positions.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn should_add_and_remove_wallets() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = init_server_client().await;
+    let wallet = "9AhKqLR67hwapvG8SA2JFXaCshXc9nALJjpKaHZrsbkw".to_string();
+    let new_wallet = "5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1".to_string();
+
+    let response = client.add_wallets(vec![new_wallet.clone()]).await?;
+    assert_eq!(2, response.wallets.len());
+    assert!(response.wallets.contains(&new_wallet));
+
+    let response = client.remove_wallets(vec![wallet]).await?;
+    assert_eq!(vec![new_wallet.clone()], response.wallets);
+
+    // the last wallet of the client can not be removed
+    let result = client.remove_wallets(vec![new_wallet]).await;
+
+    assert!(result.is_err());
+    let binding = result.unwrap_err();
+    let your_error = binding.downcast_ref::<Status>();
+    assert!(your_error.is_some());
+    assert_eq!("Missing wallets", your_error.unwrap().message());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn should_remove_and_add_tokens() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = init_server_client().await;
+    let token = "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263".to_string();
+    let wsol = "So11111111111111111111111111111111111111112".to_string();
+
+    // WSOL is kept even if it is requested to be removed
+    let response = client
+        .remove_tokens(vec![token.clone(), wsol.clone()])
+        .await?;
+    assert_eq!(vec![wsol.clone()], response.tokens);
+
+    let response = client.add_tokens(vec![token.clone()]).await?;
+    assert_eq!(vec![token, wsol], response.tokens);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn should_reject_invalid_token() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = init_server_client().await;
+
+    let result = client.add_tokens(vec!["invalid_token".to_string()]).await;
+
+    assert!(result.is_err());
+    let binding = result.unwrap_err();
+    let your_error = binding.downcast_ref::<Status>();
+    assert!(your_error.is_some());
+    assert_eq!(
+        "Invalid token: invalid_token",
+        your_error.unwrap().message()
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn should_report_untracked_tokens_in_all_holdings() -> Result<(), Box<dyn std::error::Error>>
+{
+    let mut client = init_server_client().await;
+
+    let response = client.holdings(false).await?;
+    assert!(response.holdings.is_empty());
+
+    let response = client.holdings(true).await?;
+    assert_eq!(1, response.holdings.len());
+    assert_eq!(UNTRACKED_TOKEN, response.holdings[0].address);
+    assert_eq!("1", response.holdings[0].balance);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn should_set_list_and_delete_alerts() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = init_server_client().await;
+    let token = "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263".to_string();
+
+    let alert = client
+        .set_alert(token.clone(), AlertCondition::PriceAbove, 2.5)
+        .await?
+        .alert
+        .unwrap();
+    assert_eq!(token, alert.mint);
+    assert_eq!(AlertCondition::PriceAbove, alert.condition());
+
+    let response = client.list_alerts().await?;
+    assert_eq!(vec![alert.clone()], response.alerts);
+
+    let response = client.delete_alert(alert.id).await?;
+    assert!(response.alerts.is_empty());
+
+    // already deleted
+    let result = client.delete_alert(alert.id).await;
+    let binding = result.unwrap_err();
+    assert_eq!(
+        "Alert not found",
+        binding.downcast_ref::<Status>().unwrap().message()
+    );
+
+    // the value must be positive
+    let result = client
+        .set_alert(token, AlertCondition::PriceBelow, 0.0)
+        .await;
+    let binding = result.unwrap_err();
+    assert_eq!(
+        "Invalid alert value",
+        binding.downcast_ref::<Status>().unwrap().message()
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn should_push_triggered_alert_on_subscription() -> Result<(), Box<dyn std::error::Error>> {
+    let addr = spawn_test_server(TestServerOptions {
+        alerts_interval: Some(Duration::from_millis(100)),
+        ..Default::default()
+    })
+    .await;
+    let mut client = TestClientApp::build(addr).await;
+    let token = "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263".to_string();
+
+    let mut stream = client.sub().await?;
+    let alert = client
+        .set_alert(token, AlertCondition::PriceAbove, TOKEN_USD_PRICE - 0.5)
+        .await?
+        .alert
+        .unwrap();
+
+    // the subscription status comes first
+    loop {
+        let msg = timeout(Duration::from_secs(5), stream.message())
+            .await?
+            .unwrap()
+            .unwrap();
+        if let Some(Event::Alert(alert_event)) = msg.event {
+            assert_eq!(Some(alert.clone()), alert_event.alert);
+            assert_eq!(TOKEN_USD_PRICE, alert_event.usd_price);
+            break;
+        }
+    }
+
+    // a one-shot alert is removed once triggered
+    let response = client.list_alerts().await?;
+    assert!(response.alerts.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn should_return_portfolio_history() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = init_server_client().await;
+
+    // hourly buckets, the latest snapshot of the bucket is kept
+    let response = client
+        .portfolio_history(Some(0), Some(10_000), 3_600)
+        .await?;
+    assert_eq!(
+        vec![3_600, 7_200],
+        response
+            .points
+            .iter()
+            .map(|point| point.timestamp)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(2.0, response.points[0].usd_value);
+    assert_eq!("2", response.points[0].holdings[0].balance);
+    assert_eq!(3.0, response.points[1].usd_value);
+
+    let response = client
+        .portfolio_history(Some(SNAPSHOT_TIMESTAMPS[2]), None, 0)
+        .await?;
+    assert_eq!(1, response.points.len());
+
+    let result = client.portfolio_history(Some(200), Some(100), 0).await;
+    let binding = result.unwrap_err();
+    assert_eq!(
+        "Invalid time range",
+        binding.downcast_ref::<Status>().unwrap().message()
+    );
+
+    Ok(())
+}
+
 /*
 Other test cases:
     Sub -> Sub is handled in the product client with CancellationToken
@@ -0,0 +1,22 @@
+#!/bin/sh
+# regenerates the test CA, the localhost server certificate and the client certificate
+set -e
+cd "$(dirname "$0")"
+
+openssl genpkey -algorithm EC -pkeyopt ec_paramgen_curve:P-256 -out ca.key
+openssl req -x509 -new -key ca.key -days 36500 -subj "/CN=sol-trace test CA" \
+    -addext "basicConstraints=critical,CA:TRUE" -addext "keyUsage=critical,keyCertSign" -out ca.pem
+
+openssl genpkey -algorithm EC -pkeyopt ec_paramgen_curve:P-256 -out server.key
+openssl req -new -key server.key -subj "/CN=localhost" -out server.csr
+printf "subjectAltName=DNS:localhost,IP:127.0.0.1\nextendedKeyUsage=serverAuth\nbasicConstraints=CA:FALSE\n" > server.ext
+openssl x509 -req -in server.csr -CA ca.pem -CAkey ca.key -CAcreateserial -days 36500 \
+    -extfile server.ext -out server.pem
+
+openssl genpkey -algorithm EC -pkeyopt ec_paramgen_curve:P-256 -out client.key
+openssl req -new -key client.key -subj "/CN=desk-a" -out client.csr
+printf "extendedKeyUsage=clientAuth\nbasicConstraints=CA:FALSE\n" > client.ext
+openssl x509 -req -in client.csr -CA ca.pem -CAkey ca.key -CAcreateserial -days 36500 \
+    -extfile client.ext -out client.pem
+
+rm -f ca.key ca.srl server.csr server.ext client.csr client.ext